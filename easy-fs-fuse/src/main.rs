@@ -1,5 +1,8 @@
 use clap::{App, Arg};
-use easy_fs::{BlockDevice, EasyFileSystem};
+use easy_fs::{
+    block_cache_sync_all, try_get_block_cache, BlockCacheManager, BlockDevice, BlockError,
+    CacheStats, DiskInode, EasyFileSystem, MemBlockDevice, BLOCK_CACHE_SIZE,
+};
 use std::fs::{read_dir, File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::sync::Arc;
@@ -81,8 +84,12 @@ fn easy_fs_pack() -> std::io::Result<()> {
         host_file.read_to_end(&mut all_data).unwrap();
         // create a file in easy-fs
         let inode = root_inode.create(app.as_str()).unwrap();
+        // only files with the exec bit can be exec'ed
+        inode.set_mode(0o755);
         // write data to easy-fs
-        inode.write_at(0, all_data.as_slice());
+        inode
+            .write_at(0, all_data.as_slice())
+            .expect("no room left in the image");
     }
     // list apps
     for app in root_inode.ls() {
@@ -153,3 +160,709 @@ fn efs_test() -> std::io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn efs_max_size_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(20000));
+    // enough data blocks for a file of DiskInode::max_size()
+    EasyFileSystem::create(block_device.clone(), 20000, 1);
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("bigfile").unwrap();
+    let max_size = DiskInode::max_size() as usize;
+    // a write crossing the limit is truncated at it
+    let buf = [0x5au8; 3 * BLOCK_SZ];
+    assert_eq!(file.write_at(max_size - BLOCK_SZ, &buf), Some(BLOCK_SZ));
+    // nothing can be written at or beyond the limit
+    assert_eq!(file.write_at(max_size, &buf), Some(0));
+    assert_eq!(file.write_at(max_size + BLOCK_SZ, &buf), Some(0));
+    let mut read_buffer = [0u8; 2 * BLOCK_SZ];
+    assert_eq!(file.read_at(max_size - BLOCK_SZ, &mut read_buffer), BLOCK_SZ);
+    assert!(read_buffer[..BLOCK_SZ].iter().all(|&b| b == 0x5a));
+    // the blocks are still usable after the file is cleared
+    file.clear();
+    assert_eq!(file.write_at(0, &buf), Some(buf.len()));
+}
+
+#[test]
+fn efs_disk_full_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(2000));
+    EasyFileSystem::create(block_device.clone(), 2000, 1);
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let small = root_inode.create("small").unwrap();
+    assert_eq!(small.write_at(0, b"still here"), Some(10));
+    // grow a file block by block, past indirect2, until the disk is full
+    let big = root_inode.create("big").unwrap();
+    let chunk = [0x5au8; 16 * BLOCK_SZ];
+    let mut size = 0;
+    while let Some(written) = big.write_at(size, &chunk) {
+        assert_eq!(written, chunk.len());
+        size += written;
+    }
+    assert!(size > 156 * BLOCK_SZ);
+    // the failed write left the file as it was
+    let mut buffer = [0u8; BLOCK_SZ];
+    assert_eq!(big.read_at(size, &mut buffer), 0);
+    assert_eq!(big.read_at(size - BLOCK_SZ, &mut buffer), BLOCK_SZ);
+    assert!(buffer.iter().all(|&b| b == 0x5a));
+    // a single block may still fit, but then nothing does
+    while big.write_at(size, &buffer[..1]).is_some() {
+        size += 1;
+    }
+    assert_eq!(big.write_at(size, &buffer), None);
+    // other files are untouched and the space comes back
+    let mut small_buffer = [0u8; 10];
+    assert_eq!(small.read_at(0, &mut small_buffer), 10);
+    assert_eq!(&small_buffer, b"still here");
+    big.clear();
+    assert_eq!(big.write_at(0, &chunk), Some(chunk.len()));
+}
+
+#[test]
+fn efs_link_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(4096));
+    EasyFileSystem::create(block_device.clone(), 4096, 1);
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    // "file0" shares its inode block with the root directory, "file7" does not
+    for i in 0..8 {
+        let name = format!("file{}", i);
+        let file = root_inode.create(&name).unwrap();
+        file.write_at(0, name.as_bytes());
+    }
+    for i in [0, 7] {
+        let name = format!("file{}", i);
+        let link_name = format!("link{}", i);
+        assert!(root_inode.linkat(&name, &link_name).is_some());
+        let file = root_inode.find(&name).unwrap();
+        let link = root_inode.find(&link_name).unwrap();
+        assert_eq!(file.nlink(), 2);
+        let mut buffer = [0u8; 16];
+        let len = link.read_at(0, &mut buffer);
+        assert_eq!(&buffer[..len], name.as_bytes());
+        // writes through the link are visible through the original name
+        link.write_at(0, b"linked");
+        let len = file.read_at(0, &mut buffer);
+        assert_eq!(&buffer[..len], b"linked");
+    }
+    assert!(root_inode.linkat("file1", "link0").is_none());
+    assert!(root_inode.linkat("missing", "link_missing").is_none());
+    assert!(root_inode.find("link_missing").is_none());
+}
+
+#[test]
+fn efs_unlink_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(4096));
+    EasyFileSystem::create(block_device.clone(), 4096, 1);
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    for name in ["first", "middle", "last"] {
+        root_inode.create(name).unwrap();
+    }
+    assert!(root_inode.unlink("middle").is_some());
+    assert_eq!(root_inode.ls(), vec!["first", "last"]);
+    assert!(root_inode.find("middle").is_none());
+    assert!(root_inode.unlink("middle").is_none());
+    // the other link keeps the file alive
+    let file = root_inode.find("first").unwrap();
+    file.write_at(0, b"data");
+    assert!(root_inode.linkat("first", "second").is_some());
+    assert!(root_inode.unlink("first").is_some());
+    assert_eq!(file.nlink(), 1);
+    let mut buffer = [0u8; 16];
+    let second = root_inode.find("second").unwrap();
+    assert_eq!(second.read_at(0, &mut buffer), 4);
+    assert_eq!(&buffer[..4], b"data");
+    // a directory spanning several blocks shrinks back, and freed blocks are reused
+    let names: Vec<String> = (0..40).map(|i| format!("file{}", i)).collect();
+    for name in names.iter() {
+        root_inode.create(name).unwrap();
+    }
+    for name in names.iter().rev().step_by(2) {
+        assert!(root_inode.unlink(name).is_some());
+    }
+    for name in names.iter().step_by(2) {
+        assert!(root_inode.unlink(name).is_some());
+    }
+    for name in ["last", "second"] {
+        assert!(root_inode.unlink(name).is_some());
+    }
+    assert!(root_inode.ls().is_empty());
+    for name in names.iter() {
+        root_inode.create(name).unwrap();
+    }
+    assert_eq!(root_inode.ls(), names);
+}
+
+#[test]
+fn efs_unlink_open_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(4096));
+    EasyFileSystem::create(block_device.clone(), 4096, 1);
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("file").unwrap();
+    file.open();
+    let data = [0x5au8; 4 * BLOCK_SZ];
+    assert_eq!(file.write_at(0, &data), Some(data.len()));
+    let used_data_blocks = efs.lock().used_data_blocks();
+    // the name is gone, but the open file keeps its inode and blocks
+    assert!(root_inode.unlink("file").is_some());
+    assert!(root_inode.find("file").is_none());
+    assert_eq!(file.nlink(), 0);
+    assert_eq!(efs.lock().used_inodes(), 2);
+    // only the emptied directory gives its dirent block back
+    assert_eq!(efs.lock().used_data_blocks(), used_data_blocks - 1);
+    // a new file must not take them over
+    let other = root_inode.create("other").unwrap();
+    assert_eq!(other.write_at(0, &[0xa5u8; BLOCK_SZ]), Some(BLOCK_SZ));
+    assert_eq!(file.write_at(data.len(), &data[..BLOCK_SZ]), Some(BLOCK_SZ));
+    let mut buffer = [0u8; 5 * BLOCK_SZ];
+    assert_eq!(file.read_at(0, &mut buffer), buffer.len());
+    assert!(buffer.iter().all(|byte| *byte == 0x5a));
+    // the last close frees what is left
+    file.close();
+    assert!(root_inode.unlink("other").is_some());
+    assert_eq!(efs.lock().used_inodes(), 1);
+    assert_eq!(efs.lock().used_data_blocks(), 0);
+}
+
+#[test]
+fn efs_mode_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(4096));
+    assert_eq!(core::mem::size_of::<DiskInode>(), 128);
+    EasyFileSystem::create(block_device.clone(), 4096, 1);
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    assert!(root_inode.is_dir());
+    assert_eq!(root_inode.mode(), 0o755);
+    assert_eq!(root_inode.inode_id(), 0);
+    let file = root_inode.create("filea").unwrap();
+    assert!(!file.is_dir());
+    assert_eq!(file.mode(), 0o644);
+    assert_eq!(file.inode_id(), 1);
+    file.set_mode(0o444);
+    assert_eq!(root_inode.find("filea").unwrap().mode(), 0o444);
+}
+
+/// Another handle on a RAM disk, which the block caches take for a device
+/// of its own, as if the image had been opened a second time.
+struct DiskHandle(Arc<MemBlockDevice>);
+
+impl BlockDevice for DiskHandle {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        self.0.read_block(block_id, buf);
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.0.write_block(block_id, buf);
+    }
+
+    fn handle_irq(&self) {}
+}
+
+#[test]
+fn efs_checksum_test() {
+    let disk = Arc::new(MemBlockDevice::new(4096));
+    let efs = EasyFileSystem::create_with_checksum(disk.clone(), 4096, 1);
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let filea = root_inode.create("filea").unwrap();
+    filea.write_at(0, b"checksummed data");
+
+    // a second handle on the disk does not share the block caches
+    let block_device: Arc<dyn BlockDevice> = Arc::new(DiskHandle(disk.clone()));
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let mut buffer = [0u8; 32];
+    let len = root_inode.find("filea").unwrap().read_at(0, &mut buffer);
+    assert_eq!(&buffer[..len], b"checksummed data");
+
+    // flip a byte of a block behind the back of the checksums
+    let (clean_block, corrupted_block) = {
+        let efs = efs.lock();
+        (efs.get_data_block_id(100), efs.get_data_block_id(101))
+    };
+    let mut block = [0u8; BLOCK_SZ];
+    disk.read_block(corrupted_block as usize, &mut block);
+    block[7] ^= 0xff;
+    disk.write_block(corrupted_block as usize, &block);
+    assert!(try_get_block_cache(clean_block as usize, block_device.clone()).is_ok());
+    match try_get_block_cache(corrupted_block as usize, block_device.clone()) {
+        Err(BlockError::ChecksumMismatch { block_id, .. }) => {
+            assert_eq!(block_id, corrupted_block as usize)
+        }
+        _ => panic!("corruption of block {} not detected", corrupted_block),
+    }
+}
+
+#[test]
+fn efs_checksum_io_test() {
+    let disk = Arc::new(MemBlockDevice::new(4096));
+    let efs = EasyFileSystem::create_with_checksum(disk.clone(), 4096, 1);
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let data = [0x42u8; 3 * BLOCK_SZ];
+    root_inode.create("file").unwrap().write_at(0, &data);
+    block_cache_sync_all();
+
+    let block_device: Arc<dyn BlockDevice> = Arc::new(DiskHandle(disk.clone()));
+    let efs = EasyFileSystem::open(block_device.clone());
+    let file = EasyFileSystem::root_inode(&efs).find("file").unwrap();
+    let corrupted_block = file.block_ids()[1];
+    let mut block = [0u8; BLOCK_SZ];
+    disk.read_block(corrupted_block as usize, &mut block);
+    block[0] ^= 0xff;
+    disk.write_block(corrupted_block as usize, &block);
+    // the blocks around it are fine, reading or writing it is an error
+    let mut buffer = [0u8; BLOCK_SZ];
+    assert_eq!(file.try_read_at(0, &mut buffer), Ok(BLOCK_SZ));
+    assert_eq!(file.try_read_at(2 * BLOCK_SZ, &mut buffer), Ok(BLOCK_SZ));
+    assert_eq!(buffer, [0x42u8; BLOCK_SZ]);
+    match file.try_read_at(BLOCK_SZ, &mut buffer) {
+        Err(BlockError::ChecksumMismatch { block_id, .. }) => {
+            assert_eq!(block_id, corrupted_block as usize)
+        }
+        other => panic!("read of a corrupted block returned {:?}", other),
+    }
+    assert!(file.try_write_at(BLOCK_SZ + 1, b"x").is_err());
+    assert_eq!(file.try_write_at(2 * BLOCK_SZ, b"x"), Ok(Some(1)));
+}
+
+#[test]
+fn efs_cache_stats_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(BLOCK_CACHE_SIZE + 1));
+    // a manager of its own, the global one is shared by the other tests
+    let mut manager = BlockCacheManager::new();
+    let mut access = |block_id: usize| {
+        manager
+            .get_block_cache(block_id, block_device.clone())
+            .unwrap();
+    };
+    // fill the cache, then hit every cached block once
+    (0..BLOCK_CACHE_SIZE).for_each(&mut access);
+    (0..BLOCK_CACHE_SIZE).for_each(&mut access);
+    // one more block evicts the oldest, which misses when read again
+    access(BLOCK_CACHE_SIZE);
+    access(0);
+    assert_eq!(
+        manager.stats(),
+        CacheStats {
+            hits: BLOCK_CACHE_SIZE,
+            misses: BLOCK_CACHE_SIZE + 2,
+            evictions: 2,
+            prefetches: 0,
+        }
+    );
+}
+
+#[test]
+fn efs_cache_capacity_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(2));
+    let mut manager = BlockCacheManager::with_capacity(1);
+    let mut access = |block_id: usize| {
+        manager
+            .get_block_cache(block_id, block_device.clone())
+            .unwrap();
+    };
+    // reading the same block twice misses once and hits once
+    access(0);
+    access(0);
+    // a single slot is already full
+    access(1);
+    assert_eq!(
+        manager.stats(),
+        CacheStats {
+            hits: 1,
+            misses: 2,
+            evictions: 1,
+            prefetches: 0,
+        }
+    );
+}
+
+#[test]
+fn efs_mem_block_dev_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(4096));
+    EasyFileSystem::create(block_device.clone(), 4096, 1);
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    assert_eq!(efs.lock().used_inodes(), 1);
+    assert_eq!(efs.lock().used_data_blocks(), 0);
+    // one dirent block for the root directory
+    let file = root_inode.create("file").unwrap();
+    assert_eq!(efs.lock().used_inodes(), 2);
+    assert_eq!(efs.lock().used_data_blocks(), 1);
+    // direct blocks, then indirect1, then indirect2
+    let data: Vec<u8> = (0..300 * BLOCK_SZ).map(|i| (i % 251) as u8).collect();
+    for blocks in [10, 100, 300] {
+        let size = blocks * BLOCK_SZ;
+        assert_eq!(file.write_at(0, &data[..size]), Some(size));
+        assert_eq!(
+            efs.lock().used_data_blocks(),
+            1 + DiskInode::total_blocks(size as u32) as usize
+        );
+    }
+    let mut buffer = vec![0u8; data.len()];
+    assert_eq!(file.read_at(0, &mut buffer), data.len());
+    assert_eq!(buffer, data);
+    // everything is on the device, a fresh mount sees the same file
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.find("file").unwrap();
+    assert_eq!(file.read_at(BLOCK_SZ, &mut buffer[..BLOCK_SZ]), BLOCK_SZ);
+    assert_eq!(buffer[..BLOCK_SZ], data[BLOCK_SZ..2 * BLOCK_SZ]);
+    file.clear();
+    assert_eq!(efs.lock().used_data_blocks(), 1);
+    assert!(root_inode.unlink("file").is_some());
+    assert_eq!(efs.lock().used_inodes(), 1);
+    assert_eq!(efs.lock().used_data_blocks(), 0);
+}
+
+#[test]
+fn efs_truncate_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(4096));
+    EasyFileSystem::create(block_device.clone(), 4096, 1);
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("file").unwrap();
+    let data: Vec<u8> = (0..300 * BLOCK_SZ).map(|i| (i % 251) as u8 + 1).collect();
+    assert_eq!(file.write_at(0, &data[..4096]), Some(4096));
+    let block_ids = file.block_ids();
+    assert_eq!(block_ids.len(), 4096 / BLOCK_SZ);
+    // reads stop at the new end and the blocks past it are free again
+    assert!(file.truncate(100).is_some());
+    let mut buffer = vec![0u8; data.len()];
+    assert_eq!(file.read_at(0, &mut buffer), 100);
+    assert_eq!(buffer[..100], data[..100]);
+    assert_eq!(file.read_at(100, &mut buffer), 0);
+    assert_eq!(file.block_ids(), block_ids[..1]);
+    assert!(efs.lock().is_data_allocated(block_ids[0]));
+    for &block_id in block_ids[1..].iter() {
+        assert!(!efs.lock().is_data_allocated(block_id));
+    }
+    assert_eq!(efs.lock().used_data_blocks(), 2);
+    // growing again reads zeros past the old end
+    assert!(file.truncate(1000).is_some());
+    assert_eq!(file.read_at(0, &mut buffer), 1000);
+    assert_eq!(buffer[..100], data[..100]);
+    assert!(buffer[100..1000].iter().all(|&b| b == 0));
+    // index blocks go away with the data blocks they point to
+    assert_eq!(file.write_at(0, &data), Some(data.len()));
+    for blocks in [200, 100, 27, 0] {
+        let size = (blocks * BLOCK_SZ) as u32;
+        assert!(file.truncate(size).is_some());
+        assert_eq!(
+            efs.lock().used_data_blocks(),
+            1 + DiskInode::total_blocks(size) as usize
+        );
+        assert_eq!(file.read_at(0, &mut buffer), size as usize);
+        assert_eq!(buffer[..size as usize], data[..size as usize]);
+    }
+    assert!(root_inode.truncate(0).is_none());
+}
+
+#[test]
+fn efs_path_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(4096));
+    EasyFileSystem::create(block_device.clone(), 4096, 1);
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = Arc::new(EasyFileSystem::root_inode(&efs));
+    assert_eq!(root_inode.path(), "/");
+    assert!(Arc::ptr_eq(&root_inode.parent(), &root_inode));
+    let dir = root_inode.create_dir("dir").unwrap();
+    assert!(dir.is_dir());
+    let sub_dir = dir.create_dir("sub_dir").unwrap();
+    assert_eq!(sub_dir.create("file").unwrap().path(), "/dir/sub_dir/file");
+    // descend two levels by name, then walk back up
+    let file = root_inode
+        .find("dir")
+        .and_then(|dir| dir.find("sub_dir"))
+        .and_then(|sub_dir| sub_dir.find("file"))
+        .unwrap();
+    assert_eq!(file.name(), "file");
+    assert_eq!(file.path(), "/dir/sub_dir/file");
+    let sub_dir = file.parent();
+    assert_eq!(sub_dir.path(), "/dir/sub_dir");
+    assert_eq!(sub_dir.ls(), vec!["file"]);
+    let dir = sub_dir.parent();
+    assert_eq!(dir.path(), "/dir");
+    assert_eq!(dir.parent().path(), "/");
+    assert_eq!(dir.parent().parent().path(), "/");
+    assert_eq!(root_inode.ls(), vec!["dir"]);
+}
+
+/// A RAM disk that remembers which blocks were read from it.
+struct LogBlockDevice {
+    disk: MemBlockDevice,
+    reads: Mutex<Vec<usize>>,
+}
+
+impl LogBlockDevice {
+    /// A copy of `disk`, which no block cache has seen yet.
+    fn copy_of(disk: &MemBlockDevice) -> Self {
+        let image = MemBlockDevice::new(disk.total_blocks());
+        let mut buf = [0u8; BLOCK_SZ];
+        for block_id in 0..disk.total_blocks() {
+            disk.read_block(block_id, &mut buf);
+            image.write_block(block_id, &buf);
+        }
+        Self {
+            disk: image,
+            reads: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn was_read(&self, block_id: u32) -> bool {
+        self.reads.lock().unwrap().contains(&(block_id as usize))
+    }
+}
+
+impl BlockDevice for LogBlockDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        self.reads.lock().unwrap().push(block_id);
+        self.disk.read_block(block_id, buf);
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.disk.write_block(block_id, buf);
+    }
+
+    fn handle_irq(&self) {}
+}
+
+#[test]
+fn efs_read_ahead_test() {
+    const BLOCKS: usize = 32;
+    let disk = Arc::new(MemBlockDevice::new(4096));
+    EasyFileSystem::create(disk.clone(), 4096, 1);
+    let efs = EasyFileSystem::open(disk.clone());
+    let file = EasyFileSystem::root_inode(&efs).create("file").unwrap();
+    // direct blocks and the start of indirect1
+    let data: Vec<u8> = (0..BLOCKS * BLOCK_SZ).map(|i| (i % 251) as u8).collect();
+    assert_eq!(file.write_at(0, &data), Some(data.len()));
+    block_cache_sync_all();
+    let mount = |device: &Arc<LogBlockDevice>| {
+        let efs = EasyFileSystem::open(device.clone());
+        let file = EasyFileSystem::root_inode(&efs).find("file").unwrap();
+        let block_ids = file.block_ids();
+        (file, block_ids)
+    };
+    let mut buffer = [0u8; BLOCK_SZ];
+
+    // reading block by block, each block is loaded before its turn
+    let device = Arc::new(LogBlockDevice::copy_of(&disk));
+    let (file, block_ids) = mount(&device);
+    for i in 0..BLOCKS {
+        if i > 0 {
+            assert!(device.was_read(block_ids[i]), "block {} not read ahead", i);
+        }
+        assert_eq!(file.read_at(i * BLOCK_SZ, &mut buffer), BLOCK_SZ);
+        assert_eq!(buffer, data[i * BLOCK_SZ..(i + 1) * BLOCK_SZ]);
+    }
+    // and read only once
+    let reads = device.reads.lock().unwrap();
+    for &block_id in &block_ids[..BLOCKS] {
+        let count = reads.iter().filter(|&&id| id == block_id as usize).count();
+        assert_eq!(count, 1);
+    }
+    drop(reads);
+
+    // a first read in the middle is not taken as sequential, the next one is
+    let device = Arc::new(LogBlockDevice::copy_of(&disk));
+    let (file, block_ids) = mount(&device);
+    file.read_at(10 * BLOCK_SZ, &mut buffer);
+    assert!(!device.was_read(block_ids[11]));
+    file.read_at(11 * BLOCK_SZ, &mut buffer);
+    assert!(device.was_read(block_ids[12]) && device.was_read(block_ids[13]));
+    assert!(!device.was_read(block_ids[14]));
+}
+
+/// A RAM disk that keeps working after `writes_left` writes, but takes a
+/// copy of itself right then: the disk as it would be if the power went off.
+struct CrashBlockDevice {
+    disk: MemBlockDevice,
+    writes_left: Mutex<usize>,
+    crashed: Mutex<Option<MemBlockDevice>>,
+}
+
+impl CrashBlockDevice {
+    fn new(total_blocks: usize) -> Self {
+        Self {
+            disk: MemBlockDevice::new(total_blocks),
+            writes_left: Mutex::new(usize::MAX),
+            crashed: Mutex::new(None),
+        }
+    }
+
+    fn copy_disk(&self) -> MemBlockDevice {
+        let image = MemBlockDevice::new(self.disk.total_blocks());
+        let mut buf = [0u8; BLOCK_SZ];
+        for block_id in 0..self.disk.total_blocks() {
+            self.disk.read_block(block_id, &mut buf);
+            image.write_block(block_id, &buf);
+        }
+        image
+    }
+
+    /// What was on the disk at the crash, or now if it did not crash.
+    fn image(&self) -> MemBlockDevice {
+        self.crashed
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_else(|| self.copy_disk())
+    }
+}
+
+impl BlockDevice for CrashBlockDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        self.disk.read_block(block_id, buf);
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        let mut writes_left = self.writes_left.lock().unwrap();
+        if *writes_left == 0 {
+            let mut crashed = self.crashed.lock().unwrap();
+            if crashed.is_none() {
+                *crashed = Some(self.copy_disk());
+            }
+        } else {
+            *writes_left -= 1;
+        }
+        self.disk.write_block(block_id, buf);
+    }
+
+    fn handle_irq(&self) {}
+}
+
+#[test]
+fn efs_crash_test() {
+    const TOTAL_BLOCKS: usize = 2048;
+    // direct blocks, indirect1 and the start of indirect2
+    let data: Vec<u8> = (0..160 * BLOCK_SZ).map(|i| (i % 251) as u8).collect();
+    let old_size = 10 * BLOCK_SZ;
+    // crash after every possible number of writes of the second write_at
+    for budget in 0.. {
+        let device = Arc::new(CrashBlockDevice::new(TOTAL_BLOCKS));
+        let block_device: Arc<dyn BlockDevice> = device.clone();
+        EasyFileSystem::create(block_device.clone(), TOTAL_BLOCKS as u32, 1);
+        let efs = EasyFileSystem::open(block_device);
+        let file = EasyFileSystem::root_inode(&efs).create("file").unwrap();
+        assert_eq!(file.write_at(0, &data[..old_size]), Some(old_size));
+        *device.writes_left.lock().unwrap() = budget;
+        assert_eq!(
+            file.write_at(old_size, &data[old_size..]),
+            Some(data.len() - old_size)
+        );
+        let finished = device.crashed.lock().unwrap().is_none();
+
+        let efs = EasyFileSystem::open(Arc::new(device.image()));
+        let file = EasyFileSystem::root_inode(&efs).find("file").unwrap();
+        // the inode only points to allocated blocks, holding the data written
+        for block_id in file.block_ids() {
+            assert!(
+                efs.lock().is_data_allocated(block_id),
+                "block {} is not allocated after {} writes",
+                block_id,
+                budget
+            );
+        }
+        let mut buffer = vec![0u8; data.len()];
+        let size = file.read_at(0, &mut buffer);
+        assert!(size == old_size || size == data.len());
+        assert_eq!(buffer[..size], data[..size]);
+        if finished {
+            assert_eq!(size, data.len());
+            break;
+        }
+    }
+}
+
+/// Taken by the tests counting what is left dirty in the block cache shared
+/// by all tests, and by those writing all of it back.
+static SHARED_CACHE: Mutex<()> = Mutex::new(());
+
+#[test]
+fn efs_sync_all_test() {
+    let _shared_cache = SHARED_CACHE.lock().unwrap();
+    // two fs like the root and /mnt in the kernel, `sync` reaches both
+    let devices: Vec<Arc<CrashBlockDevice>> = (0..2)
+        .map(|_| Arc::new(CrashBlockDevice::new(2048)))
+        .collect();
+    let files: Vec<_> = devices
+        .iter()
+        .map(|device| {
+            let block_device: Arc<dyn BlockDevice> = device.clone();
+            EasyFileSystem::create(block_device.clone(), 2048, 1);
+            let efs = EasyFileSystem::open(block_device);
+            let file = EasyFileSystem::root_inode(&efs).create("file").unwrap();
+            // allocating, so it is on the disk at once
+            assert_eq!(file.write_at(0, b"before"), Some(6));
+            file
+        })
+        .collect();
+    let read_disk = |device: &CrashBlockDevice| {
+        let efs = EasyFileSystem::open(Arc::new(device.image()));
+        let file = EasyFileSystem::root_inode(&efs).find("file").unwrap();
+        let mut buffer = [0u8; 6];
+        assert_eq!(file.read_at(0, &mut buffer), 6);
+        buffer
+    };
+    // an overwrite stays in the block cache
+    for file in files.iter() {
+        assert_eq!(file.write_at(0, b"after!"), Some(6));
+    }
+    for device in devices.iter() {
+        assert_eq!(&read_disk(device), b"before");
+    }
+    block_cache_sync_all();
+    for device in devices.iter() {
+        assert_eq!(&read_disk(device), b"after!");
+    }
+}
+
+#[test]
+fn efs_fsync_test() {
+    const TOTAL_BLOCKS: usize = 2048;
+    const CHUNK: usize = 64;
+    let _shared_cache = SHARED_CACHE.lock().unwrap();
+    let device = Arc::new(CrashBlockDevice::new(TOTAL_BLOCKS));
+    let block_device: Arc<dyn BlockDevice> = device.clone();
+    EasyFileSystem::create(block_device.clone(), TOTAL_BLOCKS as u32, 1);
+    let efs = EasyFileSystem::open(block_device);
+    let file = EasyFileSystem::root_inode(&efs).create("file").unwrap();
+    let data: Vec<u8> = (0..100 * BLOCK_SZ).map(|i| (i % 251) as u8).collect();
+    assert_eq!(file.write_at(0, &data), Some(data.len()));
+    // overwrite the 100 blocks a little at a time, nothing is allocated
+    let data: Vec<u8> = data.iter().map(|b| b + 1).collect();
+    let writes = || usize::MAX - *device.writes_left.lock().unwrap();
+    let chunks = data.len() / CHUNK;
+    *device.writes_left.lock().unwrap() = usize::MAX;
+    for (i, chunk) in data.chunks(CHUNK).enumerate() {
+        assert_eq!(file.write_at(i * CHUNK, chunk), Some(CHUNK));
+    }
+    // syncing after every write_at took at least one write_block each, now
+    // only blocks evicted from the cache are written back
+    assert!(writes() < chunks / 4, "{} writes", writes());
+    file.sync();
+    assert!(writes() < chunks / 4 + 100, "{} writes", writes());
+    let efs = EasyFileSystem::open(Arc::new(device.image()));
+    let file_on_disk = EasyFileSystem::root_inode(&efs).find("file").unwrap();
+    let mut buffer = vec![0u8; data.len()];
+    assert_eq!(file_on_disk.read_at(0, &mut buffer), data.len());
+    assert_eq!(buffer, data);
+    // an append allocates and is written back at once, but only its own
+    // data, index and bitmap blocks and the inode, not the dirty overwrites
+    let data: Vec<u8> = data.iter().map(|b| b + 1).collect();
+    for (i, chunk) in data.chunks(CHUNK).enumerate() {
+        assert_eq!(file.write_at(i * CHUNK, chunk), Some(CHUNK));
+    }
+    let appended = [0x77u8; BLOCK_SZ];
+    let before = writes();
+    assert_eq!(file.write_at(data.len(), &appended), Some(BLOCK_SZ));
+    assert!(writes() - before < 8, "{} writes", writes() - before);
+    let efs = EasyFileSystem::open(Arc::new(device.image()));
+    let file_on_disk = EasyFileSystem::root_inode(&efs).find("file").unwrap();
+    let mut buffer = [0u8; 2 * BLOCK_SZ];
+    assert_eq!(file_on_disk.read_at(data.len(), &mut buffer), BLOCK_SZ);
+    assert_eq!(buffer[..BLOCK_SZ], appended);
+}
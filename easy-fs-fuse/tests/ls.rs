@@ -0,0 +1,35 @@
+//! Counts block cache lookups, so it needs the global block cache to itself.
+//! As a test binary of its own, nothing else runs in its process.
+
+use easy_fs::{cache_stats, BlockDevice, EasyFileSystem, MemBlockDevice, BLOCK_SZ};
+use std::sync::Arc;
+
+#[test]
+fn efs_ls_test() {
+    let block_device: Arc<dyn BlockDevice> = Arc::new(MemBlockDevice::new(4096));
+    EasyFileSystem::create(block_device.clone(), 4096, 1);
+    let efs = EasyFileSystem::open(block_device.clone());
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let names: Vec<String> = (0..200).map(|i| format!("file{}", i)).collect();
+    for name in names.iter() {
+        root_inode.create(name).unwrap();
+    }
+    assert_eq!(root_inode.dirent_count(), names.len());
+    // block cache lookups of `f`
+    let lookups = |f: &dyn Fn()| {
+        let before = cache_stats();
+        f();
+        let after = cache_stats();
+        after.hits + after.misses - before.hits - before.misses
+    };
+    // the root inode block, then each dirent block once
+    let dir_blocks = (names.len() * 32 + BLOCK_SZ - 1) / BLOCK_SZ;
+    assert_eq!(
+        lookups(&|| assert_eq!(root_inode.ls(), names)),
+        1 + dir_blocks
+    );
+    assert_eq!(
+        lookups(&|| assert!(root_inode.find("file199").is_some())),
+        1 + dir_blocks
+    );
+}
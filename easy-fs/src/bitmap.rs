@@ -1,5 +1,6 @@
 use super::{get_block_cache, BlockDevice, BLOCK_SZ};
 use alloc::sync::Arc;
+use core::ops::Range;
 
 type BitmapBlock = [u64; 64];
 
@@ -8,6 +9,8 @@ const BLOCK_BITS: usize = BLOCK_SZ * 8;
 pub struct Bitmap {
     start_block_id: usize,
     blocks: usize,
+    /// number of usable bits, the last block may have more than that
+    bits: usize,
 }
 
 /// Return (block_pos, bits64_pos, inner_pos)
@@ -19,9 +22,16 @@ fn decomposition(mut bit: usize) -> (usize, usize, usize) {
 
 impl Bitmap {
     pub fn new(start_block_id: usize, blocks: usize) -> Self {
+        Self::with_bits(start_block_id, blocks, blocks * BLOCK_BITS)
+    }
+
+    /// Like `new`, but only the first `bits` bits are ever allocated.
+    pub fn with_bits(start_block_id: usize, blocks: usize, bits: usize) -> Self {
+        assert!(bits <= blocks * BLOCK_BITS);
         Self {
             start_block_id,
             blocks,
+            bits,
         }
     }
 
@@ -39,15 +49,22 @@ impl Bitmap {
                     .find(|(_, bits64)| **bits64 != u64::MAX)
                     .map(|(bits64_pos, bits64)| (bits64_pos, bits64.trailing_ones() as usize))
                 {
+                    let pos = block_id * BLOCK_BITS + bits64_pos * 64 + inner_pos as usize;
+                    // this is the lowest free bit, so all usable ones are taken
+                    if pos >= self.bits {
+                        return Err(());
+                    }
                     // modify cache
                     bitmap_block[bits64_pos] |= 1u64 << inner_pos;
-                    Some(block_id * BLOCK_BITS + bits64_pos * 64 + inner_pos as usize)
+                    Ok(Some(pos))
                 } else {
-                    None
+                    Ok(None)
                 }
             });
-            if pos.is_some() {
-                return pos;
+            match pos {
+                Ok(Some(pos)) => return Some(pos),
+                Ok(None) => {}
+                Err(()) => return None,
             }
         }
         None
@@ -63,7 +80,37 @@ impl Bitmap {
             });
     }
 
+    pub fn is_allocated(&self, block_device: &Arc<dyn BlockDevice>, bit: usize) -> bool {
+        let (block_pos, bits64_pos, inner_pos) = decomposition(bit);
+        get_block_cache(block_pos + self.start_block_id, Arc::clone(block_device))
+            .lock()
+            .read(0, |bitmap_block: &BitmapBlock| {
+                bitmap_block[bits64_pos] & (1u64 << inner_pos) > 0
+            })
+    }
+
+    /// Number of bits in use.
+    pub fn allocated(&self, block_device: &Arc<dyn BlockDevice>) -> usize {
+        (0..self.blocks)
+            .map(|block_id| {
+                get_block_cache(block_id + self.start_block_id, Arc::clone(block_device))
+                    .lock()
+                    .read(0, |bitmap_block: &BitmapBlock| {
+                        bitmap_block
+                            .iter()
+                            .map(|bits64| bits64.count_ones() as usize)
+                            .sum::<usize>()
+                    })
+            })
+            .sum()
+    }
+
+    /// The blocks holding this bitmap.
+    pub fn block_ids(&self) -> Range<usize> {
+        self.start_block_id..self.start_block_id + self.blocks
+    }
+
     pub fn maximum(&self) -> usize {
-        self.blocks * BLOCK_BITS
+        self.bits
     }
 }
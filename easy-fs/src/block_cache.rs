@@ -1,4 +1,4 @@
-use super::{BlockDevice, BLOCK_SZ};
+use super::{device_id, update_checksum, verify_block, BlockDevice, BlockError, BLOCK_SZ};
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
 use alloc::vec;
@@ -14,17 +14,18 @@ pub struct BlockCache {
 }
 
 impl BlockCache {
-    /// Load a new BlockCache from disk.
-    pub fn new(block_id: usize, block_device: Arc<dyn BlockDevice>) -> Self {
+    /// Load a new BlockCache from disk, verifying its checksum if the fs has them.
+    pub fn new(block_id: usize, block_device: Arc<dyn BlockDevice>) -> Result<Self, BlockError> {
         // for alignment and move effciency
         let mut cache = vec![0u8; BLOCK_SZ];
         block_device.read_block(block_id, &mut cache);
-        Self {
+        verify_block(block_id, &cache, &block_device)?;
+        Ok(Self {
             cache,
             block_id,
             block_device,
             modified: false,
-        }
+        })
     }
 
     fn addr_of_offset(&self, offset: usize) -> usize {
@@ -64,6 +65,7 @@ impl BlockCache {
         if self.modified {
             self.modified = false;
             self.block_device.write_block(self.block_id, &self.cache);
+            update_checksum(self.block_id, &self.cache, &self.block_device);
         }
     }
 }
@@ -74,48 +76,114 @@ impl Drop for BlockCache {
     }
 }
 
-const BLOCK_CACHE_SIZE: usize = 16;
+/// Default number of blocks kept in memory, tune it to trade memory for disk reads.
+pub const BLOCK_CACHE_SIZE: usize = 64;
+
+/// Counters of `BlockCacheManager::get_block_cache` since start.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    /// misses that had to drop another cached block
+    pub evictions: usize,
+    /// blocks read ahead of a request, see `prefetch`
+    pub prefetches: usize,
+}
 
 pub struct BlockCacheManager {
-    queue: VecDeque<(usize, Arc<Mutex<BlockCache>>)>,
+    /// (device id, block id, cache), several devices may share the manager
+    queue: VecDeque<(usize, usize, Arc<Mutex<BlockCache>>)>,
+    /// most blocks cached at once
+    capacity: usize,
+    stats: CacheStats,
 }
 
 impl BlockCacheManager {
     pub fn new() -> Self {
+        Self::with_capacity(BLOCK_CACHE_SIZE)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0);
         Self {
             queue: VecDeque::new(),
+            capacity,
+            stats: CacheStats::default(),
         }
     }
 
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
     pub fn get_block_cache(
         &mut self,
         block_id: usize,
         block_device: Arc<dyn BlockDevice>,
-    ) -> Arc<Mutex<BlockCache>> {
-        if let Some(pair) = self.queue.iter().find(|pair| pair.0 == block_id) {
-            Arc::clone(&pair.1)
+    ) -> Result<Arc<Mutex<BlockCache>>, BlockError> {
+        let device_id = device_id(&block_device);
+        if let Some(entry) = self
+            .queue
+            .iter()
+            .find(|entry| entry.0 == device_id && entry.1 == block_id)
+        {
+            self.stats.hits += 1;
+            Ok(Arc::clone(&entry.2))
         } else {
-            // substitute
-            if self.queue.len() == BLOCK_CACHE_SIZE {
-                // from front to tail
-                if let Some((idx, _)) = self
-                    .queue
-                    .iter()
-                    .enumerate()
-                    .find(|(_, pair)| Arc::strong_count(&pair.1) == 1)
-                {
-                    self.queue.drain(idx..=idx);
-                } else {
-                    panic!("Run out of BlockCache!");
-                }
+            self.stats.misses += 1;
+            if !self.make_room() {
+                panic!("Run out of BlockCache!");
             }
             // load block into mem and push back
             let block_cache = Arc::new(Mutex::new(BlockCache::new(
                 block_id,
                 Arc::clone(&block_device),
-            )));
-            self.queue.push_back((block_id, Arc::clone(&block_cache)));
-            block_cache
+            )?));
+            self.queue
+                .push_back((device_id, block_id, Arc::clone(&block_cache)));
+            Ok(block_cache)
+        }
+    }
+
+    /// Load `block_id` before it is asked for, unless it is cached already.
+    /// This is only a hint: nothing happens if every cached block is in use,
+    /// and a corrupted block is left for the request to report.
+    pub fn prefetch(&mut self, block_id: usize, block_device: Arc<dyn BlockDevice>) {
+        let device_id = device_id(&block_device);
+        if self
+            .queue
+            .iter()
+            .any(|entry| entry.0 == device_id && entry.1 == block_id)
+            || !self.make_room()
+        {
+            return;
+        }
+        if let Ok(block_cache) = BlockCache::new(block_id, block_device) {
+            self.stats.prefetches += 1;
+            self.queue
+                .push_back((device_id, block_id, Arc::new(Mutex::new(block_cache))));
+        }
+    }
+
+    /// Drop the oldest block not in use if the cache is full.
+    /// Return false if there is no room and none can be dropped.
+    fn make_room(&mut self) -> bool {
+        if self.queue.len() < self.capacity {
+            return true;
+        }
+        // from front to tail
+        match self
+            .queue
+            .iter()
+            .position(|entry| Arc::strong_count(&entry.2) == 1)
+        {
+            Some(idx) => {
+                self.queue.remove(idx);
+                self.stats.evictions += 1;
+                true
+            }
+            None => false,
         }
     }
 }
@@ -125,18 +193,61 @@ lazy_static! {
         Mutex::new(BlockCacheManager::new());
 }
 
-pub fn get_block_cache(
+pub fn try_get_block_cache(
     block_id: usize,
     block_device: Arc<dyn BlockDevice>,
-) -> Arc<Mutex<BlockCache>> {
+) -> Result<Arc<Mutex<BlockCache>>, BlockError> {
     BLOCK_CACHE_MANAGER
         .lock()
         .get_block_cache(block_id, block_device)
 }
 
-pub fn block_cache_sync_all() {
-    let manager = BLOCK_CACHE_MANAGER.lock();
-    for (_, cache) in manager.queue.iter() {
+/// Like `try_get_block_cache`, but a corrupted block is fatal.
+pub fn get_block_cache(
+    block_id: usize,
+    block_device: Arc<dyn BlockDevice>,
+) -> Arc<Mutex<BlockCache>> {
+    try_get_block_cache(block_id, block_device).unwrap_or_else(|err| corrupted(err))
+}
+
+/// Give up on a block the fs can not do without.
+pub fn corrupted(err: BlockError) -> ! {
+    panic!("[easy-fs] corrupted block: {:?}", err)
+}
+
+/// See `BlockCacheManager::prefetch`.
+pub fn prefetch_block_cache(block_id: usize, block_device: Arc<dyn BlockDevice>) {
+    BLOCK_CACHE_MANAGER.lock().prefetch(block_id, block_device)
+}
+
+pub fn cache_stats() -> CacheStats {
+    BLOCK_CACHE_MANAGER.lock().stats()
+}
+
+/// Write back the cached blocks `(device id, block id)` picked by `f`.
+/// They are locked only after the manager is released, since the holder
+/// of a block may be waiting for the manager.
+fn sync_caches(f: impl Fn(usize, usize) -> bool) {
+    let caches: Vec<Arc<Mutex<BlockCache>>> = BLOCK_CACHE_MANAGER
+        .lock()
+        .queue
+        .iter()
+        .filter(|(device_id, block_id, _)| f(*device_id, *block_id))
+        .map(|(_, _, cache)| Arc::clone(cache))
+        .collect();
+    for cache in caches {
         cache.lock().sync();
     }
 }
+
+pub fn block_cache_sync_all() {
+    sync_caches(|_, _| true);
+}
+
+/// Write back the cached blocks of `block_device` listed in `block_ids`.
+pub fn block_cache_sync(block_device: &Arc<dyn BlockDevice>, block_ids: &[usize]) {
+    let device_id = device_id(block_device);
+    sync_caches(|entry_device_id, entry_block_id| {
+        entry_device_id == device_id && block_ids.contains(&entry_block_id)
+    });
+}
@@ -0,0 +1,145 @@
+use super::{BlockDevice, BLOCK_SZ};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use lazy_static::*;
+use spin::Mutex;
+
+const CHECKSUM_SZ: usize = core::mem::size_of::<u32>();
+const CHECKSUMS_PER_BLOCK: usize = BLOCK_SZ / CHECKSUM_SZ;
+
+/// Checksums of the blocks in `covered_start..covered_end`, one CRC32 per
+/// block, stored in the blocks from `start_block` on.
+#[derive(Copy, Clone, Debug)]
+pub struct ChecksumArea {
+    pub start_block: usize,
+    pub covered_start: usize,
+    pub covered_end: usize,
+}
+
+impl ChecksumArea {
+    /// Number of blocks needed to hold checksums of `covered_blocks` blocks.
+    pub fn blocks_needed(covered_blocks: usize) -> usize {
+        (covered_blocks + CHECKSUMS_PER_BLOCK - 1) / CHECKSUMS_PER_BLOCK
+    }
+    fn covers(&self, block_id: usize) -> bool {
+        (self.covered_start..self.covered_end).contains(&block_id)
+    }
+    /// Return (block id, offset) of the checksum of `block_id`.
+    fn slot(&self, block_id: usize) -> (usize, usize) {
+        let index = block_id - self.covered_start;
+        (
+            self.start_block + index / CHECKSUMS_PER_BLOCK,
+            index % CHECKSUMS_PER_BLOCK * CHECKSUM_SZ,
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BlockError {
+    /// The data read from `block_id` does not match its stored checksum.
+    ChecksumMismatch {
+        block_id: usize,
+        expected: u32,
+        found: u32,
+    },
+}
+
+lazy_static! {
+    /// (device id, checksum area) of the devices whose fs has checksums enabled
+    static ref CHECKSUM_AREAS: Mutex<Vec<(usize, ChecksumArea)>> = Mutex::new(Vec::new());
+}
+
+/// Identify a block device by the address of its data.
+pub fn device_id(block_device: &Arc<dyn BlockDevice>) -> usize {
+    Arc::as_ptr(block_device) as *const () as usize
+}
+
+/// Enable or disable checksums of the blocks on `block_device`.
+pub fn set_checksum_area(block_device: &Arc<dyn BlockDevice>, area: Option<ChecksumArea>) {
+    let device_id = device_id(block_device);
+    let mut areas = CHECKSUM_AREAS.lock();
+    areas.retain(|(id, _)| *id != device_id);
+    if let Some(area) = area {
+        areas.push((device_id, area));
+    }
+}
+
+/// Return the checksum area covering `block_id` of `block_device`.
+fn checksum_area(block_device: &Arc<dyn BlockDevice>, block_id: usize) -> Option<ChecksumArea> {
+    let device_id = device_id(block_device);
+    CHECKSUM_AREAS
+        .lock()
+        .iter()
+        .find(|(id, area)| *id == device_id && area.covers(block_id))
+        .map(|(_, area)| *area)
+}
+
+/// CRC32 (IEEE 802.3) of `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Check `data` just read from `block_id` against its stored checksum.
+/// The checksum blocks are accessed directly rather than through the block cache.
+pub fn verify_block(
+    block_id: usize,
+    data: &[u8],
+    block_device: &Arc<dyn BlockDevice>,
+) -> Result<(), BlockError> {
+    let area = match checksum_area(block_device, block_id) {
+        Some(area) => area,
+        None => return Ok(()),
+    };
+    let (checksum_block, offset) = area.slot(block_id);
+    // heap buffer since a device may DMA into it
+    let mut buf = vec![0u8; BLOCK_SZ];
+    block_device.read_block(checksum_block, &mut buf);
+    let mut expected = [0u8; CHECKSUM_SZ];
+    expected.copy_from_slice(&buf[offset..offset + CHECKSUM_SZ]);
+    let expected = u32::from_le_bytes(expected);
+    let found = crc32(data);
+    if expected == found {
+        Ok(())
+    } else {
+        Err(BlockError::ChecksumMismatch {
+            block_id,
+            expected,
+            found,
+        })
+    }
+}
+
+/// Store the checksum of `data` just written to `block_id`.
+pub fn update_checksum(block_id: usize, data: &[u8], block_device: &Arc<dyn BlockDevice>) {
+    if let Some(area) = checksum_area(block_device, block_id) {
+        let (checksum_block, offset) = area.slot(block_id);
+        let mut buf = vec![0u8; BLOCK_SZ];
+        block_device.read_block(checksum_block, &mut buf);
+        buf[offset..offset + CHECKSUM_SZ].copy_from_slice(&crc32(data).to_le_bytes());
+        block_device.write_block(checksum_block, &buf);
+    }
+}
+
+/// Fill `area` with the checksum of a zeroed block, used right after formatting.
+pub fn init_checksum_area(area: &ChecksumArea, block_device: &Arc<dyn BlockDevice>) {
+    let zero_checksum = crc32(&[0u8; BLOCK_SZ]).to_le_bytes();
+    let covered_blocks = area.covered_end - area.covered_start;
+    let mut buf = vec![0u8; BLOCK_SZ];
+    for i in 0..ChecksumArea::blocks_needed(covered_blocks) {
+        let count = (covered_blocks - i * CHECKSUMS_PER_BLOCK).min(CHECKSUMS_PER_BLOCK);
+        buf.iter_mut().for_each(|byte| *byte = 0);
+        for slot in buf.chunks_exact_mut(CHECKSUM_SZ).take(count) {
+            slot.copy_from_slice(&zero_checksum);
+        }
+        block_device.write_block(area.start_block + i, &buf);
+    }
+}
@@ -1,8 +1,9 @@
 use super::{
-    block_cache_sync_all, get_block_cache, Bitmap, BlockDevice, DiskInode, DiskInodeType, Inode,
-    SuperBlock,
+    block_cache_sync_all, get_block_cache, init_checksum_area, set_checksum_area, Bitmap,
+    BlockDevice, ChecksumArea, DiskInode, DiskInodeType, Inode, SuperBlock, FEATURE_CHECKSUM,
 };
 use crate::BLOCK_SZ;
+use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
 use spin::Mutex;
 
@@ -12,6 +13,8 @@ pub struct EasyFileSystem {
     pub data_bitmap: Bitmap,
     inode_area_start_block: u32,
     data_area_start_block: u32,
+    /// Open handles per inode id, see `Inode::open`.
+    open_inodes: BTreeMap<u32, usize>,
 }
 
 type DataBlock = [u8; BLOCK_SZ];
@@ -22,18 +25,47 @@ impl EasyFileSystem {
         total_blocks: u32,
         inode_bitmap_blocks: u32,
     ) -> Arc<Mutex<Self>> {
+        Self::create_with_features(block_device, total_blocks, inode_bitmap_blocks, 0)
+    }
+
+    /// Create an fs whose blocks carry CRC32 checksums, see `FEATURE_CHECKSUM`.
+    pub fn create_with_checksum(
+        block_device: Arc<dyn BlockDevice>,
+        total_blocks: u32,
+        inode_bitmap_blocks: u32,
+    ) -> Arc<Mutex<Self>> {
+        Self::create_with_features(
+            block_device,
+            total_blocks,
+            inode_bitmap_blocks,
+            FEATURE_CHECKSUM,
+        )
+    }
+
+    fn create_with_features(
+        block_device: Arc<dyn BlockDevice>,
+        total_blocks: u32,
+        inode_bitmap_blocks: u32,
+        features: u32,
+    ) -> Arc<Mutex<Self>> {
+        let checksum_blocks = if features & FEATURE_CHECKSUM != 0 {
+            ChecksumArea::blocks_needed(total_blocks as usize - 1) as u32
+        } else {
+            0
+        };
         // calculate block size of areas & create bitmaps
         let inode_bitmap = Bitmap::new(1, inode_bitmap_blocks as usize);
         let inode_num = inode_bitmap.maximum();
         let inode_area_blocks =
             ((inode_num * core::mem::size_of::<DiskInode>() + BLOCK_SZ - 1) / BLOCK_SZ) as u32;
         let inode_total_blocks = inode_bitmap_blocks + inode_area_blocks;
-        let data_total_blocks = total_blocks - 1 - inode_total_blocks;
+        let data_total_blocks = total_blocks - 1 - inode_total_blocks - checksum_blocks;
         let data_bitmap_blocks = (data_total_blocks + 4096) / 4097;
         let data_area_blocks = data_total_blocks - data_bitmap_blocks;
-        let data_bitmap = Bitmap::new(
+        let data_bitmap = Bitmap::with_bits(
             (1 + inode_bitmap_blocks + inode_area_blocks) as usize,
             data_bitmap_blocks as usize,
+            data_area_blocks as usize,
         );
         let mut efs = Self {
             block_device: Arc::clone(&block_device),
@@ -41,7 +73,10 @@ impl EasyFileSystem {
             data_bitmap,
             inode_area_start_block: 1 + inode_bitmap_blocks,
             data_area_start_block: 1 + inode_total_blocks + data_bitmap_blocks,
+            open_inodes: BTreeMap::new(),
         };
+        // blocks are not verified until the checksum area is written
+        set_checksum_area(&block_device, None);
         // clear all blocks
         for i in 0..total_blocks {
             get_block_cache(i as usize, Arc::clone(&block_device))
@@ -53,7 +88,7 @@ impl EasyFileSystem {
                 });
         }
         // initialize SuperBlock
-        get_block_cache(0, Arc::clone(&block_device)).lock().modify(
+        let checksum_area = get_block_cache(0, Arc::clone(&block_device)).lock().modify(
             0,
             |super_block: &mut SuperBlock| {
                 super_block.initialize(
@@ -62,9 +97,18 @@ impl EasyFileSystem {
                     inode_area_blocks,
                     data_bitmap_blocks,
                     data_area_blocks,
+                    features,
+                    checksum_blocks,
                 );
+                super_block.checksum_area()
             },
         );
+        if let Some(area) = checksum_area {
+            // every block is zeroed on disk now
+            block_cache_sync_all();
+            init_checksum_area(&area, &block_device);
+            set_checksum_area(&block_device, Some(area));
+        }
         // write back immediately
         // create a inode for root node "/"
         assert_eq!(efs.alloc_inode(), 0);
@@ -84,17 +128,20 @@ impl EasyFileSystem {
             .lock()
             .read(0, |super_block: &SuperBlock| {
                 assert!(super_block.is_valid(), "Error loading EFS!");
+                set_checksum_area(&block_device, super_block.checksum_area());
                 let inode_total_blocks =
                     super_block.inode_bitmap_blocks + super_block.inode_area_blocks;
                 let efs = Self {
                     block_device,
                     inode_bitmap: Bitmap::new(1, super_block.inode_bitmap_blocks as usize),
-                    data_bitmap: Bitmap::new(
+                    data_bitmap: Bitmap::with_bits(
                         (1 + inode_total_blocks) as usize,
                         super_block.data_bitmap_blocks as usize,
+                        super_block.data_area_blocks as usize,
                     ),
                     inode_area_start_block: 1 + super_block.inode_bitmap_blocks,
                     data_area_start_block: 1 + inode_total_blocks + super_block.data_bitmap_blocks,
+                    open_inodes: BTreeMap::new(),
                 };
                 Arc::new(Mutex::new(efs))
             })
@@ -118,6 +165,54 @@ impl EasyFileSystem {
         )
     }
 
+    /// Inverse of `get_disk_inode_pos`.
+    pub fn get_inode_id(&self, block_id: u32, block_offset: usize) -> u32 {
+        let inode_size = core::mem::size_of::<DiskInode>();
+        let inodes_per_block = (BLOCK_SZ / inode_size) as u32;
+        (block_id - self.inode_area_start_block) * inodes_per_block
+            + (block_offset / inode_size) as u32
+    }
+
+    pub fn open_inode(&mut self, inode_id: u32) {
+        *self.open_inodes.entry(inode_id).or_insert(0) += 1;
+    }
+
+    /// Drop a handle of `open_inode`, return whether it was the last one.
+    pub fn close_inode(&mut self, inode_id: u32) -> bool {
+        let count = self.open_inodes.get_mut(&inode_id).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            self.open_inodes.remove(&inode_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn is_inode_open(&self, inode_id: u32) -> bool {
+        self.open_inodes.contains_key(&inode_id)
+    }
+
+    /// Number of inodes in use, the root directory included.
+    pub fn used_inodes(&self) -> usize {
+        self.inode_bitmap.allocated(&self.block_device)
+    }
+
+    /// Number of data blocks in use, indirect blocks included.
+    pub fn used_data_blocks(&self) -> usize {
+        self.data_bitmap.allocated(&self.block_device)
+    }
+
+    /// Whether `block_id` is in the data area and marked in use.
+    pub fn is_data_allocated(&self, block_id: u32) -> bool {
+        block_id >= self.data_area_start_block
+            && ((block_id - self.data_area_start_block) as usize) < self.data_bitmap.maximum()
+            && self.data_bitmap.is_allocated(
+                &self.block_device,
+                (block_id - self.data_area_start_block) as usize,
+            )
+    }
+
     pub fn get_data_block_id(&self, data_block_id: u32) -> u32 {
         self.data_area_start_block + data_block_id
     }
@@ -126,9 +221,16 @@ impl EasyFileSystem {
         self.inode_bitmap.alloc(&self.block_device).unwrap() as u32
     }
 
-    /// Return a block ID not ID in the data area.
-    pub fn alloc_data(&mut self) -> u32 {
-        self.data_bitmap.alloc(&self.block_device).unwrap() as u32 + self.data_area_start_block
+    pub fn dealloc_inode(&mut self, inode_id: u32) {
+        self.inode_bitmap
+            .dealloc(&self.block_device, inode_id as usize)
+    }
+
+    /// Return a block ID not ID in the data area, `None` when the data area is full.
+    pub fn alloc_data(&mut self) -> Option<u32> {
+        self.data_bitmap
+            .alloc(&self.block_device)
+            .map(|bit| bit as u32 + self.data_area_start_block)
     }
 
     pub fn dealloc_data(&mut self, block_id: u32) {
@@ -1,17 +1,23 @@
-use super::{get_block_cache, BlockDevice, BLOCK_SZ};
+use super::{
+    corrupted, get_block_cache, try_get_block_cache, BlockDevice, BlockError, ChecksumArea,
+    BLOCK_SZ,
+};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
-use core::fmt::{Debug, Formatter, Result};
+use core::fmt::{self, Debug, Formatter};
 
 const EFS_MAGIC: u32 = 0x3b800001;
-const INODE_DIRECT_COUNT: usize = 28;
+/// Blocks after the super block carry CRC32 checksums.
+pub const FEATURE_CHECKSUM: u32 = 1;
+const INODE_DIRECT_COUNT: usize = 27;
 const NAME_LENGTH_LIMIT: usize = 27;
 const INODE_INDIRECT1_COUNT: usize = BLOCK_SZ / 4;
 const INODE_INDIRECT2_COUNT: usize = INODE_INDIRECT1_COUNT * INODE_INDIRECT1_COUNT;
 const DIRECT_BOUND: usize = INODE_DIRECT_COUNT;
 const INDIRECT1_BOUND: usize = DIRECT_BOUND + INODE_INDIRECT1_COUNT;
-#[allow(unused)]
 const INDIRECT2_BOUND: usize = INDIRECT1_BOUND + INODE_INDIRECT2_COUNT;
+const DEFAULT_FILE_MODE: u16 = 0o644;
+const DEFAULT_DIR_MODE: u16 = 0o755;
 
 #[repr(C)]
 pub struct SuperBlock {
@@ -21,16 +27,22 @@ pub struct SuperBlock {
     pub inode_area_blocks: u32,
     pub data_bitmap_blocks: u32,
     pub data_area_blocks: u32,
+    /// zero in images made before features existed
+    pub features: u32,
+    /// placed at the end of the device when `FEATURE_CHECKSUM` is set
+    pub checksum_blocks: u32,
 }
 
 impl Debug for SuperBlock {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("SuperBlock")
             .field("total_blocks", &self.total_blocks)
             .field("inode_bitmap_blocks", &self.inode_bitmap_blocks)
             .field("inode_area_blocks", &self.inode_area_blocks)
             .field("data_bitmap_blocks", &self.data_bitmap_blocks)
             .field("data_area_blocks", &self.data_area_blocks)
+            .field("features", &self.features)
+            .field("checksum_blocks", &self.checksum_blocks)
             .finish()
     }
 }
@@ -43,6 +55,8 @@ impl SuperBlock {
         inode_area_blocks: u32,
         data_bitmap_blocks: u32,
         data_area_blocks: u32,
+        features: u32,
+        checksum_blocks: u32,
     ) {
         *self = Self {
             magic: EFS_MAGIC,
@@ -51,11 +65,25 @@ impl SuperBlock {
             inode_area_blocks,
             data_bitmap_blocks,
             data_area_blocks,
+            features,
+            checksum_blocks,
         }
     }
     pub fn is_valid(&self) -> bool {
         self.magic == EFS_MAGIC
     }
+    /// Checksums cover every block between the super block and the checksum area.
+    pub fn checksum_area(&self) -> Option<ChecksumArea> {
+        if self.features & FEATURE_CHECKSUM == 0 {
+            return None;
+        }
+        let start_block = (self.total_blocks - self.checksum_blocks) as usize;
+        Some(ChecksumArea {
+            start_block,
+            covered_start: 1,
+            covered_end: start_block,
+        })
+    }
 }
 
 #[derive(PartialEq)]
@@ -70,19 +98,28 @@ type DataBlock = [u8; BLOCK_SZ];
 #[repr(C)]
 pub struct DiskInode {
     pub size: u32,
+    /// number of dirents pointing to this inode
+    pub nlink: u32,
     pub direct: [u32; INODE_DIRECT_COUNT],
     pub indirect1: u32,
     pub indirect2: u32,
     type_: DiskInodeType,
+    /// permission bits, e.g. 0o644
+    pub mode: u16,
 }
 
 impl DiskInode {
     /// indirect1 and indirect2 block are allocated only when they are needed.
     pub fn initialize(&mut self, type_: DiskInodeType) {
         self.size = 0;
+        self.nlink = 1;
         self.direct.iter_mut().for_each(|v| *v = 0);
         self.indirect1 = 0;
         self.indirect2 = 0;
+        self.mode = match type_ {
+            DiskInodeType::File => DEFAULT_FILE_MODE,
+            DiskInodeType::Directory => DEFAULT_DIR_MODE,
+        };
         self.type_ = type_;
     }
     pub fn is_dir(&self) -> bool {
@@ -92,6 +129,10 @@ impl DiskInode {
     pub fn is_file(&self) -> bool {
         self.type_ == DiskInodeType::File
     }
+    /// Return the largest file size addressable by direct, indirect1 and indirect2 blocks.
+    pub const fn max_size() -> u32 {
+        (INDIRECT2_BOUND * BLOCK_SZ) as u32
+    }
     /// Return block number correspond to size.
     pub fn data_blocks(&self) -> u32 {
         Self::_data_blocks(self.size)
@@ -121,28 +162,66 @@ impl DiskInode {
         Self::total_blocks(new_size) - Self::total_blocks(self.size)
     }
     pub fn get_block_id(&self, inner_id: u32, block_device: &Arc<dyn BlockDevice>) -> u32 {
+        self.try_get_block_id(inner_id, block_device)
+            .unwrap_or_else(|err| corrupted(err))
+    }
+    /// Like `get_block_id`, but fail if an index block is corrupted.
+    pub fn try_get_block_id(
+        &self,
+        inner_id: u32,
+        block_device: &Arc<dyn BlockDevice>,
+    ) -> Result<u32, BlockError> {
         let inner_id = inner_id as usize;
         if inner_id < INODE_DIRECT_COUNT {
-            self.direct[inner_id]
+            Ok(self.direct[inner_id])
         } else if inner_id < INDIRECT1_BOUND {
-            get_block_cache(self.indirect1 as usize, Arc::clone(block_device))
-                .lock()
-                .read(0, |indirect_block: &IndirectBlock| {
-                    indirect_block[inner_id - INODE_DIRECT_COUNT]
-                })
+            Ok(
+                try_get_block_cache(self.indirect1 as usize, Arc::clone(block_device))?
+                    .lock()
+                    .read(0, |indirect_block: &IndirectBlock| {
+                        indirect_block[inner_id - INODE_DIRECT_COUNT]
+                    }),
+            )
         } else {
             let last = inner_id - INDIRECT1_BOUND;
-            let indirect1 = get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
+            let indirect1 = try_get_block_cache(self.indirect2 as usize, Arc::clone(block_device))?
                 .lock()
                 .read(0, |indirect2: &IndirectBlock| {
                     indirect2[last / INODE_INDIRECT1_COUNT]
                 });
-            get_block_cache(indirect1 as usize, Arc::clone(block_device))
+            Ok(
+                try_get_block_cache(indirect1 as usize, Arc::clone(block_device))?
+                    .lock()
+                    .read(0, |indirect1: &IndirectBlock| {
+                        indirect1[last % INODE_INDIRECT1_COUNT]
+                    }),
+            )
+        }
+    }
+    /// Every block this inode points to, the index blocks included.
+    pub fn block_ids(&self, block_device: &Arc<dyn BlockDevice>) -> Vec<u32> {
+        self.block_ids_from(0, block_device)
+    }
+    /// Like `block_ids`, but only the data blocks from byte `start` on.
+    pub fn block_ids_from(&self, start: usize, block_device: &Arc<dyn BlockDevice>) -> Vec<u32> {
+        let data_blocks = self.data_blocks() as usize;
+        let mut v: Vec<u32> = ((start / BLOCK_SZ).min(data_blocks) as u32..data_blocks as u32)
+            .map(|inner_id| self.get_block_id(inner_id, block_device))
+            .collect();
+        if data_blocks > INODE_DIRECT_COUNT {
+            v.push(self.indirect1);
+        }
+        if data_blocks > INDIRECT1_BOUND {
+            v.push(self.indirect2);
+            let indirect1_count =
+                (data_blocks - INDIRECT1_BOUND + INODE_INDIRECT1_COUNT - 1) / INODE_INDIRECT1_COUNT;
+            get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
                 .lock()
-                .read(0, |indirect1: &IndirectBlock| {
-                    indirect1[last % INODE_INDIRECT1_COUNT]
-                })
+                .read(0, |indirect2: &IndirectBlock| {
+                    v.extend_from_slice(&indirect2[..indirect1_count])
+                });
         }
+        v
     }
     pub fn increase_size(
         &mut self,
@@ -150,6 +229,7 @@ impl DiskInode {
         new_blocks: Vec<u32>,
         block_device: &Arc<dyn BlockDevice>,
     ) {
+        assert!(new_size <= Self::max_size(), "file size exceeds DiskInode::max_size()");
         let mut current_blocks = self.data_blocks();
         self.size = new_size;
         let mut total_blocks = self.data_blocks();
@@ -217,6 +297,46 @@ impl DiskInode {
             });
     }
 
+    /// Shrink to `new_size` and return blocks that should be deallocated,
+    /// including the indirect blocks no longer needed.
+    pub fn decrease_size(
+        &mut self,
+        new_size: u32,
+        block_device: &Arc<dyn BlockDevice>,
+    ) -> Vec<u32> {
+        assert!(new_size <= self.size);
+        let old_blocks = self.data_blocks() as usize;
+        let new_blocks = Self::_data_blocks(new_size) as usize;
+        let mut v: Vec<u32> = (new_blocks..old_blocks)
+            .map(|inner_id| self.get_block_id(inner_id as u32, block_device))
+            .collect();
+        // low-level indirect1 and indirect2
+        if old_blocks > INDIRECT1_BOUND {
+            let indirect1_count = |blocks: usize| {
+                (blocks.saturating_sub(INDIRECT1_BOUND) + INODE_INDIRECT1_COUNT - 1)
+                    / INODE_INDIRECT1_COUNT
+            };
+            get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |indirect2: &IndirectBlock| {
+                    v.extend_from_slice(
+                        &indirect2[indirect1_count(new_blocks)..indirect1_count(old_blocks)],
+                    );
+                });
+            if new_blocks <= INDIRECT1_BOUND {
+                v.push(self.indirect2);
+                self.indirect2 = 0;
+            }
+        }
+        // indirect1
+        if old_blocks > INODE_DIRECT_COUNT && new_blocks <= INODE_DIRECT_COUNT {
+            v.push(self.indirect1);
+            self.indirect1 = 0;
+        }
+        self.size = new_size;
+        v
+    }
+
     /// Clear size to zero and return blocks that should be deallocated.
     ///
     /// We will clear the block contents to zero later.
@@ -297,10 +417,20 @@ impl DiskInode {
         buf: &mut [u8],
         block_device: &Arc<dyn BlockDevice>,
     ) -> usize {
+        self.try_read_at(offset, buf, block_device)
+            .unwrap_or_else(|err| corrupted(err))
+    }
+    /// Like `read_at`, but fail if a block on the way is corrupted.
+    pub fn try_read_at(
+        &self,
+        offset: usize,
+        buf: &mut [u8],
+        block_device: &Arc<dyn BlockDevice>,
+    ) -> Result<usize, BlockError> {
         let mut start = offset;
         let end = (offset + buf.len()).min(self.size as usize);
         if start >= end {
-            return 0;
+            return Ok(0);
         }
         let mut start_block = start / BLOCK_SZ;
         let mut read_size = 0usize;
@@ -311,10 +441,10 @@ impl DiskInode {
             // read and update read size
             let block_read_size = end_current_block - start;
             let dst = &mut buf[read_size..read_size + block_read_size];
-            get_block_cache(
-                self.get_block_id(start_block as u32, block_device) as usize,
+            try_get_block_cache(
+                self.try_get_block_id(start_block as u32, block_device)? as usize,
                 Arc::clone(block_device),
-            )
+            )?
             .lock()
             .read(0, |data_block: &DataBlock| {
                 let src = &data_block[start % BLOCK_SZ..start % BLOCK_SZ + block_read_size];
@@ -328,7 +458,7 @@ impl DiskInode {
             start_block += 1;
             start = end_current_block;
         }
-        read_size
+        Ok(read_size)
     }
     /// File size must be adjusted before.
     pub fn write_at(
@@ -337,6 +467,17 @@ impl DiskInode {
         buf: &[u8],
         block_device: &Arc<dyn BlockDevice>,
     ) -> usize {
+        self.try_write_at(offset, buf, block_device)
+            .unwrap_or_else(|err| corrupted(err))
+    }
+    /// Like `write_at`, but fail if a block on the way is corrupted. The
+    /// blocks before it are written already.
+    pub fn try_write_at(
+        &mut self,
+        offset: usize,
+        buf: &[u8],
+        block_device: &Arc<dyn BlockDevice>,
+    ) -> Result<usize, BlockError> {
         let mut start = offset;
         let end = (offset + buf.len()).min(self.size as usize);
         assert!(start <= end);
@@ -348,10 +489,10 @@ impl DiskInode {
             end_current_block = end_current_block.min(end);
             // write and update write size
             let block_write_size = end_current_block - start;
-            get_block_cache(
-                self.get_block_id(start_block as u32, block_device) as usize,
+            try_get_block_cache(
+                self.try_get_block_id(start_block as u32, block_device)? as usize,
                 Arc::clone(block_device),
-            )
+            )?
             .lock()
             .modify(0, |data_block: &mut DataBlock| {
                 let src = &buf[write_size..write_size + block_write_size];
@@ -366,7 +507,7 @@ impl DiskInode {
             start_block += 1;
             start = end_current_block;
         }
-        write_size
+        Ok(write_size)
     }
 }
 
@@ -5,14 +5,26 @@ extern crate alloc;
 mod bitmap;
 mod block_cache;
 mod block_dev;
+mod checksum;
 mod efs;
 mod layout;
+#[cfg(feature = "mem_block_dev")]
+mod mem_block_dev;
 mod vfs;
 
 pub const BLOCK_SZ: usize = 512;
 use bitmap::Bitmap;
-use block_cache::{block_cache_sync_all, get_block_cache};
+use block_cache::{block_cache_sync, corrupted, get_block_cache, prefetch_block_cache};
+pub use block_cache::{
+    block_cache_sync_all, cache_stats, try_get_block_cache, BlockCacheManager, CacheStats,
+    BLOCK_CACHE_SIZE,
+};
 pub use block_dev::BlockDevice;
+use checksum::*;
+pub use checksum::BlockError;
 pub use efs::EasyFileSystem;
 use layout::*;
+pub use layout::DiskInode;
+#[cfg(feature = "mem_block_dev")]
+pub use mem_block_dev::MemBlockDevice;
 pub use vfs::Inode;
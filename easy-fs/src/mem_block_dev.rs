@@ -0,0 +1,30 @@
+use super::{BlockDevice, BLOCK_SZ};
+use alloc::vec;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+/// A RAM disk, so that easy-fs can be tested on the host without an image file.
+pub struct MemBlockDevice(Mutex<Vec<[u8; BLOCK_SZ]>>);
+
+impl MemBlockDevice {
+    /// A zeroed disk of `total_blocks` blocks.
+    pub fn new(total_blocks: usize) -> Self {
+        Self(Mutex::new(vec![[0u8; BLOCK_SZ]; total_blocks]))
+    }
+
+    pub fn total_blocks(&self) -> usize {
+        self.0.lock().len()
+    }
+}
+
+impl BlockDevice for MemBlockDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        buf.copy_from_slice(&self.0.lock()[block_id]);
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.0.lock()[block_id].copy_from_slice(buf);
+    }
+
+    fn handle_irq(&self) {}
+}
@@ -1,17 +1,31 @@
 use super::{
-    block_cache_sync_all, get_block_cache, BlockDevice, DirEntry, DiskInode, DiskInodeType,
-    EasyFileSystem, DIRENT_SZ,
+    block_cache_sync, block_cache_sync_all, corrupted, get_block_cache, prefetch_block_cache,
+    try_get_block_cache, BlockDevice, BlockError, DirEntry, DiskInode, DiskInodeType,
+    EasyFileSystem, BLOCK_SZ, DIRENT_SZ,
 };
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU32, Ordering};
 use spin::{Mutex, MutexGuard};
 
+/// Blocks loaded ahead of a sequential `read_at`.
+const READ_AHEAD_BLOCKS: u32 = 2;
+/// `Inode::last_block_read` before the first read.
+const NO_BLOCK: u32 = u32::MAX;
+
+#[derive(Clone)]
 pub struct Inode {
     block_id: usize,
     block_offset: usize,
     fs: Arc<Mutex<EasyFileSystem>>,
     block_device: Arc<dyn BlockDevice>,
+    /// The directory this inode was found in, `None` for the root.
+    parent: Option<Arc<Inode>>,
+    /// The name this inode was found by, empty for the root.
+    name: String,
+    /// Index in the file of the block the last `read_at` ended in.
+    last_block_read: Arc<AtomicU32>,
 }
 
 impl Inode {
@@ -27,9 +41,27 @@ impl Inode {
             block_offset,
             fs,
             block_device,
+            parent: None,
+            name: String::new(),
+            last_block_read: Arc::new(AtomicU32::new(NO_BLOCK)),
         }
     }
 
+    /// The inode `inode_id` found as `name` in this directory.
+    fn child(&self, inode_id: u32, name: &str, fs: &EasyFileSystem) -> Arc<Inode> {
+        let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
+        Arc::new(Self {
+            parent: Some(Arc::new(self.clone())),
+            name: String::from(name),
+            ..Self::new(
+                block_id,
+                block_offset,
+                self.fs.clone(),
+                self.block_device.clone(),
+            )
+        })
+    }
+
     fn read_disk_inode<V>(&self, f: impl FnOnce(&DiskInode) -> V) -> V {
         get_block_cache(self.block_id, Arc::clone(&self.block_device))
             .lock()
@@ -42,18 +74,100 @@ impl Inode {
             .modify(self.block_offset, f)
     }
 
+    /// Like `modify_disk_inode`, then write back the blocks `f` may have
+    /// changed for a write from byte `start` on: the data blocks from there
+    /// or the old end of the file, whichever comes first, the index blocks
+    /// and `other_blocks`, such as the bitmaps. The block holding this inode
+    /// stays locked and goes last, after the blocks it points to, see
+    /// `increase_size`.
+    fn modify_disk_inode_synced<V>(
+        &self,
+        start: usize,
+        other_blocks: &[usize],
+        f: impl FnOnce(&mut DiskInode) -> V,
+    ) -> V {
+        let cache = get_block_cache(self.block_id, Arc::clone(&self.block_device));
+        let mut guard = cache.lock();
+        let start = start.min(guard.read(self.block_offset, |disk_inode: &DiskInode| {
+            disk_inode.size as usize
+        }));
+        let v = guard.modify(self.block_offset, f);
+        let mut block_ids: Vec<usize> = guard
+            .read(self.block_offset, |disk_inode: &DiskInode| {
+                disk_inode.block_ids_from(start, &self.block_device)
+            })
+            .into_iter()
+            .map(|block_id| block_id as usize)
+            .collect();
+        block_ids.extend_from_slice(other_blocks);
+        // locked by us already, a new inode may live in the same block
+        block_ids.retain(|block_id| *block_id != self.block_id);
+        // only the modified ones are written
+        block_cache_sync(&self.block_device, &block_ids);
+        guard.sync();
+        v
+    }
+
+    /// Modify the disk inodes of `self` and `other` together. Locking the same
+    /// block cache twice would spin forever, so when both inodes live in one
+    /// block its guard is shared; otherwise both caches are locked.
+    fn modify_disk_inode_pair<V>(
+        &self,
+        other: &Inode,
+        f: impl FnOnce(&mut DiskInode, &mut DiskInode) -> V,
+    ) -> V {
+        assert!(
+            self.block_id != other.block_id || self.block_offset != other.block_offset,
+            "can not borrow a disk inode twice"
+        );
+        let cache = get_block_cache(self.block_id, Arc::clone(&self.block_device));
+        let mut guard = cache.lock();
+        if self.block_id == other.block_id {
+            let disk_inode = guard.get_mut::<DiskInode>(self.block_offset) as *mut DiskInode;
+            let other_disk_inode = guard.get_mut::<DiskInode>(other.block_offset) as *mut DiskInode;
+            // the two inodes are disjoint parts of the block
+            unsafe { f(&mut *disk_inode, &mut *other_disk_inode) }
+        } else {
+            let other_cache = get_block_cache(other.block_id, Arc::clone(&self.block_device));
+            let mut other_guard = other_cache.lock();
+            f(
+                guard.get_mut(self.block_offset),
+                other_guard.get_mut(other.block_offset),
+            )
+        }
+    }
+
     fn find_inode_id(&self, name: &str, disk_inode: &DiskInode) -> Option<u32> {
+        self.find_dirent(name, disk_inode)
+            .map(|(inode_id, _)| inode_id)
+    }
+
+    /// Return the inode id of `name` and the index of its dirent.
+    fn find_dirent(&self, name: &str, disk_inode: &DiskInode) -> Option<(u32, usize)> {
+        self.find_map_dirent(disk_inode, |i, dirent| {
+            (dirent.name() == name).then(|| (dirent.inode_number() as u32, i))
+        })
+    }
+
+    /// Call `f` with the index of each dirent and the dirent itself until it
+    /// returns `Some`. The directory is read a block at a time, so that the
+    /// block cache is looked up once per block instead of once per dirent.
+    fn find_map_dirent<V>(
+        &self,
+        disk_inode: &DiskInode,
+        mut f: impl FnMut(usize, &DirEntry) -> Option<V>,
+    ) -> Option<V> {
         // assert it is a directory
         assert!(disk_inode.is_dir());
-        let file_count = (disk_inode.size as usize) / DIRENT_SZ;
+        let mut block = [0u8; BLOCK_SZ];
         let mut dirent = DirEntry::empty();
-        for i in 0..file_count {
-            assert_eq!(
-                disk_inode.read_at(DIRENT_SZ * i, dirent.as_bytes_mut(), &self.block_device,),
-                DIRENT_SZ,
-            );
-            if dirent.name() == name {
-                return Some(dirent.inode_number() as u32);
+        for offset in (0..disk_inode.size as usize).step_by(BLOCK_SZ) {
+            let len = disk_inode.read_at(offset, &mut block, &self.block_device);
+            for (i, bytes) in block[..len].chunks_exact(DIRENT_SZ).enumerate() {
+                dirent.as_bytes_mut().copy_from_slice(bytes);
+                if let Some(v) = f(offset / DIRENT_SZ + i, &dirent) {
+                    return Some(v);
+                }
             }
         }
         None
@@ -62,44 +176,93 @@ impl Inode {
     pub fn find(&self, name: &str) -> Option<Arc<Inode>> {
         let fs = self.fs.lock();
         self.read_disk_inode(|disk_inode| {
-            self.find_inode_id(name, disk_inode).map(|inode_id| {
-                let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
-                Arc::new(Self::new(
-                    block_id,
-                    block_offset,
-                    self.fs.clone(),
-                    self.block_device.clone(),
-                ))
-            })
+            self.find_inode_id(name, disk_inode)
+                .map(|inode_id| self.child(inode_id, name, &fs))
+        })
+    }
+
+    /// The name this inode was found by, empty for the root.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The directory this inode was found in, the root is its own parent.
+    pub fn parent(self: &Arc<Self>) -> Arc<Inode> {
+        self.parent.clone().unwrap_or_else(|| self.clone())
+    }
+
+    /// The absolute path of this inode inside its fs, built from the names
+    /// cached on the way down instead of searching each parent directory.
+    pub fn path(&self) -> String {
+        let mut names = Vec::new();
+        let mut inode = self;
+        while let Some(parent) = inode.parent.as_ref() {
+            names.push(inode.name.as_str());
+            inode = parent;
+        }
+        if names.is_empty() {
+            return String::from("/");
+        }
+        names.iter().rev().fold(String::new(), |mut path, name| {
+            path.push('/');
+            path.push_str(name);
+            path
         })
     }
 
+    /// Return `None` and leave `disk_inode` as it was when the disk is full.
+    ///
+    /// The new blocks and their bitmap bits only become reachable through
+    /// the new size, so callers grow the inode in `modify_disk_inode_synced`:
+    /// after a crash the inode on disk may miss some of its new blocks, which
+    /// then leak, but never points to a block that is free or not written.
+    #[must_use]
     fn increase_size(
         &self,
         new_size: u32,
         disk_inode: &mut DiskInode,
         fs: &mut MutexGuard<EasyFileSystem>,
-    ) {
+    ) -> Option<()> {
         if new_size < disk_inode.size {
-            return;
+            return Some(());
         }
         let blocks_needed = disk_inode.blocks_num_needed(new_size);
         let mut v: Vec<u32> = Vec::new();
         for _ in 0..blocks_needed {
-            v.push(fs.alloc_data());
+            match fs.alloc_data() {
+                Some(block_id) => v.push(block_id),
+                None => {
+                    v.into_iter().for_each(|block_id| fs.dealloc_data(block_id));
+                    return None;
+                }
+            }
         }
         disk_inode.increase_size(new_size, v, &self.block_device);
+        Some(())
     }
 
     pub fn create(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::File)
+    }
+
+    /// Create an empty directory `name` in this directory.
+    pub fn create_dir(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::Directory)
+    }
+
+    fn create_inode(&self, name: &str, type_: DiskInodeType) -> Option<Arc<Inode>> {
         let mut fs = self.fs.lock();
-        let op = |root_inode: &mut DiskInode| {
+        let op = |root_inode: &DiskInode| {
             // assert it is a directory
             assert!(root_inode.is_dir());
             // has the file been created?
-            self.find_inode_id(name, root_inode)
+            (
+                self.find_inode_id(name, root_inode),
+                root_inode.size as usize,
+            )
         };
-        if self.modify_disk_inode(op).is_some() {
+        let (inode_id, dir_size) = self.read_disk_inode(op);
+        if inode_id.is_some() {
             return None;
         }
         // create a new file
@@ -110,14 +273,18 @@ impl Inode {
         get_block_cache(new_inode_block_id as usize, Arc::clone(&self.block_device))
             .lock()
             .modify(new_inode_block_offset, |new_inode: &mut DiskInode| {
-                new_inode.initialize(DiskInodeType::File);
+                new_inode.initialize(type_);
             });
-        self.modify_disk_inode(|root_inode| {
+        // the dirent must not reach the disk before the inode it points to
+        let mut other_blocks: Vec<usize> = fs.data_bitmap.block_ids().collect();
+        other_blocks.extend(fs.inode_bitmap.block_ids());
+        other_blocks.push(new_inode_block_id as usize);
+        let appended = self.modify_disk_inode_synced(dir_size, &other_blocks, |root_inode| {
             // append file in the dirent
             let file_count = (root_inode.size as usize) / DIRENT_SZ;
             let new_size = (file_count + 1) * DIRENT_SZ;
             // increase size
-            self.increase_size(new_size as u32, root_inode, &mut fs);
+            self.increase_size(new_size as u32, root_inode, &mut fs)?;
             // write dirent
             let dirent = DirEntry::new(name, new_inode_id);
             root_inode.write_at(
@@ -125,50 +292,330 @@ impl Inode {
                 dirent.as_bytes(),
                 &self.block_device,
             );
+            Some(())
         });
+        if appended.is_none() {
+            // no room for the dirent
+            fs.dealloc_inode(new_inode_id);
+            block_cache_sync_all();
+            return None;
+        }
+        // return inode
+        Some(self.child(new_inode_id, name, &fs))
+        // release efs lock automatically by compiler
+    }
 
-        let (block_id, block_offset) = fs.get_disk_inode_pos(new_inode_id);
+    /// Add `new_name` to this directory as a hard link to the file `old_name`.
+    pub fn linkat(&self, old_name: &str, new_name: &str) -> Option<()> {
+        let mut fs = self.fs.lock();
+        let (inode_id, new_exists) = self.read_disk_inode(|root_inode| {
+            (
+                self.find_inode_id(old_name, root_inode),
+                self.find_inode_id(new_name, root_inode).is_some(),
+            )
+        });
+        let inode_id = inode_id?;
+        if new_exists {
+            return None;
+        }
+        let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
+        let inode = Self::new(
+            block_id,
+            block_offset,
+            self.fs.clone(),
+            self.block_device.clone(),
+        );
+        // the directory and the file may share an inode block
+        let linked = self.modify_disk_inode_pair(&inode, |root_inode, disk_inode| {
+            if disk_inode.is_dir() {
+                return None;
+            }
+            let file_count = (root_inode.size as usize) / DIRENT_SZ;
+            let new_size = (file_count + 1) * DIRENT_SZ;
+            self.increase_size(new_size as u32, root_inode, &mut fs)?;
+            let dirent = DirEntry::new(new_name, inode_id);
+            root_inode.write_at(
+                file_count * DIRENT_SZ,
+                dirent.as_bytes(),
+                &self.block_device,
+            );
+            disk_inode.nlink += 1;
+            Some(())
+        });
         block_cache_sync_all();
-        // return inode
-        Some(Arc::new(Self::new(
+        linked
+    }
+
+    /// Remove `name` from this directory, freeing the file with its last link.
+    /// A file still open is only freed by the last `close`, until then it
+    /// stays usable without a name. The last dirent is moved into the freed
+    /// slot, so the directory never has holes and shrinks as files go away.
+    pub fn unlink(&self, name: &str) -> Option<()> {
+        let mut fs = self.fs.lock();
+        let (inode_id, index) =
+            self.read_disk_inode(|root_inode| self.find_dirent(name, root_inode))?;
+        let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
+        let inode = Self::new(
             block_id,
             block_offset,
             self.fs.clone(),
             self.block_device.clone(),
-        )))
-        // release efs lock automatically by compiler
+        );
+        let open = fs.is_inode_open(inode_id);
+        // the directory and the file may share an inode block
+        let unlinked = self.modify_disk_inode_pair(&inode, |root_inode, disk_inode| {
+            if disk_inode.is_dir() {
+                return None;
+            }
+            let file_count = (root_inode.size as usize) / DIRENT_SZ;
+            let mut last = DirEntry::empty();
+            root_inode.read_at(
+                (file_count - 1) * DIRENT_SZ,
+                last.as_bytes_mut(),
+                &self.block_device,
+            );
+            root_inode.write_at(index * DIRENT_SZ, last.as_bytes(), &self.block_device);
+            let mut blocks =
+                root_inode.decrease_size(((file_count - 1) * DIRENT_SZ) as u32, &self.block_device);
+            disk_inode.nlink -= 1;
+            let last_link = disk_inode.nlink == 0 && !open;
+            if last_link {
+                blocks.extend(disk_inode.clear_size(&self.block_device));
+            }
+            Some((blocks, last_link))
+        });
+        let result = unlinked.map(|(blocks, last_link)| {
+            for data_block in blocks.into_iter() {
+                fs.dealloc_data(data_block);
+            }
+            if last_link {
+                fs.dealloc_inode(inode_id);
+            }
+        });
+        block_cache_sync_all();
+        result
+    }
+
+    /// Keep the file alive while it is in use, even past its last unlink.
+    /// Every `open` has to be paired with a `close`.
+    pub fn open(&self) {
+        let mut fs = self.fs.lock();
+        let inode_id = fs.get_inode_id(self.block_id as u32, self.block_offset);
+        fs.open_inode(inode_id);
+    }
+
+    /// Undo an `open`, freeing the file if it was unlinked in the meantime.
+    pub fn close(&self) {
+        let mut fs = self.fs.lock();
+        let inode_id = fs.get_inode_id(self.block_id as u32, self.block_offset);
+        if !fs.close_inode(inode_id) || self.read_disk_inode(|disk_inode| disk_inode.nlink) > 0 {
+            return;
+        }
+        let blocks = self.modify_disk_inode(|disk_inode| disk_inode.clear_size(&self.block_device));
+        for data_block in blocks.into_iter() {
+            fs.dealloc_data(data_block);
+        }
+        fs.dealloc_inode(inode_id);
+        block_cache_sync_all();
+    }
+
+    /// Number of dirents pointing to this inode.
+    pub fn nlink(&self) -> u32 {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.nlink)
+    }
+
+    pub fn is_dir(&self) -> bool {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.is_dir())
+    }
+
+    /// Permission bits of this inode.
+    pub fn mode(&self) -> u16 {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.mode)
+    }
+
+    pub fn set_mode(&self, mode: u16) {
+        let _fs = self.fs.lock();
+        self.modify_disk_inode(|disk_inode| disk_inode.mode = mode);
+        block_cache_sync_all();
+    }
+
+    pub fn inode_id(&self) -> u32 {
+        self.fs
+            .lock()
+            .get_inode_id(self.block_id as u32, self.block_offset)
     }
 
     pub fn ls(&self) -> Vec<String> {
         let _fs = self.fs.lock();
         self.read_disk_inode(|disk_inode| {
-            let file_count = (disk_inode.size as usize) / DIRENT_SZ;
             let mut v: Vec<String> = Vec::new();
-            for i in 0..file_count {
-                let mut dirent = DirEntry::empty();
-                assert_eq!(
-                    disk_inode.read_at(i * DIRENT_SZ, dirent.as_bytes_mut(), &self.block_device,),
-                    DIRENT_SZ,
-                );
+            self.find_map_dirent(disk_inode, |_, dirent| {
                 v.push(String::from(dirent.name()));
-            }
+                None::<()>
+            });
             v
         })
     }
 
+    /// Number of dirents in this directory, directories have no holes.
+    pub fn dirent_count(&self) -> usize {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| {
+            assert!(disk_inode.is_dir());
+            disk_inode.size as usize / DIRENT_SZ
+        })
+    }
+
+    /// A read starting at 0, or in or right after the block where the last
+    /// one ended, is taken as sequential and loads the next blocks as well.
     pub fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+        self.try_read_at(offset, buf)
+            .unwrap_or_else(|err| corrupted(err))
+    }
+
+    /// Like `read_at`, but fail if the inode or a block of the file is corrupted.
+    pub fn try_read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, BlockError> {
+        let _fs = self.fs.lock();
+        try_get_block_cache(self.block_id, Arc::clone(&self.block_device))?
+            .lock()
+            .read(self.block_offset, |disk_inode: &DiskInode| {
+                let read_size = disk_inode.try_read_at(offset, buf, &self.block_device)?;
+                if read_size > 0 {
+                    self.read_ahead(disk_inode, offset, read_size);
+                }
+                Ok(read_size)
+            })
+    }
+
+    fn read_ahead(&self, disk_inode: &DiskInode, offset: usize, read_size: usize) {
+        let first = (offset / BLOCK_SZ) as u32;
+        let last = ((offset + read_size - 1) / BLOCK_SZ) as u32;
+        let prev = self.last_block_read.swap(last, Ordering::Relaxed);
+        let sequential = first == 0 || (prev != NO_BLOCK && (first == prev || first == prev + 1));
+        if !sequential {
+            return;
+        }
+        let end = (last + 1 + READ_AHEAD_BLOCKS).min(disk_inode.data_blocks());
+        for inner_id in last + 1..end {
+            // a corrupted index block is left for the next read to report
+            let block_id = match disk_inode.try_get_block_id(inner_id, &self.block_device) {
+                Ok(block_id) => block_id,
+                Err(_) => break,
+            };
+            prefetch_block_cache(block_id as usize, Arc::clone(&self.block_device));
+        }
+    }
+
+    /// Write as much of `buf` as fits below `DiskInode::max_size()`,
+    /// returning the number of bytes written.
+    /// Return `None` and write nothing if the disk has no room for the new size.
+    ///
+    /// Only a write that allocates blocks is written back at once, see
+    /// `increase_size`. Otherwise the data and the new size stay in the block
+    /// cache until evicted or `sync`ed, so a crash may lose the last writes
+    /// or leave a size covering data never written, read back as zeros.
+    pub fn write_at(&self, offset: usize, buf: &[u8]) -> Option<usize> {
+        self.try_write_at(offset, buf)
+            .unwrap_or_else(|err| corrupted(err))
+    }
+
+    /// Like `write_at`, but fail if the inode or a block of the file is
+    /// corrupted. The part of `buf` in front of the bad block is written.
+    pub fn try_write_at(&self, offset: usize, buf: &[u8]) -> Result<Option<usize>, BlockError> {
+        let max_size = DiskInode::max_size() as usize;
+        if offset >= max_size {
+            return Ok(Some(0));
+        }
+        let buf = &buf[..buf.len().min(max_size - offset)];
+        let new_size = (offset + buf.len()) as u32;
+        let mut fs = self.fs.lock();
+        // verified here and kept cached, so that `modify_disk_inode` finds it
+        let inode_block = try_get_block_cache(self.block_id, Arc::clone(&self.block_device))?;
+        let allocates = inode_block
+            .lock()
+            .read(self.block_offset, |disk_inode: &DiskInode| {
+                DiskInode::total_blocks(new_size) > DiskInode::total_blocks(disk_inode.size)
+            });
+        let bitmap_blocks: Vec<usize> = fs.data_bitmap.block_ids().collect();
+        let write = |disk_inode: &mut DiskInode| {
+            if self.increase_size(new_size, disk_inode, &mut fs).is_none() {
+                return Ok(None);
+            }
+            disk_inode
+                .try_write_at(offset, buf, &self.block_device)
+                .map(Some)
+        };
+        if allocates {
+            self.modify_disk_inode_synced(offset, &bitmap_blocks, write)
+        } else {
+            self.modify_disk_inode(write)
+        }
+    }
+
+    /// Write back the cached data and index blocks of this inode, then the
+    /// block holding the inode itself.
+    pub fn sync(&self) {
         let _fs = self.fs.lock();
-        self.read_disk_inode(|disk_inode| disk_inode.read_at(offset, buf, &self.block_device))
+        let block_ids: Vec<usize> = self
+            .read_disk_inode(|disk_inode| disk_inode.block_ids(&self.block_device))
+            .into_iter()
+            .map(|block_id| block_id as usize)
+            .collect();
+        block_cache_sync(&self.block_device, &block_ids);
+        block_cache_sync(&self.block_device, &[self.block_id]);
     }
 
-    pub fn write_at(&self, offset: usize, buf: &[u8]) -> usize {
+    /// Every data and index block of this inode.
+    pub fn block_ids(&self) -> Vec<u32> {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.block_ids(&self.block_device))
+    }
+
+    /// Set the size of this file to `new_size`, growing it with zeros or
+    /// freeing the blocks past the new end.
+    /// Return `None` for a directory or if the disk has no room to grow.
+    pub fn truncate(&self, new_size: u32) -> Option<()> {
+        if new_size > DiskInode::max_size() {
+            return None;
+        }
         let mut fs = self.fs.lock();
-        let size = self.modify_disk_inode(|disk_inode| {
-            self.increase_size((offset + buf.len()) as u32, disk_inode, &mut fs);
-            disk_inode.write_at(offset, buf, &self.block_device)
+        let (is_dir, size) =
+            self.read_disk_inode(|disk_inode| (disk_inode.is_dir(), disk_inode.size));
+        if is_dir {
+            return None;
+        }
+        if new_size >= size {
+            let bitmap_blocks: Vec<usize> = fs.data_bitmap.block_ids().collect();
+            return self.modify_disk_inode_synced(size as usize, &bitmap_blocks, |disk_inode| {
+                self.increase_size(new_size, disk_inode, &mut fs)
+            });
+        }
+        let data_blocks_dealloc = self.modify_disk_inode(|disk_inode| {
+            // growing again must read zeros in the rest of the last block
+            let tail_end =
+                ((new_size as usize + BLOCK_SZ - 1) / BLOCK_SZ * BLOCK_SZ).min(size as usize);
+            if tail_end > new_size as usize {
+                let zeros = [0u8; BLOCK_SZ];
+                disk_inode.write_at(
+                    new_size as usize,
+                    &zeros[..tail_end - new_size as usize],
+                    &self.block_device,
+                );
+            }
+            disk_inode.decrease_size(new_size, &self.block_device)
         });
+        // the inode must not point to the freed blocks on disk once they are reused
+        get_block_cache(self.block_id, Arc::clone(&self.block_device))
+            .lock()
+            .sync();
+        for data_block in data_blocks_dealloc.into_iter() {
+            fs.dealloc_data(data_block);
+        }
         block_cache_sync_all();
-        size
+        Some(())
     }
 
     pub fn clear(&self) {
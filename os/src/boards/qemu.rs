@@ -11,13 +11,16 @@ pub type BlockDeviceImpl = crate::drivers::block::VirtIOBlock;
 pub type CharDeviceImpl = crate::drivers::chardev::NS16550a<VIRT_UART>;
 
 pub const VIRT_PLIC: usize = 0xC00_0000;
+pub const VIRT_RTC: usize = 0x10_1000;
 pub const VIRT_UART: usize = 0x1000_0000;
+pub const VIRTIO_BLK: usize = 0x1000_8000;
+pub const VIRTIO_BLK_MNT: usize = 0x1000_4000;
 #[allow(unused)]
 pub const VIRTGPU_XRES: u32 = 1280;
 #[allow(unused)]
 pub const VIRTGPU_YRES: u32 = 800;
 
-use crate::drivers::block::BLOCK_DEVICE;
+use crate::drivers::block::{BLOCK_DEVICE, MNT_BLOCK_DEVICE};
 use crate::drivers::chardev::{CharDevice, UART};
 use crate::drivers::plic::{IntrTargetPriority, PLIC};
 use crate::drivers::{KEYBOARD_DEVICE, MOUSE_DEVICE};
@@ -30,8 +33,8 @@ pub fn device_init() {
     let machine = IntrTargetPriority::Machine;
     plic.set_threshold(hart_id, supervisor, 0);
     plic.set_threshold(hart_id, machine, 1);
-    //irq nums: 5 keyboard, 6 mouse, 8 block, 10 uart
-    for intr_src_id in [5usize, 6, 8, 10] {
+    //irq nums: 4 mnt block, 5 keyboard, 6 mouse, 8 block, 10 uart
+    for intr_src_id in [4usize, 5, 6, 8, 10] {
         plic.enable(hart_id, supervisor, intr_src_id);
         plic.set_priority(intr_src_id, 1);
     }
@@ -44,6 +47,7 @@ pub fn irq_handler() {
     let mut plic = unsafe { PLIC::new(VIRT_PLIC) };
     let intr_src_id = plic.claim(0, IntrTargetPriority::Supervisor);
     match intr_src_id {
+        4 => MNT_BLOCK_DEVICE.handle_irq(),
         5 => KEYBOARD_DEVICE.handle_irq(),
         6 => MOUSE_DEVICE.handle_irq(),
         8 => BLOCK_DEVICE.handle_irq(),
@@ -75,6 +79,9 @@ pub trait QEMUExit {
 
     /// Exit QEMU using `EXIT_FAILURE`, aka `1`.
     fn exit_failure(&self) -> !;
+
+    /// Reset the machine, QEMU boots again.
+    fn reset(&self) -> !;
 }
 
 /// RISCV64 configuration
@@ -127,6 +134,10 @@ impl QEMUExit for RISCV64 {
     fn exit_failure(&self) -> ! {
         self.exit(EXIT_FAILURE);
     }
+
+    fn reset(&self) -> ! {
+        self.exit(EXIT_RESET);
+    }
 }
 
 const VIRT_TEST: u64 = 0x100000;
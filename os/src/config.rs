@@ -1,11 +1,22 @@
 #[allow(unused)]
 
 pub const USER_STACK_SIZE: usize = 4096 * 2;
+/// With randomization on, the user stack of a new program starts up to this
+/// many pages above where it would be, see `sys_set_stack_aslr`.
+pub const USER_STACK_RANDOM_PAGES: usize = 256;
 pub const KERNEL_STACK_SIZE: usize = 4096 * 2;
+/// Fds of a process are below this, like Linux's RLIMIT_NOFILE.
+pub const MAX_FDS: usize = 1024;
 pub const KERNEL_HEAP_SIZE: usize = 0x100_0000;
 pub const MEMORY_END: usize = 0x88000000;
 pub const PAGE_SIZE: usize = 0x1000;
 pub const PAGE_SIZE_BITS: usize = 0xc;
+/// Timer ticks a task runs before it is preempted, unless it sets its own
+/// slice with `sys_set_time_slice`.
+pub const DEFAULT_TIME_SLICE: usize = 1;
+pub const MAX_TIME_SLICE: usize = 100;
+/// Free all-zero user pages when running out of frames instead of failing.
+pub const PAGE_RECLAIM: bool = false;
 
 pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
 pub const TRAP_CONTEXT_BASE: usize = TRAMPOLINE - PAGE_SIZE;
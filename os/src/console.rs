@@ -1,20 +1,190 @@
 use crate::drivers::chardev::CharDevice;
 use crate::drivers::chardev::UART;
+use crate::sbi::console_putchar;
+use crate::sync::UPIntrFreeCell;
 use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, Ordering};
+use lazy_static::*;
+
+/// Output goes through SBI until the UART driver is initialized,
+/// e.g. a panic before the kernel heap is ready.
+static UART_READY: AtomicBool = AtomicBool::new(false);
+
+const CONSOLE_BUFFER_SIZE: usize = 256;
+
+fn put_bytes(bytes: &[u8]) {
+    if UART_READY.load(Ordering::Acquire) {
+        for &c in bytes {
+            UART.write(c);
+        }
+    } else {
+        for &c in bytes {
+            console_putchar(c as usize);
+        }
+    }
+}
+
+/// Output is kept until a newline or `console_flush`, so that a line
+/// is written out at once instead of interleaving with other output.
+struct ConsoleBuffer {
+    buf: [u8; CONSOLE_BUFFER_SIZE],
+    len: usize,
+}
+
+impl ConsoleBuffer {
+    fn flush(&mut self) {
+        put_bytes(&self.buf[..self.len]);
+        self.len = 0;
+    }
+}
+
+impl Write for ConsoleBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.bytes() {
+            if self.len == CONSOLE_BUFFER_SIZE {
+                self.flush();
+            }
+            self.buf[self.len] = c;
+            self.len += 1;
+            if c == b'\n' {
+                self.flush();
+            }
+        }
+        Ok(())
+    }
+}
 
 struct Stdout;
 
 impl Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            UART.write(c as u8);
-        }
+        put_bytes(s.as_bytes());
         Ok(())
     }
 }
 
+lazy_static! {
+    static ref CONSOLE_BUFFER: UPIntrFreeCell<ConsoleBuffer> = unsafe {
+        UPIntrFreeCell::new(ConsoleBuffer {
+            buf: [0; CONSOLE_BUFFER_SIZE],
+            len: 0,
+        })
+    };
+}
+
+/// Switch console output to the interrupt-driven UART driver.
+pub fn init() {
+    UART.init();
+    UART_READY.store(true, Ordering::Release);
+}
+
 pub fn print(args: fmt::Arguments) {
-    Stdout.write_fmt(args).unwrap();
+    match CONSOLE_BUFFER.try_exclusive_access() {
+        Some(mut buffer) => buffer.write_fmt(args).unwrap(),
+        // printing while the buffer is in use, e.g. a panic inside the console
+        None => Stdout.write_fmt(args).unwrap(),
+    }
+}
+
+/// Write out the partial line left in the console buffer.
+pub fn console_flush() {
+    if let Some(mut buffer) = CONSOLE_BUFFER.try_exclusive_access() {
+        buffer.flush();
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Off => "OFF",
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+    /// ANSI color code
+    fn color(self) -> u8 {
+        match self {
+            Level::Off => 0,
+            Level::Error => 31,
+            Level::Warn => 93,
+            Level::Info => 34,
+            Level::Debug => 32,
+            Level::Trace => 90,
+        }
+    }
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn parse_level(level: Option<&str>) -> Level {
+    let level = match level {
+        Some(level) => level,
+        None => return Level::Info,
+    };
+    if str_eq(level, "OFF") {
+        Level::Off
+    } else if str_eq(level, "ERROR") {
+        Level::Error
+    } else if str_eq(level, "WARN") {
+        Level::Warn
+    } else if str_eq(level, "DEBUG") {
+        Level::Debug
+    } else if str_eq(level, "TRACE") {
+        Level::Trace
+    } else {
+        Level::Info
+    }
+}
+
+/// Messages above this level, set by the `LOG` env var at build time, are compiled out.
+pub const LOG_LEVEL: Level = parse_level(option_env!("LOG"));
+
+static COLOR_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Turn the ANSI colors of the log macros on or off.
+#[allow(unused)]
+pub fn set_color(enabled: bool) {
+    COLOR_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Print a log line in a single `print` so it is never split.
+pub fn log(level: Level, args: fmt::Arguments) {
+    if COLOR_ENABLED.load(Ordering::Relaxed) {
+        print(format_args!(
+            "\x1b[{}m[{}] {}\x1b[0m\n",
+            level.color(),
+            level.name(),
+            args
+        ));
+    } else {
+        print(format_args!("[{}] {}\n", level.name(), args));
+    }
 }
 
 #[macro_export]
@@ -30,3 +200,63 @@ macro_rules! println {
         $crate::console::print(format_args!(concat!($fmt, "\n") $(, $($arg)+)?))
     }
 }
+
+#[macro_export]
+macro_rules! error {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        if $crate::console::Level::Error <= $crate::console::LOG_LEVEL {
+            $crate::console::log(
+                $crate::console::Level::Error,
+                format_args!($fmt $(, $($arg)+)?),
+            )
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        if $crate::console::Level::Warn <= $crate::console::LOG_LEVEL {
+            $crate::console::log(
+                $crate::console::Level::Warn,
+                format_args!($fmt $(, $($arg)+)?),
+            )
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! info {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        if $crate::console::Level::Info <= $crate::console::LOG_LEVEL {
+            $crate::console::log(
+                $crate::console::Level::Info,
+                format_args!($fmt $(, $($arg)+)?),
+            )
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        if $crate::console::Level::Debug <= $crate::console::LOG_LEVEL {
+            $crate::console::log(
+                $crate::console::Level::Debug,
+                format_args!($fmt $(, $($arg)+)?),
+            )
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        if $crate::console::Level::Trace <= $crate::console::LOG_LEVEL {
+            $crate::console::log(
+                $crate::console::Level::Trace,
+                format_args!($fmt $(, $($arg)+)?),
+            )
+        }
+    }
+}
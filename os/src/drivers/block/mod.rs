@@ -2,13 +2,16 @@ mod virtio_blk;
 
 pub use virtio_blk::VirtIOBlock;
 
-use crate::board::BlockDeviceImpl;
+use crate::board::{BlockDeviceImpl, VIRTIO_BLK, VIRTIO_BLK_MNT};
 use alloc::sync::Arc;
 use easy_fs::BlockDevice;
 use lazy_static::*;
 
 lazy_static! {
-    pub static ref BLOCK_DEVICE: Arc<dyn BlockDevice> = Arc::new(BlockDeviceImpl::new());
+    pub static ref BLOCK_DEVICE: Arc<dyn BlockDevice> = Arc::new(BlockDeviceImpl::new(VIRTIO_BLK));
+    /// Holds the fs mounted at `/mnt/`.
+    pub static ref MNT_BLOCK_DEVICE: Arc<dyn BlockDevice> =
+        Arc::new(BlockDeviceImpl::new(VIRTIO_BLK_MNT));
 }
 
 #[allow(unused)]
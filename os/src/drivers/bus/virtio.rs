@@ -1,6 +1,6 @@
 use crate::mm::{
-    frame_alloc, frame_dealloc, kernel_token, FrameTracker, PageTable, PhysAddr, PhysPageNum,
-    StepByOne, VirtAddr,
+    frame_alloc_contiguous, frame_dealloc, kernel_token, FrameTracker, PageTable, PhysAddr,
+    PhysPageNum, StepByOne, VirtAddr,
 };
 use crate::sync::UPIntrFreeCell;
 use alloc::vec::Vec;
@@ -16,16 +16,9 @@ pub struct VirtioHal;
 
 impl Hal for VirtioHal {
     fn dma_alloc(pages: usize) -> usize {
-        let mut ppn_base = PhysPageNum(0);
-        for i in 0..pages {
-            let frame = frame_alloc().unwrap();
-            if i == 0 {
-                ppn_base = frame.ppn;
-            }
-            assert_eq!(frame.ppn.0, ppn_base.0 + i);
-            QUEUE_FRAMES.exclusive_access().push(frame);
-        }
-        let pa: PhysAddr = ppn_base.into();
+        let frames = frame_alloc_contiguous(pages).unwrap();
+        let pa: PhysAddr = frames[0].ppn.into();
+        QUEUE_FRAMES.exclusive_access().extend(frames);
         pa.0
     }
 
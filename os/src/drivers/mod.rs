@@ -4,8 +4,9 @@ pub mod chardev;
 pub mod gpu;
 pub mod input;
 pub mod plic;
+pub mod rtc;
 
-pub use block::BLOCK_DEVICE;
+pub use block::{BLOCK_DEVICE, MNT_BLOCK_DEVICE};
 pub use bus::*;
 pub use chardev::UART;
 pub use gpu::*;
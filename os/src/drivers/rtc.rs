@@ -0,0 +1,53 @@
+//! Goldfish RTC of the QEMU virt machine, it counts nanoseconds since the Unix epoch.
+use crate::board::VIRT_RTC;
+use crate::sync::UPIntrFreeCell;
+use crate::timer::get_time_ms;
+use core::ptr::read_volatile;
+use lazy_static::*;
+
+const TIME_LOW: usize = 0x00;
+const TIME_HIGH: usize = 0x04;
+const NSEC_PER_SEC: u64 = 1_000_000_000;
+
+pub struct GoldfishRtc {
+    base_addr: usize,
+}
+
+impl GoldfishRtc {
+    pub fn new(base_addr: usize) -> Self {
+        Self { base_addr }
+    }
+
+    /// Nanoseconds since the Unix epoch.
+    pub fn read_ns(&self) -> u64 {
+        // reading TIME_LOW latches TIME_HIGH
+        unsafe {
+            let low = read_volatile((self.base_addr + TIME_LOW) as *const u32);
+            let high = read_volatile((self.base_addr + TIME_HIGH) as *const u32);
+            (high as u64) << 32 | low as u64
+        }
+    }
+}
+
+lazy_static! {
+    /// `None` until `init` finds the device.
+    static ref RTC: UPIntrFreeCell<Option<GoldfishRtc>> = unsafe { UPIntrFreeCell::new(None) };
+}
+
+/// Probe the RTC, a missing device reads as 0.
+pub fn init() {
+    let rtc = GoldfishRtc::new(VIRT_RTC);
+    if rtc.read_ns() == 0 {
+        println!("KERN: no rtc, the wall clock counts from boot");
+        return;
+    }
+    *RTC.exclusive_access() = Some(rtc);
+}
+
+/// Seconds since the Unix epoch, or since boot without an RTC.
+pub fn unix_time() -> usize {
+    match RTC.exclusive_access().as_ref() {
+        Some(rtc) => (rtc.read_ns() / NSEC_PER_SEC) as usize,
+        None => get_time_ms() / 1000,
+    }
+}
@@ -1,13 +1,17 @@
-use super::File;
-use crate::drivers::BLOCK_DEVICE;
+use super::{File, Stat, StatMode};
+use crate::drivers::{BLOCK_DEVICE, MNT_BLOCK_DEVICE};
 use crate::mm::UserBuffer;
 use crate::sync::UPIntrFreeCell;
+use crate::syscall::Errno;
+use crate::task::preempt_point;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use bitflags::*;
-use easy_fs::{EasyFileSystem, Inode};
+use easy_fs::{block_cache_sync_all, cache_stats, DiskInode, EasyFileSystem, Inode};
 use lazy_static::*;
 
+/// An open file description: every `open` makes a new one with its own
+/// offset, while `dup` and `fork` share the `Arc` and so the offset.
 pub struct OSInode {
     readable: bool,
     writable: bool,
@@ -21,12 +25,20 @@ pub struct OSInodeInner {
 
 impl OSInode {
     pub fn new(readable: bool, writable: bool, inode: Arc<Inode>) -> Self {
+        inode.open();
         Self {
             readable,
             writable,
             inner: unsafe { UPIntrFreeCell::new(OSInodeInner { offset: 0, inode }) },
         }
     }
+    pub fn is_dir(&self) -> bool {
+        self.inner.exclusive_access().inode.is_dir()
+    }
+    /// Permission bits of the inode.
+    pub fn mode(&self) -> u16 {
+        self.inner.exclusive_access().inode.mode()
+    }
     pub fn read_all(&self) -> Vec<u8> {
         let mut inner = self.inner.exclusive_access();
         let mut buffer = [0u8; 512];
@@ -43,11 +55,135 @@ impl OSInode {
     }
 }
 
+impl Drop for OSInode {
+    fn drop(&mut self) {
+        self.inner.exclusive_access().inode.close();
+    }
+}
+
 lazy_static! {
     pub static ref ROOT_INODE: Arc<Inode> = {
         let efs = EasyFileSystem::open(BLOCK_DEVICE.clone());
         Arc::new(EasyFileSystem::root_inode(&efs))
     };
+    /// Root of the fs on the second block device, opened on first access.
+    pub static ref MNT_INODE: Arc<Inode> = {
+        let efs = EasyFileSystem::open(MNT_BLOCK_DEVICE.clone());
+        Arc::new(EasyFileSystem::root_inode(&efs))
+    };
+}
+
+/// A fs that can be mounted at a path prefix.
+enum Mount {
+    /// easy-fs on a block device, given by its root inode
+    Efs(fn() -> Arc<Inode>),
+    /// the in-memory fs of `tmpfs`
+    Tmp,
+    /// the kernel stats of `procfs`
+    Proc,
+}
+
+/// Mount table: path prefix -> the mounted fs.
+/// Every other path lives on `ROOT_INODE`.
+static MOUNT_TABLE: &[(&str, Mount)] = &[
+    ("/mnt/", Mount::Efs(|| MNT_INODE.clone())),
+    ("/tmp/", Mount::Tmp),
+    ("/proc/", Mount::Proc),
+];
+
+/// Pick the easy-fs holding `path`, return its root inode and the name inside it.
+/// Paths on the tmpfs or /proc must be checked with `tmp_name` or
+/// `proc_name` first.
+pub fn resolve_path(path: &str) -> (Arc<Inode>, &str) {
+    for (prefix, mount) in MOUNT_TABLE {
+        if let (Mount::Efs(root_inode), Some(name)) = (mount, path.strip_prefix(prefix)) {
+            return (root_inode(), name);
+        }
+    }
+    (ROOT_INODE.clone(), path.strip_prefix('/').unwrap_or(path))
+}
+
+/// The name inside the tmpfs if `path` lives there.
+pub fn tmp_name(path: &str) -> Option<&str> {
+    MOUNT_TABLE.iter().find_map(|(prefix, mount)| match mount {
+        Mount::Tmp => path.strip_prefix(prefix),
+        _ => None,
+    })
+}
+
+/// The name inside /proc if `path` lives there.
+pub fn proc_name(path: &str) -> Option<&str> {
+    MOUNT_TABLE.iter().find_map(|(prefix, mount)| match mount {
+        Mount::Proc => path.strip_prefix(prefix),
+        _ => None,
+    })
+}
+
+/// Create `new_path` as a hard link to `old_path`.
+/// Return None if the paths are on different fs or the link can not be made.
+pub fn link_file(old_path: &str, new_path: &str) -> Option<()> {
+    let (root_inode, old_name) = resolve_path(old_path);
+    let (new_root_inode, new_name) = resolve_path(new_path);
+    if !Arc::ptr_eq(&root_inode, &new_root_inode) {
+        return None;
+    }
+    root_inode.linkat(old_name, new_name)
+}
+
+/// Remove `path`, return None if it does not exist or is a directory.
+pub fn unlink_file(path: &str) -> Option<()> {
+    let (root_inode, name) = resolve_path(path);
+    root_inode.unlink(name)
+}
+
+/// Look up the inode of `path` without opening it.
+pub fn find_inode(path: &str) -> Option<Arc<Inode>> {
+    let (root_inode, name) = resolve_path(path);
+    root_inode.find(name)
+}
+
+/// Build the `Stat` of `inode`, shared by `fstat` and `stat`.
+pub fn inode_stat(inode: &Inode) -> Stat {
+    let type_ = if inode.is_dir() {
+        StatMode::DIR
+    } else {
+        StatMode::FILE
+    };
+    let mode = type_ | StatMode::from_bits_truncate(inode.mode() as u32);
+    Stat::new(inode.inode_id() as u64, mode, inode.nlink())
+}
+
+/// Whether `old_path` and `new_path` live on the same fs.
+pub fn same_fs(old_path: &str, new_path: &str) -> bool {
+    match (tmp_name(old_path), tmp_name(new_path)) {
+        (Some(_), Some(_)) => return true,
+        (None, None) => {}
+        _ => return false,
+    }
+    Arc::ptr_eq(&resolve_path(old_path).0, &resolve_path(new_path).0)
+}
+
+/// Write every dirty cached block back to its disk.
+pub fn sync_all() {
+    block_cache_sync_all();
+}
+
+/// Report how well the block cache did, at shutdown.
+pub fn log_cache_stats() {
+    let stats = cache_stats();
+    let accesses = stats.hits + stats.misses;
+    debug!(
+        "[kernel] block cache: {} hits, {} misses, {} evictions, {} prefetches, hit ratio {}%",
+        stats.hits,
+        stats.misses,
+        stats.evictions,
+        stats.prefetches,
+        if accesses == 0 {
+            0
+        } else {
+            stats.hits * 100 / accesses
+        }
+    );
 }
 
 pub fn list_apps() {
@@ -65,6 +201,9 @@ bitflags! {
         const RDWR = 1 << 1;
         const CREATE = 1 << 9;
         const TRUNC = 1 << 10;
+        const EXCL = 1 << 11;
+        /// close the fd on exec, like `fcntl(fd, F_SETFD, FD_CLOEXEC)`
+        const CLOEXEC = 1 << 19;
     }
 }
 
@@ -72,9 +211,10 @@ impl OpenFlags {
     /// Do not check validity for simplicity
     /// Return (readable, writable)
     pub fn read_write(&self) -> (bool, bool) {
-        if self.is_empty() {
+        let flags = *self - Self::CLOEXEC;
+        if flags.is_empty() {
             (true, false)
-        } else if self.contains(Self::WRONLY) {
+        } else if flags.contains(Self::WRONLY) {
             (false, true)
         } else {
             (true, true)
@@ -83,25 +223,37 @@ impl OpenFlags {
 }
 
 pub fn open_file(name: &str, flags: OpenFlags) -> Option<Arc<OSInode>> {
+    let (root_inode, name) = resolve_path(name);
+    open_file_in(&root_inode, name, flags, 0)
+}
+
+/// Open the file `name` in the directory `root_inode`.
+/// A file created here does not get the permission bits in `umask`.
+pub fn open_file_in(
+    root_inode: &Inode,
+    name: &str,
+    flags: OpenFlags,
+    umask: u16,
+) -> Option<Arc<OSInode>> {
     let (readable, writable) = flags.read_write();
-    if flags.contains(OpenFlags::CREATE) {
-        if let Some(inode) = ROOT_INODE.find(name) {
+    if let Some(inode) = root_inode.find(name) {
+        if flags.contains(OpenFlags::CREATE | OpenFlags::EXCL) {
+            // the file must not exist yet
+            return None;
+        }
+        if flags.contains(OpenFlags::TRUNC) {
             // clear size
             inode.clear();
-            Some(Arc::new(OSInode::new(readable, writable, inode)))
-        } else {
-            // create file
-            ROOT_INODE
-                .create(name)
-                .map(|inode| Arc::new(OSInode::new(readable, writable, inode)))
         }
-    } else {
-        ROOT_INODE.find(name).map(|inode| {
-            if flags.contains(OpenFlags::TRUNC) {
-                inode.clear();
-            }
+        Some(Arc::new(OSInode::new(readable, writable, inode)))
+    } else if flags.contains(OpenFlags::CREATE) {
+        // create file
+        root_inode.create(name).map(|inode| {
+            inode.set_mode(inode.mode() & !umask);
             Arc::new(OSInode::new(readable, writable, inode))
         })
+    } else {
+        None
     }
 }
 
@@ -112,28 +264,81 @@ impl File for OSInode {
     fn writable(&self) -> bool {
         self.writable
     }
-    fn read(&self, mut buf: UserBuffer) -> usize {
-        let mut inner = self.inner.exclusive_access();
+    fn read(&self, mut buf: UserBuffer) -> Result<usize, Errno> {
+        // a directory is opened only as the base of `openat`
+        if self.is_dir() {
+            return Ok(0);
+        }
         let mut total_read_size = 0usize;
         for slice in buf.buffers.iter_mut() {
-            let read_size = inner.inode.read_at(inner.offset, *slice);
+            let mut inner = self.inner.exclusive_access();
+            let read_size = match inner.inode.try_read_at(inner.offset, slice) {
+                Ok(read_size) => read_size,
+                // a corrupted block, report what was read in front of it
+                Err(_) if total_read_size > 0 => break,
+                Err(_) => return Err(Errno::EIO),
+            };
             if read_size == 0 {
                 break;
             }
             inner.offset += read_size;
             total_read_size += read_size;
+            drop(inner);
+            // interrupts are masked while `inner` is held, a tick lands here
+            preempt_point();
         }
-        total_read_size
+        Ok(total_read_size)
     }
-    fn write(&self, buf: UserBuffer) -> usize {
-        let mut inner = self.inner.exclusive_access();
+    fn write(&self, buf: UserBuffer) -> Result<usize, Errno> {
         let mut total_write_size = 0usize;
         for slice in buf.buffers.iter() {
-            let write_size = inner.inode.write_at(inner.offset, *slice);
-            assert_eq!(write_size, slice.len());
+            let mut inner = self.inner.exclusive_access();
+            // a full disk ends the write short, possibly at 0 bytes
+            let write_size = match inner.inode.try_write_at(inner.offset, slice) {
+                Ok(Some(write_size)) => write_size,
+                Ok(None) => break,
+                Err(_) if total_write_size > 0 => break,
+                Err(_) => return Err(Errno::EIO),
+            };
             inner.offset += write_size;
             total_write_size += write_size;
+            // the file has reached its maximum size
+            if write_size < slice.len() {
+                break;
+            }
+            drop(inner);
+            preempt_point();
+        }
+        Ok(total_write_size)
+    }
+    fn is_regular(&self) -> bool {
+        !self.is_dir()
+    }
+    fn stat(&self) -> Option<Stat> {
+        Some(inode_stat(&self.inner.exclusive_access().inode))
+    }
+    fn truncate(&self, len: usize) -> Result<(), Errno> {
+        let len = u32::try_from(len)
+            .ok()
+            .filter(|len| *len <= DiskInode::max_size())
+            .ok_or(Errno::EFBIG)?;
+        // only a growth can fail now, for lack of blocks
+        self.inner
+            .exclusive_access()
+            .inode
+            .truncate(len)
+            .ok_or(Errno::ENOSPC)
+    }
+    fn sync(&self) -> Option<()> {
+        self.inner.exclusive_access().inode.sync();
+        Some(())
+    }
+    fn dir(&self) -> Option<Arc<Inode>> {
+        let inner = self.inner.exclusive_access();
+        if inner.inode.is_dir() {
+            Some(inner.inode.clone())
+        } else {
+            None
         }
-        total_write_size
     }
 }
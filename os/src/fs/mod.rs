@@ -1,16 +1,134 @@
 mod inode;
 mod pipe;
+mod procfs;
 mod stdio;
+mod tmpfs;
 
 use crate::mm::UserBuffer;
+use crate::syscall::Errno;
+use alloc::sync::Arc;
+use bitflags::*;
+use easy_fs::Inode;
 
 pub trait File: Send + Sync {
     fn readable(&self) -> bool;
     fn writable(&self) -> bool;
-    fn read(&self, buf: UserBuffer) -> usize;
-    fn write(&self, buf: UserBuffer) -> usize;
+    /// Return the number of bytes read, short if it fails halfway.
+    fn read(&self, buf: UserBuffer) -> Result<usize, Errno>;
+    /// Return the number of bytes written, short if it fails halfway.
+    fn write(&self, buf: UserBuffer) -> Result<usize, Errno>;
+    /// Only files backed by an inode have a `Stat`.
+    fn stat(&self) -> Option<Stat> {
+        None
+    }
+    /// Set the size of the file behind this one to `len` bytes. Fail with
+    /// EFBIG past the largest file size, ENOSPC if the fs has no room left.
+    fn truncate(&self, _len: usize) -> Result<(), Errno> {
+        Err(Errno::EINVAL)
+    }
+    /// Write the cached blocks of the file behind this one back to its disk.
+    fn sync(&self) -> Option<()> {
+        None
+    }
+    /// The directory inode behind this file, the base of `openat`.
+    fn dir(&self) -> Option<Arc<Inode>> {
+        None
+    }
+    /// Whether a `read` would return without blocking.
+    fn readable_now(&self) -> bool {
+        self.readable()
+    }
+    /// Whether a `write` would return without blocking.
+    fn writable_now(&self) -> bool {
+        self.writable()
+    }
+    /// Whether this is the console, the only file taking terminal `ioctl`s.
+    fn is_tty(&self) -> bool {
+        false
+    }
+    /// Whether this is a regular file on some fs, where writing nothing
+    /// means the fs is full.
+    fn is_regular(&self) -> bool {
+        false
+    }
 }
 
-pub use inode::{list_apps, open_file, OSInode, OpenFlags, ROOT_INODE};
+/// One entry of the array passed to `sys_poll`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PollFd {
+    pub fd: i32,
+    /// requested `PollEvents`
+    pub events: i16,
+    /// `PollEvents` filled in by the kernel
+    pub revents: i16,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct Stat {
+    /// ID of the device containing the file
+    pub dev: u64,
+    /// inode number
+    pub ino: u64,
+    /// file type and permission bits
+    pub mode: StatMode,
+    /// number of hard links
+    pub nlink: u32,
+    pad: [u64; 7],
+}
+
+impl Stat {
+    pub fn new(ino: u64, mode: StatMode, nlink: u32) -> Self {
+        Self {
+            dev: 0,
+            ino,
+            mode,
+            nlink,
+            pad: [0; 7],
+        }
+    }
+}
+
+bitflags! {
+    pub struct StatMode: u32 {
+        const NULL  = 0;
+        /// directory
+        const DIR   = 0o040000;
+        /// ordinary regular file
+        const FILE  = 0o100000;
+        /// owner has read permission
+        const S_IRUSR = 0o400;
+        /// owner has write permission
+        const S_IWUSR = 0o200;
+        /// owner has execute permission
+        const S_IXUSR = 0o100;
+        /// group and others permission bits
+        const S_IRWXG = 0o070;
+        const S_IRWXO = 0o007;
+    }
+}
+
+bitflags! {
+    pub struct PollEvents: i16 {
+        /// there is data to read
+        const POLLIN = 0x001;
+        /// writing will not block
+        const POLLOUT = 0x004;
+        /// the fd is not open
+        const POLLNVAL = 0x020;
+    }
+}
+
+pub use inode::{
+    find_inode, inode_stat, link_file, list_apps, log_cache_stats, open_file, open_file_in,
+    proc_name, resolve_path, same_fs, sync_all, tmp_name, unlink_file, OSInode, OpenFlags,
+    ROOT_INODE,
+};
+pub use easy_fs::{cache_stats, CacheStats};
 pub use pipe::{make_pipe, Pipe};
-pub use stdio::{Stdin, Stdout};
+pub use procfs::{open_proc_file, ProcFile};
+pub use stdio::{
+    console_termios, set_console_termios, Stdin, Stdout, Termios, Winsize, CONSOLE_WINSIZE,
+};
+pub use tmpfs::{open_tmp_file, tmp_find, tmp_link, tmp_unlink};
@@ -1,9 +1,10 @@
 use super::File;
 use crate::mm::UserBuffer;
 use crate::sync::UPIntrFreeCell;
+use crate::syscall::Errno;
 use alloc::sync::{Arc, Weak};
 
-use crate::task::suspend_current_and_run_next;
+use crate::task::WaitQueue;
 
 pub struct Pipe {
     readable: bool,
@@ -43,6 +44,10 @@ pub struct PipeRingBuffer {
     tail: usize,
     status: RingBufferStatus,
     write_end: Option<Weak<Pipe>>,
+    /// readers waiting for data or for the write end to be closed
+    read_wait: Arc<WaitQueue>,
+    /// writers waiting for free space
+    write_wait: Arc<WaitQueue>,
 }
 
 impl PipeRingBuffer {
@@ -53,6 +58,8 @@ impl PipeRingBuffer {
             tail: 0,
             status: RingBufferStatus::Empty,
             write_end: None,
+            read_wait: Arc::new(WaitQueue::new()),
+            write_wait: Arc::new(WaitQueue::new()),
         }
     }
     pub fn set_write_end(&mut self, write_end: &Arc<Pipe>) {
@@ -112,7 +119,14 @@ impl File for Pipe {
     fn writable(&self) -> bool {
         self.writable
     }
-    fn read(&self, buf: UserBuffer) -> usize {
+    fn readable_now(&self) -> bool {
+        let ring_buffer = self.buffer.exclusive_access();
+        self.readable && (ring_buffer.available_read() > 0 || ring_buffer.all_write_ends_closed())
+    }
+    fn writable_now(&self) -> bool {
+        self.writable && self.buffer.exclusive_access().available_write() > 0
+    }
+    fn read(&self, buf: UserBuffer) -> Result<usize, Errno> {
         assert!(self.readable());
         let want_to_read = buf.len();
         let mut buf_iter = buf.into_iter();
@@ -122,10 +136,11 @@ impl File for Pipe {
             let loop_read = ring_buffer.available_read();
             if loop_read == 0 {
                 if ring_buffer.all_write_ends_closed() {
-                    return already_read;
+                    return Ok(already_read);
                 }
+                let read_wait = ring_buffer.read_wait.clone();
                 drop(ring_buffer);
-                suspend_current_and_run_next();
+                read_wait.block_current();
                 continue;
             }
             for _ in 0..loop_read {
@@ -134,16 +149,17 @@ impl File for Pipe {
                         *byte_ref = ring_buffer.read_byte();
                     }
                     already_read += 1;
-                    if already_read == want_to_read {
-                        return want_to_read;
-                    }
                 } else {
-                    return already_read;
+                    break;
                 }
             }
+            ring_buffer.write_wait.wake_all();
+            if already_read == want_to_read {
+                return Ok(want_to_read);
+            }
         }
     }
-    fn write(&self, buf: UserBuffer) -> usize {
+    fn write(&self, buf: UserBuffer) -> Result<usize, Errno> {
         assert!(self.writable());
         let want_to_write = buf.len();
         let mut buf_iter = buf.into_iter();
@@ -152,8 +168,9 @@ impl File for Pipe {
             let mut ring_buffer = self.buffer.exclusive_access();
             let loop_write = ring_buffer.available_write();
             if loop_write == 0 {
+                let write_wait = ring_buffer.write_wait.clone();
                 drop(ring_buffer);
-                suspend_current_and_run_next();
+                write_wait.block_current();
                 continue;
             }
             // write at most loop_write bytes
@@ -161,13 +178,23 @@ impl File for Pipe {
                 if let Some(byte_ref) = buf_iter.next() {
                     ring_buffer.write_byte(unsafe { *byte_ref });
                     already_write += 1;
-                    if already_write == want_to_write {
-                        return want_to_write;
-                    }
                 } else {
-                    return already_write;
+                    break;
                 }
             }
+            ring_buffer.read_wait.wake_all();
+            if already_write == want_to_write {
+                return Ok(want_to_write);
+            }
+        }
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        // readers blocked on an empty pipe must see the write end closed
+        if self.writable {
+            self.buffer.exclusive_access().read_wait.wake_all();
         }
     }
 }
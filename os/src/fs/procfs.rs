@@ -0,0 +1,101 @@
+//! Read-only files under /proc whose contents are made up by the kernel
+//! when read, there is no inode behind them.
+
+use super::File;
+use crate::mm::{frame_stats, UserBuffer};
+use crate::sync::UPIntrFreeCell;
+use crate::syscall::Errno;
+use crate::task::task_count;
+use crate::timer::get_time_ms;
+use alloc::format;
+use alloc::string::String;
+
+#[derive(Clone, Copy)]
+enum ProcKind {
+    /// seconds since boot
+    Uptime,
+    /// physical frames and tasks
+    Meminfo,
+}
+
+static PROC_FILES: &[(&str, ProcKind)] =
+    &[("uptime", ProcKind::Uptime), ("meminfo", ProcKind::Meminfo)];
+
+impl ProcKind {
+    fn render(self) -> String {
+        match self {
+            Self::Uptime => {
+                let ms = get_time_ms();
+                format!("{}.{:03}\n", ms / 1000, ms % 1000)
+            }
+            Self::Meminfo => {
+                let (total, free) = frame_stats();
+                format!(
+                    "FramesTotal: {}\nFramesFree: {}\nFramesUsed: {}\nTasks: {}\n",
+                    total,
+                    free,
+                    total - free,
+                    task_count()
+                )
+            }
+        }
+    }
+}
+
+pub struct ProcFile {
+    kind: ProcKind,
+    inner: UPIntrFreeCell<ProcFileInner>,
+}
+
+pub struct ProcFileInner {
+    offset: usize,
+    /// what the last read from offset 0 saw, later reads go on with it
+    contents: String,
+}
+
+/// Open the file `name` of /proc, there is none to create.
+pub fn open_proc_file(name: &str) -> Option<ProcFile> {
+    let kind = PROC_FILES
+        .iter()
+        .find(|(proc_name, _)| *proc_name == name)?
+        .1;
+    Some(ProcFile {
+        kind,
+        inner: unsafe {
+            UPIntrFreeCell::new(ProcFileInner {
+                offset: 0,
+                contents: String::new(),
+            })
+        },
+    })
+}
+
+impl File for ProcFile {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    fn read(&self, mut buf: UserBuffer) -> Result<usize, Errno> {
+        let mut inner = self.inner.exclusive_access();
+        if inner.offset == 0 {
+            inner.contents = self.kind.render();
+        }
+        let mut total_read_size = 0usize;
+        for slice in buf.buffers.iter_mut() {
+            let rest = &inner.contents.as_bytes()[inner.offset..];
+            let read_size = rest.len().min(slice.len());
+            if read_size == 0 {
+                break;
+            }
+            slice[..read_size].copy_from_slice(&rest[..read_size]);
+            inner.offset += read_size;
+            total_read_size += read_size;
+        }
+        Ok(total_read_size)
+    }
+    fn write(&self, _buf: UserBuffer) -> Result<usize, Errno> {
+        panic!("ProcFile not writable!");
+    }
+}
@@ -1,11 +1,107 @@
 use super::File;
 use crate::drivers::chardev::CharDevice;
+use crate::console::console_flush;
 use crate::drivers::chardev::UART;
 use crate::mm::UserBuffer;
+use crate::sync::UPIntrFreeCell;
+use crate::syscall::Errno;
+use alloc::collections::VecDeque;
+use lazy_static::*;
 
 pub struct Stdin;
 pub struct Stdout;
 
+/// `lflag` bit: read input a line at a time, with erasing.
+pub const ICANON: u32 = 0o000002;
+/// `lflag` bit: echo input characters.
+pub const ECHO: u32 = 0o000010;
+
+/// Terminal attributes, laid out like Linux's `struct termios`.
+/// Only `ICANON` and `ECHO` of `lflag` take effect, the rest is kept as set.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Termios {
+    pub iflag: u32,
+    pub oflag: u32,
+    pub cflag: u32,
+    pub lflag: u32,
+    pub line: u8,
+    pub cc: [u8; 19],
+}
+
+/// Terminal size returned by `TIOCGWINSZ`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Winsize {
+    pub row: u16,
+    pub col: u16,
+    pub xpixel: u16,
+    pub ypixel: u16,
+}
+
+/// The uart console has no way to report its size, assume the classic one.
+pub const CONSOLE_WINSIZE: Winsize = Winsize {
+    row: 24,
+    col: 80,
+    xpixel: 0,
+    ypixel: 0,
+};
+
+lazy_static! {
+    /// Attributes of the console, raw without echo by default since the
+    /// shell does its own line editing.
+    static ref TERMIOS: UPIntrFreeCell<Termios> =
+        unsafe { UPIntrFreeCell::new(Termios::default()) };
+    /// A line read in canonical mode that is not fully consumed yet.
+    static ref LINE: UPIntrFreeCell<VecDeque<u8>> =
+        unsafe { UPIntrFreeCell::new(VecDeque::new()) };
+}
+
+pub fn console_termios() -> Termios {
+    *TERMIOS.exclusive_access()
+}
+
+pub fn set_console_termios(termios: &Termios) {
+    *TERMIOS.exclusive_access() = *termios;
+}
+
+fn echo(bytes: &[u8]) {
+    for &byte in bytes {
+        print!("{}", byte as char);
+    }
+    console_flush();
+}
+
+/// Read from the uart until a newline, handling backspace.
+fn read_line(echoing: bool) {
+    let mut line = VecDeque::new();
+    loop {
+        // do not hold `LINE` here, the uart may block
+        let ch = UART.read();
+        match ch {
+            b'\r' | b'\n' => {
+                line.push_back(b'\n');
+                if echoing {
+                    echo(b"\n");
+                }
+                break;
+            }
+            0x08 | 0x7f => {
+                if line.pop_back().is_some() && echoing {
+                    echo(b"\x08 \x08");
+                }
+            }
+            _ => {
+                line.push_back(ch);
+                if echoing {
+                    echo(&[ch]);
+                }
+            }
+        }
+    }
+    *LINE.exclusive_access() = line;
+}
+
 impl File for Stdin {
     fn readable(&self) -> bool {
         true
@@ -13,18 +109,46 @@ impl File for Stdin {
     fn writable(&self) -> bool {
         false
     }
-    fn read(&self, mut user_buf: UserBuffer) -> usize {
-        assert_eq!(user_buf.len(), 1);
-        //println!("before UART.read() in Stdin::read()");
-        let ch = UART.read();
-        unsafe {
-            user_buf.buffers[0].as_mut_ptr().write_volatile(ch);
+    /// In canonical mode a partial line in the uart may still block the read.
+    fn readable_now(&self) -> bool {
+        !LINE.exclusive_access().is_empty() || !UART.read_buffer_is_empty()
+    }
+    fn read(&self, mut user_buf: UserBuffer) -> Result<usize, Errno> {
+        if user_buf.len() == 0 {
+            return Ok(0);
         }
-        1
+        let lflag = console_termios().lflag;
+        if lflag & ICANON == 0 {
+            // raw mode, every byte is handed over as soon as it arrives
+            let ch = UART.read();
+            if lflag & ECHO != 0 {
+                echo(&[ch]);
+            }
+            unsafe {
+                user_buf.buffers[0].as_mut_ptr().write_volatile(ch);
+            }
+            return Ok(1);
+        }
+        if LINE.exclusive_access().is_empty() {
+            read_line(lflag & ECHO != 0);
+        }
+        let mut line = LINE.exclusive_access();
+        let mut read_size = 0;
+        for byte_ref in user_buf.into_iter() {
+            match line.pop_front() {
+                Some(byte) => unsafe { *byte_ref = byte },
+                None => break,
+            }
+            read_size += 1;
+        }
+        Ok(read_size)
     }
-    fn write(&self, _user_buf: UserBuffer) -> usize {
+    fn write(&self, _user_buf: UserBuffer) -> Result<usize, Errno> {
         panic!("Cannot write to stdin!");
     }
+    fn is_tty(&self) -> bool {
+        true
+    }
 }
 
 impl File for Stdout {
@@ -34,13 +158,18 @@ impl File for Stdout {
     fn writable(&self) -> bool {
         true
     }
-    fn read(&self, _user_buf: UserBuffer) -> usize {
+    fn read(&self, _user_buf: UserBuffer) -> Result<usize, Errno> {
         panic!("Cannot read from stdout!");
     }
-    fn write(&self, user_buf: UserBuffer) -> usize {
+    fn write(&self, user_buf: UserBuffer) -> Result<usize, Errno> {
         for buffer in user_buf.buffers.iter() {
             print!("{}", core::str::from_utf8(*buffer).unwrap());
         }
-        user_buf.len()
+        // a prompt without newline must show up
+        console_flush();
+        Ok(user_buf.len())
+    }
+    fn is_tty(&self) -> bool {
+        true
     }
 }
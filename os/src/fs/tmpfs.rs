@@ -0,0 +1,262 @@
+use super::{File, OpenFlags, Stat, StatMode};
+use crate::mm::UserBuffer;
+use crate::sync::UPIntrFreeCell;
+use crate::syscall::Errno;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lazy_static::*;
+
+/// Bytes all tmpfs files may hold together, the rest of the kernel heap is
+/// left to the kernel.
+const TMP_FS_SIZE: usize = 4 << 20;
+/// Bytes held by tmpfs files now, unlinked but open ones included.
+static TMP_FS_USED: AtomicUsize = AtomicUsize::new(0);
+
+/// A file of the tmpfs, its contents live in the kernel heap.
+pub struct RamInode {
+    ino: u64,
+    inner: UPIntrFreeCell<RamInodeInner>,
+}
+
+pub struct RamInodeInner {
+    data: Vec<u8>,
+    mode: u16,
+    nlink: u32,
+}
+
+impl RamInode {
+    fn new(ino: u64) -> Self {
+        Self {
+            ino,
+            inner: unsafe {
+                UPIntrFreeCell::new(RamInodeInner {
+                    data: Vec::new(),
+                    mode: 0o644,
+                    nlink: 1,
+                })
+            },
+        }
+    }
+    pub fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+        let inner = self.inner.exclusive_access();
+        if offset >= inner.data.len() {
+            return 0;
+        }
+        let len = buf.len().min(inner.data.len() - offset);
+        buf[..len].copy_from_slice(&inner.data[offset..offset + len]);
+        len
+    }
+    /// Grow the file as needed, a hole before `offset` reads as zeros.
+    /// Return how much was written, short once the tmpfs or the heap is full.
+    pub fn write_at(&self, offset: usize, buf: &[u8]) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        let len = inner.data.len();
+        let room = TMP_FS_SIZE.saturating_sub(TMP_FS_USED.load(Ordering::Relaxed));
+        let mut end = offset.saturating_add(buf.len()).min(len + room);
+        if end > len && inner.data.try_reserve(end - len).is_err() {
+            end = len;
+        }
+        if end <= offset {
+            return 0;
+        }
+        if end > len {
+            inner.data.resize(end, 0);
+            TMP_FS_USED.fetch_add(end - len, Ordering::Relaxed);
+        }
+        inner.data[offset..end].copy_from_slice(&buf[..end - offset]);
+        end - offset
+    }
+    #[allow(unused)]
+    pub fn size(&self) -> usize {
+        self.inner.exclusive_access().data.len()
+    }
+    /// Fail with EFBIG past the size of the whole tmpfs, ENOSPC past the
+    /// room left in it or in the heap.
+    pub fn truncate(&self, len: usize) -> Result<(), Errno> {
+        if len > TMP_FS_SIZE {
+            return Err(Errno::EFBIG);
+        }
+        let mut inner = self.inner.exclusive_access();
+        let old_len = inner.data.len();
+        if len > old_len {
+            let room = TMP_FS_SIZE.saturating_sub(TMP_FS_USED.load(Ordering::Relaxed));
+            if len - old_len > room || inner.data.try_reserve_exact(len - old_len).is_err() {
+                return Err(Errno::ENOSPC);
+            }
+            TMP_FS_USED.fetch_add(len - old_len, Ordering::Relaxed);
+        } else {
+            TMP_FS_USED.fetch_sub(old_len - len, Ordering::Relaxed);
+        }
+        inner.data.resize(len, 0);
+        inner.data.shrink_to_fit();
+        Ok(())
+    }
+    pub fn clear(&self) {
+        let mut inner = self.inner.exclusive_access();
+        TMP_FS_USED.fetch_sub(inner.data.len(), Ordering::Relaxed);
+        inner.data.clear();
+        inner.data.shrink_to_fit();
+    }
+    pub fn mode(&self) -> u16 {
+        self.inner.exclusive_access().mode
+    }
+    pub fn set_mode(&self, mode: u16) {
+        self.inner.exclusive_access().mode = mode;
+    }
+    pub fn stat(&self) -> Stat {
+        let inner = self.inner.exclusive_access();
+        let mode = StatMode::FILE | StatMode::from_bits_truncate(inner.mode as u32);
+        Stat::new(self.ino, mode, inner.nlink)
+    }
+}
+
+impl Drop for RamInode {
+    fn drop(&mut self) {
+        TMP_FS_USED.fetch_sub(self.inner.exclusive_access().data.len(), Ordering::Relaxed);
+    }
+}
+
+/// The only directory of the tmpfs, it is lost on reboot.
+pub struct TmpFs {
+    next_ino: u64,
+    files: BTreeMap<String, Arc<RamInode>>,
+}
+
+lazy_static! {
+    static ref TMP_FS: UPIntrFreeCell<TmpFs> = unsafe {
+        UPIntrFreeCell::new(TmpFs {
+            next_ino: 1,
+            files: BTreeMap::new(),
+        })
+    };
+}
+
+pub fn tmp_find(name: &str) -> Option<Arc<RamInode>> {
+    TMP_FS.exclusive_access().files.get(name).cloned()
+}
+
+/// Create `name`, return None if it exists already or is not a plain name.
+pub fn tmp_create(name: &str) -> Option<Arc<RamInode>> {
+    let mut fs = TMP_FS.exclusive_access();
+    if name.is_empty() || name.contains('/') || fs.files.contains_key(name) {
+        return None;
+    }
+    let inode = Arc::new(RamInode::new(fs.next_ino));
+    fs.next_ino += 1;
+    fs.files.insert(String::from(name), inode.clone());
+    Some(inode)
+}
+
+/// Create `new_name` as a hard link to `old_name`.
+pub fn tmp_link(old_name: &str, new_name: &str) -> Option<()> {
+    let mut fs = TMP_FS.exclusive_access();
+    if new_name.is_empty() || new_name.contains('/') || fs.files.contains_key(new_name) {
+        return None;
+    }
+    let inode = fs.files.get(old_name)?.clone();
+    inode.inner.exclusive_access().nlink += 1;
+    fs.files.insert(String::from(new_name), inode);
+    Some(())
+}
+
+/// Remove `name`, open files keep the contents until they are closed.
+pub fn tmp_unlink(name: &str) -> Option<()> {
+    let inode = TMP_FS.exclusive_access().files.remove(name)?;
+    inode.inner.exclusive_access().nlink -= 1;
+    Some(())
+}
+
+pub fn open_tmp_file(name: &str, flags: OpenFlags, umask: u16) -> Option<Arc<RamFile>> {
+    let (readable, writable) = flags.read_write();
+    if let Some(inode) = tmp_find(name) {
+        if flags.contains(OpenFlags::CREATE | OpenFlags::EXCL) {
+            // the file must not exist yet
+            return None;
+        }
+        if flags.contains(OpenFlags::TRUNC) {
+            inode.clear();
+        }
+        Some(Arc::new(RamFile::new(readable, writable, inode)))
+    } else if flags.contains(OpenFlags::CREATE) {
+        tmp_create(name).map(|inode| {
+            inode.set_mode(inode.mode() & !umask);
+            Arc::new(RamFile::new(readable, writable, inode))
+        })
+    } else {
+        None
+    }
+}
+
+/// An open file of the tmpfs, the counterpart of `OSInode`.
+pub struct RamFile {
+    readable: bool,
+    writable: bool,
+    inner: UPIntrFreeCell<RamFileInner>,
+}
+
+pub struct RamFileInner {
+    offset: usize,
+    inode: Arc<RamInode>,
+}
+
+impl RamFile {
+    pub fn new(readable: bool, writable: bool, inode: Arc<RamInode>) -> Self {
+        Self {
+            readable,
+            writable,
+            inner: unsafe { UPIntrFreeCell::new(RamFileInner { offset: 0, inode }) },
+        }
+    }
+}
+
+impl File for RamFile {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+    fn writable(&self) -> bool {
+        self.writable
+    }
+    fn read(&self, mut buf: UserBuffer) -> Result<usize, Errno> {
+        let mut inner = self.inner.exclusive_access();
+        let mut total_read_size = 0usize;
+        for slice in buf.buffers.iter_mut() {
+            let read_size = inner.inode.read_at(inner.offset, slice);
+            if read_size == 0 {
+                break;
+            }
+            inner.offset += read_size;
+            total_read_size += read_size;
+        }
+        Ok(total_read_size)
+    }
+    fn write(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        let mut inner = self.inner.exclusive_access();
+        let mut total_write_size = 0usize;
+        for slice in buf.buffers.iter() {
+            let write_size = inner.inode.write_at(inner.offset, slice);
+            inner.offset += write_size;
+            total_write_size += write_size;
+            // the tmpfs is full
+            if write_size < slice.len() {
+                break;
+            }
+        }
+        Ok(total_write_size)
+    }
+    fn is_regular(&self) -> bool {
+        true
+    }
+    fn stat(&self) -> Option<Stat> {
+        Some(self.inner.exclusive_access().inode.stat())
+    }
+    fn truncate(&self, len: usize) -> Result<(), Errno> {
+        self.inner.exclusive_access().inode.truncate(len)
+    }
+    /// There is no disk behind a RAM file, nothing to write back.
+    fn sync(&self) -> Option<()> {
+        Some(())
+    }
+}
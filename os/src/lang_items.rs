@@ -1,3 +1,4 @@
+use crate::console::console_flush;
 use crate::sbi::shutdown;
 use crate::task::current_kstack_top;
 use core::arch::asm;
@@ -5,6 +6,7 @@ use core::panic::PanicInfo;
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
+    console_flush();
     if let Some(location) = info.location() {
         println!(
             "[kernel] Panicked at {}:{} {}",
@@ -18,6 +20,7 @@ fn panic(info: &PanicInfo) -> ! {
     unsafe {
         backtrace();
     }
+    console_flush();
     shutdown(255)
 }
 
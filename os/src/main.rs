@@ -20,6 +20,7 @@ mod drivers;
 mod fs;
 mod lang_items;
 mod mm;
+mod random;
 mod sbi;
 mod sync;
 mod syscall;
@@ -27,9 +28,6 @@ mod task;
 mod timer;
 mod trap;
 
-use crate::drivers::chardev::CharDevice;
-use crate::drivers::chardev::UART;
-
 core::arch::global_asm!(include_str!("entry.asm"));
 
 fn clear_bss() {
@@ -55,7 +53,9 @@ lazy_static! {
 pub fn rust_main() -> ! {
     clear_bss();
     mm::init();
-    UART.init();
+    #[cfg(feature = "mm_test")]
+    mm::test();
+    console::init();
     println!("KERN: init gpu");
     let _gpu = GPU_DEVICE.clone();
     println!("KERN: init keyboard");
@@ -67,6 +67,7 @@ pub fn rust_main() -> ! {
     trap::enable_timer_interrupt();
     timer::set_next_trigger();
     board::device_init();
+    drivers::rtc::init();
     fs::list_apps();
     task::add_initproc();
     *DEV_NON_BLOCKING_ACCESS.exclusive_access() = true;
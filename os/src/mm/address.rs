@@ -3,7 +3,7 @@ use crate::config::{PAGE_SIZE, PAGE_SIZE_BITS};
 use core::fmt::{self, Debug, Formatter};
 
 const PA_WIDTH_SV39: usize = 56;
-const VA_WIDTH_SV39: usize = 39;
+pub const VA_WIDTH_SV39: usize = 39;
 const PPN_WIDTH_SV39: usize = PA_WIDTH_SV39 - PAGE_SIZE_BITS;
 const VPN_WIDTH_SV39: usize = VA_WIDTH_SV39 - PAGE_SIZE_BITS;
 
@@ -218,6 +218,13 @@ where
     pub fn get_end(&self) -> T {
         self.r
     }
+    /// `[x, x)` contains nothing and iterates zero times.
+    pub fn is_empty(&self) -> bool {
+        self.l == self.r
+    }
+    pub fn contains(&self, value: T) -> bool {
+        self.l <= value && value < self.r
+    }
 }
 impl<T> IntoIterator for SimpleRange<T>
 where
@@ -250,7 +257,8 @@ where
 {
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current == self.end {
+        // `>=` so that a reversed `SimpleRangeIterator::new` yields nothing
+        if self.current >= self.end {
             None
         } else {
             let t = self.current;
@@ -260,3 +268,26 @@ where
     }
 }
 pub type VPNRange = SimpleRange<VirtPageNum>;
+
+#[allow(unused)]
+pub fn range_test() {
+    let vpn = VirtPageNum::from(0x10);
+    let empty = VPNRange::new(vpn, vpn);
+    assert!(empty.is_empty());
+    assert!(!empty.contains(vpn));
+    assert_eq!(empty.into_iter().count(), 0);
+    let range = VPNRange::new(vpn, VirtPageNum::from(0x12));
+    assert!(!range.is_empty());
+    assert!(range.contains(vpn));
+    assert!(!range.contains(range.get_end()));
+    assert_eq!(range.into_iter().count(), 2);
+    // a reversed iterator stops right away instead of running off
+    assert_eq!(SimpleRangeIterator::new(range.get_end(), vpn).count(), 0);
+    // `[start, start + 0)` at a page boundary covers no page
+    let va = VirtAddr::from(0x10000);
+    assert!(VPNRange::new(va.floor(), va.ceil()).is_empty());
+    // while a byte inside a page covers that page
+    let va = VirtAddr::from(0x10800);
+    assert_eq!(VPNRange::new(va.floor(), va.ceil()).into_iter().count(), 1);
+    println!("range_test passed!");
+}
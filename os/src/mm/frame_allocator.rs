@@ -10,6 +10,9 @@ pub struct FrameTracker {
 }
 
 impl FrameTracker {
+    /// Frames are cleared here at allocation time rather than in `dealloc`,
+    /// which also covers never-touched frames in `current..end` that may hold
+    /// garbage left in RAM. Every frame from `frame_alloc` is thus all zero.
     pub fn new(ppn: PhysPageNum) -> Self {
         // page cleaning
         let bytes_array = ppn.get_bytes_array();
@@ -35,45 +38,84 @@ impl Drop for FrameTracker {
 trait FrameAllocator {
     fn new() -> Self;
     fn alloc(&mut self) -> Option<PhysPageNum>;
+    /// Return the first of `pages` physically consecutive frames.
+    fn alloc_contiguous(&mut self, pages: usize) -> Option<PhysPageNum>;
     fn dealloc(&mut self, ppn: PhysPageNum);
 }
 
 pub struct StackFrameAllocator {
+    start: usize,
     current: usize,
     end: usize,
     recycled: Vec<usize>,
+    /// frames handed out since boot, freed ones included
+    allocated: usize,
 }
 
 impl StackFrameAllocator {
     pub fn init(&mut self, l: PhysPageNum, r: PhysPageNum) {
+        self.start = l.0;
         self.current = l.0;
         self.end = r.0;
         // println!("last {} Physical Frames.", self.end - self.current);
     }
+    pub fn total_count(&self) -> usize {
+        self.end - self.start
+    }
+    pub fn free_count(&self) -> usize {
+        self.end - self.current + self.recycled.len()
+    }
+    /// Why `ppn` can not be freed, `None` if it can. A frame below `start`
+    /// belongs to the kernel image and must never reach the pool.
+    fn dealloc_error(&self, ppn: usize) -> Option<&'static str> {
+        if ppn < self.start {
+            Some("is below the managed region")
+        } else if ppn >= self.current {
+            Some("has not been allocated")
+        } else if self.recycled.iter().any(|&v| v == ppn) {
+            Some("is freed twice")
+        } else {
+            None
+        }
+    }
 }
 impl FrameAllocator for StackFrameAllocator {
     fn new() -> Self {
         Self {
+            start: 0,
             current: 0,
             end: 0,
             recycled: Vec::new(),
+            allocated: 0,
         }
     }
     fn alloc(&mut self) -> Option<PhysPageNum> {
-        if let Some(ppn) = self.recycled.pop() {
-            Some(ppn.into())
+        let ppn = if let Some(ppn) = self.recycled.pop() {
+            ppn
         } else if self.current == self.end {
-            None
+            return None;
         } else {
             self.current += 1;
-            Some((self.current - 1).into())
+            self.current - 1
+        };
+        self.allocated += 1;
+        Some(ppn.into())
+    }
+    /// Only `current..end` is known to be one run, `recycled` is ignored here.
+    fn alloc_contiguous(&mut self, pages: usize) -> Option<PhysPageNum> {
+        if pages == 0 || self.end - self.current < pages {
+            None
+        } else {
+            self.current += pages;
+            self.allocated += pages;
+            Some((self.current - pages).into())
         }
     }
     fn dealloc(&mut self, ppn: PhysPageNum) {
         let ppn = ppn.0;
         // validity check
-        if ppn >= self.current || self.recycled.iter().any(|&v| v == ppn) {
-            panic!("Frame ppn={:#x} has not been allocated!", ppn);
+        if let Some(error) = self.dealloc_error(ppn) {
+            panic!("Frame ppn={:#x} {}!", ppn, error);
         }
         // recycle
         self.recycled.push(ppn);
@@ -97,31 +139,121 @@ pub fn init_frame_allocator() {
     );
 }
 
+/// Called with a number of wanted frames when the allocator runs dry,
+/// returns how many frames it gave back.
+type ReclaimHook = fn(usize) -> usize;
+
+lazy_static! {
+    static ref RECLAIM_HOOK: UPIntrFreeCell<Option<ReclaimHook>> =
+        unsafe { UPIntrFreeCell::new(None) };
+}
+
+/// Frames asked from the reclaim hook at once, to not sweep for every frame.
+const RECLAIM_BATCH: usize = 16;
+
+pub fn set_reclaim_hook(hook: ReclaimHook) {
+    *RECLAIM_HOOK.exclusive_access() = Some(hook);
+}
+
 pub fn frame_alloc() -> Option<FrameTracker> {
+    if let Some(ppn) = FRAME_ALLOCATOR.exclusive_access().alloc() {
+        return Some(FrameTracker::new(ppn));
+    }
+    // the hook frees frames, so the allocator must not be borrowed here
+    let reclaim = (*RECLAIM_HOOK.exclusive_access())?;
+    if reclaim(RECLAIM_BATCH) == 0 {
+        return None;
+    }
     FRAME_ALLOCATOR
         .exclusive_access()
         .alloc()
         .map(FrameTracker::new)
 }
 
+lazy_static! {
+    /// All-zero frame shared read-only by untouched anonymous pages.
+    /// Being owned by a static, it is never given back to the allocator.
+    static ref ZERO_FRAME: FrameTracker = frame_alloc().expect("no frame for the zero page");
+}
+
+pub fn zero_frame() -> PhysPageNum {
+    ZERO_FRAME.ppn
+}
+
+/// Allocate `pages` frames with consecutive ppns, e.g. for DMA buffers.
+pub fn frame_alloc_contiguous(pages: usize) -> Option<Vec<FrameTracker>> {
+    let ppn_base = FRAME_ALLOCATOR.exclusive_access().alloc_contiguous(pages)?;
+    Some(
+        (0..pages)
+            .map(|i| FrameTracker::new(PhysPageNum(ppn_base.0 + i)))
+            .collect(),
+    )
+}
+
 pub fn frame_dealloc(ppn: PhysPageNum) {
     FRAME_ALLOCATOR.exclusive_access().dealloc(ppn);
 }
 
+/// Return (total, free) number of physical frames.
+pub fn frame_stats() -> (usize, usize) {
+    let allocator = FRAME_ALLOCATOR.exclusive_access();
+    (allocator.total_count(), allocator.free_count())
+}
+
+/// Number of frames allocated since boot, however many were freed since.
+pub fn frames_allocated() -> usize {
+    FRAME_ALLOCATOR.exclusive_access().allocated
+}
+
 #[allow(unused)]
 pub fn frame_allocator_test() {
     let mut v: Vec<FrameTracker> = Vec::new();
     for i in 0..5 {
         let frame = frame_alloc().unwrap();
         println!("{:?}", frame);
+        // never-recycled frames are zeroed as well
+        assert!(frame.ppn.get_bytes_array().iter().all(|b| *b == 0));
+        frame.ppn.get_bytes_array().fill(0xff);
         v.push(frame);
     }
     v.clear();
     for i in 0..5 {
         let frame = frame_alloc().unwrap();
         println!("{:?}", frame);
+        // recycled frames do not leak data of their previous owner
+        assert!(frame.ppn.get_bytes_array().iter().all(|b| *b == 0));
         v.push(frame);
     }
+    // the recycled frames above are not consecutive, but this still is
+    let frames = frame_alloc_contiguous(4).unwrap();
+    for (i, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.ppn.0, frames[0].ppn.0 + i);
+    }
+    drop(frames);
     drop(v);
     println!("frame_allocator_test passed!");
 }
+
+/// Frees that would poison the pool are refused by `dealloc`, which panics.
+#[allow(unused)]
+pub fn frame_dealloc_guard_test() {
+    let frame = frame_alloc().unwrap();
+    let allocator = FRAME_ALLOCATOR.exclusive_access();
+    assert_eq!(
+        allocator.dealloc_error(allocator.start - 1),
+        Some("is below the managed region")
+    );
+    assert_eq!(
+        allocator.dealloc_error(allocator.end),
+        Some("has not been allocated")
+    );
+    assert_eq!(allocator.dealloc_error(frame.ppn.0), None);
+    drop(allocator);
+    let ppn = frame.ppn.0;
+    drop(frame);
+    assert_eq!(
+        FRAME_ALLOCATOR.exclusive_access().dealloc_error(ppn),
+        Some("is freed twice")
+    );
+    println!("frame_dealloc_guard_test passed!");
+}
@@ -1,13 +1,15 @@
-use super::{frame_alloc, FrameTracker};
+use super::{frame_alloc, frame_stats, zero_frame, FrameTracker};
 use super::{PTEFlags, PageTable, PageTableEntry};
 use super::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
 use super::{StepByOne, VPNRange};
-use crate::config::{MEMORY_END, MMIO, PAGE_SIZE, TRAMPOLINE};
+use crate::config::{MEMORY_END, MMIO, PAGE_SIZE, TRAMPOLINE, USER_STACK_RANDOM_PAGES};
+use crate::random::fill_random;
 use crate::sync::UPIntrFreeCell;
 use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::arch::asm;
+use core::sync::atomic::{AtomicBool, Ordering};
 use lazy_static::*;
 use riscv::register::satp;
 
@@ -24,6 +26,25 @@ extern "C" {
     fn strampoline();
 }
 
+static STACK_RANDOMIZED: AtomicBool = AtomicBool::new(false);
+
+/// Turn the randomization of user stack bases on or off for the programs
+/// loaded from now on, return whether it was on.
+pub fn set_stack_randomized(enabled: bool) -> bool {
+    STACK_RANDOMIZED.swap(enabled, Ordering::Relaxed)
+}
+
+/// A whole number of pages below `USER_STACK_RANDOM_PAGES`, or 0 when the
+/// randomization is off.
+fn random_stack_offset() -> usize {
+    if !STACK_RANDOMIZED.load(Ordering::Relaxed) {
+        return 0;
+    }
+    let mut bytes = [0u8; 8];
+    fill_random(&mut bytes);
+    usize::from_le_bytes(bytes) % USER_STACK_RANDOM_PAGES * PAGE_SIZE
+}
+
 lazy_static! {
     pub static ref KERNEL_SPACE: Arc<UPIntrFreeCell<MemorySet>> =
         Arc::new(unsafe { UPIntrFreeCell::new(MemorySet::new_kernel()) });
@@ -39,26 +60,27 @@ pub struct MemorySet {
 }
 
 impl MemorySet {
-    pub fn new_bare() -> Self {
-        Self {
-            page_table: PageTable::new(),
+    pub fn new_bare() -> Option<Self> {
+        Some(Self {
+            page_table: PageTable::new()?,
             areas: Vec::new(),
-        }
+        })
     }
     pub fn token(&self) -> usize {
         self.page_table.token()
     }
     /// Assume that no conflicts.
+    #[must_use]
     pub fn insert_framed_area(
         &mut self,
         start_va: VirtAddr,
         end_va: VirtAddr,
         permission: MapPermission,
-    ) {
+    ) -> Option<()> {
         self.push(
             MapArea::new(start_va, end_va, MapType::Framed, permission),
             None,
-        );
+        )
     }
     pub fn remove_area_with_start_vpn(&mut self, start_vpn: VirtPageNum) {
         if let Some((idx, area)) = self
@@ -71,29 +93,217 @@ impl MemorySet {
             self.areas.remove(idx);
         }
     }
+    /// Change the permission of the user pages in `[start_vpn, end_vpn)`,
+    /// areas partly covered are split so each keeps a single `map_perm`.
+    /// Return `None` and change nothing if any page there is not a user page.
+    #[must_use]
+    pub fn mprotect(
+        &mut self,
+        start_vpn: VirtPageNum,
+        end_vpn: VirtPageNum,
+        permission: MapPermission,
+    ) -> Option<()> {
+        // an empty range must not split the area around it
+        if start_vpn == end_vpn {
+            return Some(());
+        }
+        if !self.is_covered(start_vpn, end_vpn, |area| {
+            area.map_perm.contains(MapPermission::U)
+        }) {
+            return None;
+        }
+        self.split_areas(start_vpn, end_vpn);
+        for area in self.areas.iter_mut().filter(|area| {
+            start_vpn <= area.vpn_range.get_start() && area.vpn_range.get_end() <= end_vpn
+        }) {
+            area.map_perm = permission;
+            // reclaimed pages have no pte, they get `map_perm` when faulted in
+            for vpn in area.vpn_range {
+                self.page_table.set_flags(vpn, area.pte_flags(vpn));
+            }
+        }
+        unsafe {
+            asm!("sfence.vma");
+        }
+        Some(())
+    }
+    /// Map `[start_vpn, end_vpn)` as anonymous memory with `permission`.
+    /// A `shared` mapping keeps its frames across fork, see `MapArea::shared`.
+    /// The caller makes sure that nothing is mapped there yet.
+    #[must_use]
+    pub fn mmap(
+        &mut self,
+        start_vpn: VirtPageNum,
+        end_vpn: VirtPageNum,
+        permission: MapPermission,
+        shared: bool,
+    ) -> Option<()> {
+        let mut area = MapArea::new(
+            start_vpn.into(),
+            end_vpn.into(),
+            MapType::Anonymous,
+            permission,
+        );
+        area.shared = shared;
+        self.push(area, None)
+    }
+    /// Unmap `[start_vpn, end_vpn)`, areas partly covered are split.
+    /// Return `None` and change nothing if any page there was not mapped
+    /// by `mmap`.
+    #[must_use]
+    pub fn munmap(&mut self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> Option<()> {
+        if !self.is_covered(start_vpn, end_vpn, |area| {
+            area.map_type == MapType::Anonymous
+        }) {
+            return None;
+        }
+        self.split_areas(start_vpn, end_vpn);
+        let page_table = &mut self.page_table;
+        self.areas.retain_mut(|area| {
+            let inside =
+                start_vpn <= area.vpn_range.get_start() && area.vpn_range.get_end() <= end_vpn;
+            if inside {
+                area.unmap(page_table);
+            }
+            !inside
+        });
+        unsafe {
+            asm!("sfence.vma");
+        }
+        Some(())
+    }
+    /// Give back the frames of the written pages in `[start_vpn, end_vpn)`,
+    /// which read as zero again until the next write. The areas stay.
+    /// Return `None` and change nothing if any page there was not mapped
+    /// by a private `mmap`, otherwise the number of freed frames.
+    #[must_use]
+    pub fn madvise_dontneed(
+        &mut self,
+        start_vpn: VirtPageNum,
+        end_vpn: VirtPageNum,
+    ) -> Option<usize> {
+        let private = |area: &MapArea| area.map_type == MapType::Anonymous && !area.shared;
+        if !self.is_covered(start_vpn, end_vpn, private) {
+            return None;
+        }
+        let mut freed = 0;
+        for area in self.areas.iter_mut().filter(|area| private(area)) {
+            let vpns: Vec<VirtPageNum> = area
+                .data_frames
+                .range(start_vpn..end_vpn)
+                .map(|(vpn, _)| *vpn)
+                .collect();
+            for vpn in vpns {
+                area.drop_frame(&mut self.page_table, vpn);
+                freed += 1;
+            }
+        }
+        unsafe {
+            asm!("sfence.vma");
+        }
+        Some(freed)
+    }
+    /// The start of the highest `pages` free pages in `[start_vpn, end_vpn)`,
+    /// for an `mmap` that leaves the address to the kernel. Searching from
+    /// the top keeps clear of the thread stacks growing above the elf.
+    pub fn find_free_area(
+        &self,
+        pages: usize,
+        start_vpn: VirtPageNum,
+        end_vpn: VirtPageNum,
+    ) -> Option<VirtPageNum> {
+        let mut ranges: Vec<(VirtPageNum, VirtPageNum)> = self
+            .areas
+            .iter()
+            .map(|area| (area.vpn_range.get_start(), area.vpn_range.get_end()))
+            .filter(|(start, end)| *start < end_vpn && start_vpn < *end)
+            .collect();
+        ranges.sort_by(|a, b| b.0.cmp(&a.0));
+        // the gap below `top` is free
+        let mut top = end_vpn;
+        for (start, end) in ranges {
+            if top.0 >= end.0 + pages {
+                return Some(VirtPageNum(top.0 - pages));
+            }
+            top = top.min(start);
+        }
+        top.0
+            .checked_sub(pages)
+            .filter(|start| *start >= start_vpn.0)
+            .map(VirtPageNum)
+    }
+    /// Whether any page in `[start_vpn, end_vpn)` belongs to an area.
+    pub fn overlaps(&self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> bool {
+        self.areas.iter().any(|area| {
+            area.vpn_range.get_start() < end_vpn && start_vpn < area.vpn_range.get_end()
+        })
+    }
+    /// Whether every page in `[start_vpn, end_vpn)` is in an area accepted by `f`.
+    fn is_covered(
+        &self,
+        start_vpn: VirtPageNum,
+        end_vpn: VirtPageNum,
+        f: impl Fn(&MapArea) -> bool,
+    ) -> bool {
+        VPNRange::new(start_vpn, end_vpn).into_iter().all(|vpn| {
+            self.areas
+                .iter()
+                .any(|area| area.vpn_range.contains(vpn) && f(area))
+        })
+    }
+    /// Split the areas crossing `start_vpn` or `end_vpn`, so that each area
+    /// ends up either inside `[start_vpn, end_vpn)` or outside of it.
+    fn split_areas(&mut self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) {
+        let mut idx = 0;
+        while idx < self.areas.len() {
+            let range = self.areas[idx].vpn_range;
+            // the tail is checked against `end_vpn` in the next round
+            if let Some(at) = [start_vpn, end_vpn]
+                .into_iter()
+                .find(|at| range.get_start() < *at && *at < range.get_end())
+            {
+                let tail = self.areas[idx].split_off(at);
+                self.areas.insert(idx + 1, tail);
+            }
+            idx += 1;
+        }
+    }
     /// Add a new MapArea into this MemorySet.
     /// Assuming that there are no conflicts in the virtual address
     /// space.
-    pub fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) {
-        map_area.map(&mut self.page_table);
+    /// Return `None` and leave this MemorySet unchanged when out of frames.
+    #[must_use]
+    pub fn push(&mut self, map_area: MapArea, data: Option<&[u8]>) -> Option<()> {
+        self.push_with_offset(map_area, 0, data)
+    }
+    /// Like `push`, but `data` starts `offset` bytes into the first page.
+    #[must_use]
+    pub fn push_with_offset(
+        &mut self,
+        mut map_area: MapArea,
+        offset: usize,
+        data: Option<&[u8]>,
+    ) -> Option<()> {
+        map_area.map(&mut self.page_table)?;
         if let Some(data) = data {
-            map_area.copy_data(&mut self.page_table, data);
+            map_area.copy_data(&mut self.page_table, offset, data);
         }
         self.areas.push(map_area);
+        Some(())
     }
     /// Mention that trampoline is not collected by areas.
-    fn map_trampoline(&mut self) {
+    fn map_trampoline(&mut self) -> Option<()> {
         self.page_table.map(
             VirtAddr::from(TRAMPOLINE).into(),
             PhysAddr::from(strampoline as usize).into(),
             PTEFlags::R | PTEFlags::X,
-        );
+        )
     }
     /// Without kernel stacks.
     pub fn new_kernel() -> Self {
-        let mut memory_set = Self::new_bare();
+        let mut memory_set = Self::new_bare().unwrap();
         // map trampoline
-        memory_set.map_trampoline();
+        memory_set.map_trampoline().unwrap();
         // map kernel sections
         // println!(".text [{:#x}, {:#x})", stext as usize, etext as usize);
         // println!(".rodata [{:#x}, {:#x})", srodata as usize, erodata as usize);
@@ -111,7 +321,8 @@ impl MemorySet {
                 MapPermission::R | MapPermission::X,
             ),
             None,
-        );
+        )
+        .unwrap();
         // println!("mapping .rodata section");
         memory_set.push(
             MapArea::new(
@@ -121,7 +332,8 @@ impl MemorySet {
                 MapPermission::R,
             ),
             None,
-        );
+        )
+        .unwrap();
         // println!("mapping .data section");
         memory_set.push(
             MapArea::new(
@@ -131,7 +343,8 @@ impl MemorySet {
                 MapPermission::R | MapPermission::W,
             ),
             None,
-        );
+        )
+        .unwrap();
         // println!("mapping .bss section");
         memory_set.push(
             MapArea::new(
@@ -141,7 +354,8 @@ impl MemorySet {
                 MapPermission::R | MapPermission::W,
             ),
             None,
-        );
+        )
+        .unwrap();
         // println!("mapping physical memory");
         memory_set.push(
             MapArea::new(
@@ -151,7 +365,8 @@ impl MemorySet {
                 MapPermission::R | MapPermission::W,
             ),
             None,
-        );
+        )
+        .unwrap();
         //println!("mapping memory-mapped registers");
         for pair in MMIO {
             memory_set.push(
@@ -162,16 +377,47 @@ impl MemorySet {
                     MapPermission::R | MapPermission::W,
                 ),
                 None,
-            );
+            )
+            .unwrap();
         }
         memory_set
     }
+    /// Whether `elf_data` looks like an elf `from_elf` can load: the magic
+    /// is there, the headers parse and every loaded segment lies inside the
+    /// file and inside its own memory size.
+    pub fn is_elf(elf_data: &[u8]) -> bool {
+        if !elf_data.starts_with(&[0x7f, 0x45, 0x4c, 0x46]) {
+            return false;
+        }
+        let elf = match xmas_elf::ElfFile::new(elf_data) {
+            Ok(elf) => elf,
+            Err(_) => return false,
+        };
+        (0..elf.header.pt2.ph_count()).all(|i| {
+            let ph = match elf.program_header(i) {
+                Ok(ph) => ph,
+                Err(_) => return false,
+            };
+            match ph.get_type() {
+                Ok(xmas_elf::program::Type::Load) => {
+                    let file_end = ph.offset().checked_add(ph.file_size());
+                    ph.file_size() <= ph.mem_size()
+                        && file_end.map_or(false, |end| end <= elf_data.len() as u64)
+                        && ph.virtual_addr().checked_add(ph.mem_size()).is_some()
+                }
+                Ok(_) => true,
+                Err(_) => false,
+            }
+        })
+    }
     /// Include sections in elf and trampoline,
     /// also returns user_sp_base and entry point.
-    pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize) {
-        let mut memory_set = Self::new_bare();
+    /// Return `None` when out of frames.
+    /// `elf_data` must have passed `is_elf`.
+    pub fn from_elf(elf_data: &[u8]) -> Option<(Self, usize, usize)> {
+        let mut memory_set = Self::new_bare()?;
         // map trampoline
-        memory_set.map_trampoline();
+        memory_set.map_trampoline()?;
         // map program headers of elf, with U flag
         let elf = xmas_elf::ElfFile::new(elf_data).unwrap();
         let elf_header = elf.header;
@@ -197,39 +443,69 @@ impl MemorySet {
                 }
                 let map_area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
                 max_end_vpn = map_area.vpn_range.get_end();
-                memory_set.push(
+                memory_set.push_with_offset(
                     map_area,
+                    ph.virtual_addr() as usize % PAGE_SIZE,
                     Some(&elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize]),
-                );
+                )?;
             }
         }
         let max_end_va: VirtAddr = max_end_vpn.into();
         let mut user_stack_base: usize = max_end_va.into();
-        user_stack_base += PAGE_SIZE;
-        (
+        // the guard page stays below the stack, however far it is moved up
+        user_stack_base += PAGE_SIZE + random_stack_offset();
+        Some((
             memory_set,
             user_stack_base,
             elf.header.pt2.entry_point() as usize,
-        )
+        ))
     }
-    pub fn from_existed_user(user_space: &MemorySet) -> MemorySet {
-        let mut memory_set = Self::new_bare();
+    /// Return `None` when out of frames.
+    pub fn from_existed_user(user_space: &MemorySet) -> Option<MemorySet> {
+        let mut memory_set = Self::new_bare()?;
         // map trampoline
-        memory_set.map_trampoline();
+        memory_set.map_trampoline()?;
         // copy data sections/trap_context/user_stack
         for area in user_space.areas.iter() {
+            if area.shared {
+                memory_set.share_area(area)?;
+                continue;
+            }
             let new_area = MapArea::from_another(area);
-            memory_set.push(new_area, None);
+            memory_set.push(new_area, None)?;
             // copy data from another space
             for vpn in area.vpn_range {
-                let src_ppn = user_space.translate(vpn).unwrap().ppn();
-                let dst_ppn = memory_set.translate(vpn).unwrap().ppn();
+                // a reclaimed page is all zero, just as the new frame
+                let src_ppn = match user_space.translate(vpn).filter(|pte| pte.is_valid()) {
+                    Some(pte) => pte.ppn(),
+                    None => continue,
+                };
+                // an untouched anonymous page stays on the zero frame
+                if src_ppn == zero_frame() {
+                    continue;
+                }
+                let mut dst_ppn = memory_set.translate(vpn).unwrap().ppn();
+                if dst_ppn == zero_frame() {
+                    memory_set.fault_in(vpn)?;
+                    dst_ppn = memory_set.translate(vpn).unwrap().ppn();
+                }
                 dst_ppn
                     .get_bytes_array()
                     .copy_from_slice(src_ppn.get_bytes_array());
             }
         }
-        memory_set
+        Some(memory_set)
+    }
+    /// Map the frames of the shared `area` of another space here as well,
+    /// so that writes on either side are seen by the other.
+    fn share_area(&mut self, area: &MapArea) -> Option<()> {
+        let mut new_area = MapArea::from_another(area);
+        for (vpn, frame) in area.data_frames.iter() {
+            self.page_table.map(*vpn, frame.ppn, area.pte_flags(*vpn))?;
+            new_area.data_frames.insert(*vpn, Arc::clone(frame));
+        }
+        self.areas.push(new_area);
+        Some(())
     }
     pub fn activate(&self) {
         let satp = self.page_table.token();
@@ -241,6 +517,50 @@ impl MemorySet {
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
         self.page_table.translate(vpn)
     }
+    /// One sweep of the clock over the user pages: free at most `budget`
+    /// all-zero pages not accessed since the last sweep, and clear the
+    /// accessed bit of the others. Return the number of freed frames.
+    /// A freed page reads as zero again once `fault_in` maps it back.
+    pub fn reclaim_zero_pages(&mut self, budget: usize) -> usize {
+        let mut freed = 0;
+        for area in self.areas.iter_mut().filter(|area| area.is_reclaimable()) {
+            let vpns: Vec<VirtPageNum> = area.data_frames.keys().copied().collect();
+            for vpn in vpns {
+                if freed == budget {
+                    return freed;
+                }
+                if self.page_table.take_accessed(vpn) {
+                    continue;
+                }
+                let frame = &area.data_frames[&vpn];
+                if frame.ppn.get_bytes_array().iter().all(|b| *b == 0) {
+                    area.unmap_one(&mut self.page_table, vpn);
+                    freed += 1;
+                }
+            }
+        }
+        freed
+    }
+    /// Give `vpn` a frame of its own: map back a page freed by
+    /// `reclaim_zero_pages`, or copy an anonymous page off the zero frame.
+    /// Return `None` if `vpn` is neither or when out of frames.
+    #[must_use]
+    pub fn fault_in(&mut self, vpn: VirtPageNum) -> Option<()> {
+        let area = self.areas.iter_mut().find(|area| {
+            (area.is_reclaimable() || area.map_type == MapType::Anonymous)
+                && area.vpn_range.contains(vpn)
+                && !area.data_frames.contains_key(&vpn)
+        })?;
+        if area.map_type == MapType::Anonymous {
+            area.own_frame(&mut self.page_table, vpn)?;
+        } else {
+            area.map_one(&mut self.page_table, vpn)?;
+        }
+        unsafe {
+            asm!("sfence.vma");
+        }
+        Some(())
+    }
     pub fn recycle_data_pages(&mut self) {
         //*self = Self::new_bare();
         self.areas.clear();
@@ -249,9 +569,13 @@ impl MemorySet {
 
 pub struct MapArea {
     vpn_range: VPNRange,
-    data_frames: BTreeMap<VirtPageNum, FrameTracker>,
+    /// a frame is freed once no area of any process holds it any more
+    data_frames: BTreeMap<VirtPageNum, Arc<FrameTracker>>,
     map_type: MapType,
     map_perm: MapPermission,
+    /// an anonymous area whose frames a forked child maps as well instead
+    /// of copying them, every page gets its frame right away for this
+    shared: bool,
 }
 
 impl MapArea {
@@ -268,6 +592,7 @@ impl MapArea {
             data_frames: BTreeMap::new(),
             map_type,
             map_perm,
+            shared: false,
         }
     }
     pub fn from_another(another: &MapArea) -> Self {
@@ -276,18 +601,42 @@ impl MapArea {
             data_frames: BTreeMap::new(),
             map_type: another.map_type,
             map_perm: another.map_perm,
+            shared: another.shared,
+        }
+    }
+    /// Cut this area at `at`, return the part `[at, end)` with its frames.
+    pub fn split_off(&mut self, at: VirtPageNum) -> Self {
+        let (start, end) = (self.vpn_range.get_start(), self.vpn_range.get_end());
+        assert!(start < at && at < end);
+        self.vpn_range = VPNRange::new(start, at);
+        Self {
+            vpn_range: VPNRange::new(at, end),
+            data_frames: self.data_frames.split_off(&at),
+            map_type: self.map_type,
+            map_perm: self.map_perm,
+            shared: self.shared,
         }
     }
-    pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+    pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> Option<()> {
         let ppn: PhysPageNum;
+        let mut frame = None;
         match self.map_type {
             MapType::Identical => {
                 ppn = PhysPageNum(vpn.0);
             }
             MapType::Framed => {
-                let frame = frame_alloc().unwrap();
-                ppn = frame.ppn;
-                self.data_frames.insert(vpn, frame);
+                let data_frame = frame_alloc()?;
+                ppn = data_frame.ppn;
+                frame = Some(data_frame);
+            }
+            // the zero frame can not be shared, a write would move one side off it
+            MapType::Anonymous if self.shared => {
+                let data_frame = frame_alloc()?;
+                ppn = data_frame.ppn;
+                frame = Some(data_frame);
+            }
+            MapType::Anonymous => {
+                ppn = zero_frame();
             }
             MapType::Linear(pn_offset) => {
                 // check for sv39
@@ -295,45 +644,93 @@ impl MapArea {
                 ppn = PhysPageNum((vpn.0 as isize + pn_offset) as usize);
             }
         }
-        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
-        page_table.map(vpn, ppn, pte_flags);
+        // the frame goes in first, `pte_flags` only makes an owned page writable
+        if let Some(frame) = frame {
+            self.data_frames.insert(vpn, Arc::new(frame));
+        }
+        if page_table.map(vpn, ppn, self.pte_flags(vpn)).is_none() {
+            self.data_frames.remove(&vpn);
+            return None;
+        }
+        Some(())
     }
     pub fn unmap_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
-        if self.map_type == MapType::Framed {
+        // a reclaimed page has neither a frame nor a pte left
+        if self.map_type == MapType::Framed && self.data_frames.remove(&vpn).is_none() {
+            return;
+        }
+        if self.map_type == MapType::Anonymous {
             self.data_frames.remove(&vpn);
         }
         page_table.unmap(vpn);
     }
-    pub fn map(&mut self, page_table: &mut PageTable) {
+    /// Flags of the pte of `vpn`, an anonymous page on the shared zero frame
+    /// is never writable.
+    fn pte_flags(&self, vpn: VirtPageNum) -> PTEFlags {
+        let mut map_perm = self.map_perm;
+        if self.map_type == MapType::Anonymous && !self.data_frames.contains_key(&vpn) {
+            map_perm.remove(MapPermission::W);
+        }
+        PTEFlags::from_bits(map_perm.bits).unwrap()
+    }
+    /// Move an anonymous page from the zero frame to a new zeroed frame.
+    fn own_frame(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> Option<()> {
+        let frame = frame_alloc()?;
+        page_table.unmap(vpn);
+        self.data_frames.insert(vpn, Arc::new(frame));
+        page_table
+            .map(vpn, self.data_frames[&vpn].ppn, self.pte_flags(vpn))
+            .unwrap();
+        Some(())
+    }
+    /// Move an anonymous page back to the zero frame, freeing its own frame.
+    fn drop_frame(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+        self.data_frames.remove(&vpn);
+        page_table.unmap(vpn);
+        page_table
+            .map(vpn, zero_frame(), self.pte_flags(vpn))
+            .unwrap();
+    }
+    /// Only framed user pages can be given back under memory pressure.
+    fn is_reclaimable(&self) -> bool {
+        self.map_type == MapType::Framed && self.map_perm.contains(MapPermission::U)
+    }
+    pub fn map(&mut self, page_table: &mut PageTable) -> Option<()> {
         for vpn in self.vpn_range {
-            self.map_one(page_table, vpn);
+            if self.map_one(page_table, vpn).is_none() {
+                // roll back, so that no pte is left pointing to a freed frame
+                for mapped_vpn in VPNRange::new(self.vpn_range.get_start(), vpn) {
+                    self.unmap_one(page_table, mapped_vpn);
+                }
+                return None;
+            }
         }
+        Some(())
     }
     pub fn unmap(&mut self, page_table: &mut PageTable) {
         for vpn in self.vpn_range {
             self.unmap_one(page_table, vpn);
         }
     }
-    /// data: start-aligned but maybe with shorter length
-    /// assume that all frames were cleared before
-    pub fn copy_data(&mut self, page_table: &mut PageTable, data: &[u8]) {
+    /// data: starts `offset` bytes into the first page, maybe with shorter length
+    /// everything else in the area is the .bss part of an elf segment, it is
+    /// cleared here as well instead of relying on frames being zeroed
+    pub fn copy_data(&mut self, page_table: &mut PageTable, offset: usize, data: &[u8]) {
         assert_eq!(self.map_type, MapType::Framed);
+        assert!(offset < PAGE_SIZE);
         let mut start: usize = 0;
-        let mut current_vpn = self.vpn_range.get_start();
+        let mut page_offset = offset;
         let len = data.len();
-        loop {
-            let src = &data[start..len.min(start + PAGE_SIZE)];
-            let dst = &mut page_table
-                .translate(current_vpn)
-                .unwrap()
-                .ppn()
-                .get_bytes_array()[..src.len()];
+        for vpn in self.vpn_range {
+            let src = &data[start.min(len)..len.min(start + PAGE_SIZE - page_offset)];
+            let page = page_table.translate(vpn).unwrap().ppn().get_bytes_array();
+            let (head, rest) = page.split_at_mut(page_offset);
+            head.fill(0);
+            let (dst, tail) = rest.split_at_mut(src.len());
             dst.copy_from_slice(src);
-            start += PAGE_SIZE;
-            if start >= len {
-                break;
-            }
-            current_vpn.step();
+            tail.fill(0);
+            start += PAGE_SIZE - page_offset;
+            page_offset = 0;
         }
     }
 }
@@ -342,6 +739,8 @@ impl MapArea {
 pub enum MapType {
     Identical,
     Framed,
+    /// framed, but a page shares the read-only zero frame until written
+    Anonymous,
     /// offset of page num
     Linear(isize),
 }
@@ -355,6 +754,141 @@ bitflags! {
     }
 }
 
+#[allow(unused)]
+pub fn reclaim_test() {
+    let mut memory_set = MemorySet::new_bare().unwrap();
+    let start_va = VirtAddr::from(0x1000_0000usize);
+    let end_va = VirtAddr::from(0x1000_0000usize + 4 * PAGE_SIZE);
+    memory_set
+        .insert_framed_area(
+            start_va,
+            end_va,
+            MapPermission::R | MapPermission::W | MapPermission::U,
+        )
+        .unwrap();
+    let first_vpn = start_va.floor();
+    let mut second_vpn = first_vpn;
+    second_vpn.step();
+    // a page holding data is never freed
+    memory_set
+        .translate(first_vpn)
+        .unwrap()
+        .ppn()
+        .get_bytes_array()[0] = 1;
+    let mut hoard = Vec::new();
+    while let Some(frame) = frame_alloc() {
+        hoard.push(frame);
+    }
+    assert_eq!(memory_set.reclaim_zero_pages(usize::MAX), 3);
+    assert!(!memory_set.translate(second_vpn).unwrap().is_valid());
+    drop(frame_alloc().unwrap());
+    memory_set.fault_in(second_vpn).unwrap();
+    let page = memory_set.translate(second_vpn).unwrap().ppn();
+    assert!(page.get_bytes_array().iter().all(|b| *b == 0));
+    assert!(memory_set.fault_in(first_vpn).is_none());
+    drop(hoard);
+    println!("reclaim_test passed!");
+}
+
+#[allow(unused)]
+pub fn zero_page_test() {
+    let mut memory_set = MemorySet::new_bare().unwrap();
+    let start_vpn = VirtAddr::from(0x1000_0000usize).floor();
+    let end_vpn = VirtAddr::from(0x1000_0000usize + 256 * PAGE_SIZE).floor();
+    let (_, free_before) = frame_stats();
+    memory_set
+        .mmap(
+            start_vpn,
+            end_vpn,
+            MapPermission::R | MapPermission::W | MapPermission::U,
+            false,
+        )
+        .unwrap();
+    // only page tables are allocated, every page is the read-only zero frame
+    let (_, free_mapped) = frame_stats();
+    assert!(free_before - free_mapped <= 3);
+    for vpn in VPNRange::new(start_vpn, end_vpn) {
+        let pte = memory_set.translate(vpn).unwrap();
+        assert_eq!(pte.ppn(), zero_frame());
+        assert!(pte.readable() && !pte.writable());
+    }
+    // the first write gets a private frame
+    memory_set.fault_in(start_vpn).unwrap();
+    assert_eq!(frame_stats().1, free_mapped - 1);
+    let pte = memory_set.translate(start_vpn).unwrap();
+    assert_ne!(pte.ppn(), zero_frame());
+    assert!(pte.writable());
+    pte.ppn().get_bytes_array()[0] = 1;
+    assert!(zero_frame().get_bytes_array().iter().all(|b| *b == 0));
+    assert!(memory_set.fault_in(start_vpn).is_none());
+    memory_set.munmap(start_vpn, end_vpn).unwrap();
+    assert_eq!(frame_stats().1, free_mapped);
+    println!("zero_page_test passed!");
+}
+
+#[allow(unused)]
+pub fn shared_map_test() {
+    let mut memory_set = MemorySet::new_bare().unwrap();
+    let start_vpn = VirtAddr::from(0x1000_0000usize).floor();
+    let end_vpn = VirtAddr::from(0x1000_0000usize + 4 * PAGE_SIZE).floor();
+    memory_set
+        .mmap(
+            start_vpn,
+            end_vpn,
+            MapPermission::R | MapPermission::W | MapPermission::U,
+            true,
+        )
+        .unwrap();
+    // shared pages own their frames from the start and are writable at once
+    for vpn in VPNRange::new(start_vpn, end_vpn) {
+        let pte = memory_set.translate(vpn).unwrap();
+        assert_ne!(pte.ppn(), zero_frame());
+        assert!(pte.readable() && pte.writable());
+        assert!(memory_set.fault_in(vpn).is_none());
+    }
+    memory_set.munmap(start_vpn, end_vpn).unwrap();
+    println!("shared_map_test passed!");
+}
+
+#[allow(unused)]
+pub fn copy_data_test() {
+    let mut memory_set = MemorySet::new_bare().unwrap();
+    // a segment starting near the end of a page runs into the next one
+    let start = 0x1000_0000usize + PAGE_SIZE - 4;
+    let data: Vec<u8> = (1..=8).collect();
+    // followed by a whole page of .bss
+    let end = start + data.len() + PAGE_SIZE;
+    let map_area = MapArea::new(
+        start.into(),
+        end.into(),
+        MapType::Framed,
+        MapPermission::R | MapPermission::U,
+    );
+    memory_set
+        .push_with_offset(map_area, start % PAGE_SIZE, Some(&data))
+        .unwrap();
+    for (i, byte) in data.iter().enumerate() {
+        let pa = memory_set
+            .page_table
+            .translate_va((start + i).into())
+            .unwrap();
+        assert_eq!(*pa.get_ref::<u8>(), *byte);
+    }
+    for va in start + data.len()..end {
+        let pa = memory_set.page_table.translate_va(va.into()).unwrap();
+        assert_eq!(*pa.get_ref::<u8>(), 0);
+    }
+    // bytes before the segment in its first page are zero
+    let first_page = memory_set
+        .translate(VirtAddr::from(start).floor())
+        .unwrap()
+        .ppn();
+    assert!(first_page.get_bytes_array()[..start % PAGE_SIZE]
+        .iter()
+        .all(|b| *b == 0));
+    println!("copy_data_test passed!");
+}
+
 #[allow(unused)]
 pub fn remap_test() {
     let mut kernel_space = KERNEL_SPACE.exclusive_access();
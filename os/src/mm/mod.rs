@@ -4,19 +4,44 @@ mod heap_allocator;
 mod memory_set;
 mod page_table;
 
-pub use address::VPNRange;
+use crate::config::PAGE_RECLAIM;
+pub use address::{range_test, VPNRange};
 pub use address::{PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
-pub use frame_allocator::{frame_alloc, frame_dealloc, FrameTracker};
-pub use memory_set::remap_test;
-pub use memory_set::{kernel_token, MapArea, MapPermission, MapType, MemorySet, KERNEL_SPACE};
+pub use frame_allocator::frame_dealloc_guard_test;
+pub use frame_allocator::{
+    frame_alloc, frame_alloc_contiguous, frame_dealloc, frame_stats, frames_allocated, zero_frame,
+    FrameTracker,
+};
+pub use memory_set::{copy_data_test, reclaim_test, remap_test, shared_map_test, zero_page_test};
+pub use memory_set::{
+    kernel_token, set_stack_randomized, MapArea, MapPermission, MapType, MemorySet, KERNEL_SPACE,
+};
 use page_table::PTEFlags;
 pub use page_table::{
-    translated_byte_buffer, translated_ref, translated_refmut, translated_str, PageTable,
-    PageTableEntry, UserBuffer, UserBufferIterator,
+    translated_byte_buffer, translated_byte_buffer_mut, translated_byte_buffer_mut_prefix,
+    translated_byte_buffer_prefix, translated_refmut, translated_str, PageTable, PageTableEntry,
+    UserBuffer, UserBufferIterator,
 };
 
 pub fn init() {
     heap_allocator::init_heap();
     frame_allocator::init_frame_allocator();
+    if PAGE_RECLAIM {
+        frame_allocator::set_reclaim_hook(crate::task::reclaim_frames);
+    }
     KERNEL_SPACE.exclusive_access().activate();
 }
+
+/// Self-tests of the memory subsystem, run at boot only if the kernel is
+/// built with the `mm_test` feature. Some of them use up every free frame.
+#[cfg(feature = "mm_test")]
+pub fn test() {
+    if PAGE_RECLAIM {
+        reclaim_test();
+    }
+    copy_data_test();
+    range_test();
+    zero_page_test();
+    frame_dealloc_guard_test();
+    shared_map_test();
+}
@@ -1,8 +1,12 @@
-use super::{frame_alloc, FrameTracker, PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
+use super::address::VA_WIDTH_SV39;
+use super::{
+    frame_alloc, zero_frame, FrameTracker, PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum,
+};
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use bitflags::*;
+use core::mem::size_of;
 
 bitflags! {
     pub struct PTEFlags: u8 {
@@ -50,6 +54,9 @@ impl PageTableEntry {
     pub fn executable(&self) -> bool {
         (self.flags() & PTEFlags::X) != PTEFlags::empty()
     }
+    pub fn user_accessible(&self) -> bool {
+        (self.flags() & PTEFlags::U) != PTEFlags::empty()
+    }
 }
 
 pub struct PageTable {
@@ -57,14 +64,14 @@ pub struct PageTable {
     frames: Vec<FrameTracker>,
 }
 
-/// Assume that it won't oom when creating/mapping.
+/// Creating/mapping returns `None` when running out of frames.
 impl PageTable {
-    pub fn new() -> Self {
-        let frame = frame_alloc().unwrap();
-        PageTable {
+    pub fn new() -> Option<Self> {
+        let frame = frame_alloc()?;
+        Some(PageTable {
             root_ppn: frame.ppn,
             frames: vec![frame],
-        }
+        })
     }
     /// Temporarily used to get arguments from user space.
     pub fn from_token(satp: usize) -> Self {
@@ -84,7 +91,7 @@ impl PageTable {
                 break;
             }
             if !pte.is_valid() {
-                let frame = frame_alloc().unwrap();
+                let frame = frame_alloc()?;
                 *pte = PageTableEntry::new(frame.ppn, PTEFlags::V);
                 self.frames.push(frame);
             }
@@ -110,10 +117,12 @@ impl PageTable {
         result
     }
     #[allow(unused)]
-    pub fn map(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
-        let pte = self.find_pte_create(vpn).unwrap();
+    #[must_use]
+    pub fn map(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) -> Option<()> {
+        let pte = self.find_pte_create(vpn)?;
         assert!(!pte.is_valid(), "vpn {:?} is mapped before mapping", vpn);
         *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+        Some(())
     }
     #[allow(unused)]
     pub fn unmap(&mut self, vpn: VirtPageNum) {
@@ -121,6 +130,22 @@ impl PageTable {
         assert!(pte.is_valid(), "vpn {:?} is invalid before unmapping", vpn);
         *pte = PageTableEntry::empty();
     }
+    /// Rewrite the flags of a mapped `vpn`, keeping its ppn.
+    pub fn set_flags(&mut self, vpn: VirtPageNum, flags: PTEFlags) -> Option<()> {
+        let pte = self.find_pte(vpn).filter(|pte| pte.is_valid())?;
+        *pte = PageTableEntry::new(pte.ppn(), flags | PTEFlags::V);
+        Some(())
+    }
+    /// Clear the accessed bit of a mapped `vpn`, return whether it was set.
+    pub fn take_accessed(&mut self, vpn: VirtPageNum) -> bool {
+        match self.find_pte(vpn).filter(|pte| pte.is_valid()) {
+            Some(pte) if pte.flags().contains(PTEFlags::A) => {
+                *pte = PageTableEntry::new(pte.ppn(), pte.flags() - PTEFlags::A);
+                true
+            }
+            _ => false,
+        }
+    }
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
         self.find_pte(vpn).map(|pte| *pte)
     }
@@ -137,15 +162,99 @@ impl PageTable {
     }
 }
 
-pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&'static mut [u8]> {
+/// Frame of a user page whose entry has all of `flags`, mapped back first if
+/// it has been reclaimed. If the kernel is to write to it, a page on the zero
+/// frame gets its own. Return `None` if there is no such page.
+fn try_user_ppn(page_table: &PageTable, vpn: VirtPageNum, flags: PTEFlags) -> Option<PhysPageNum> {
+    let usable = |pte: &PageTableEntry| {
+        pte.is_valid()
+            && pte.flags().contains(flags)
+            && !(flags.contains(PTEFlags::W) && pte.ppn() == zero_frame())
+    };
+    if let Some(pte) = page_table.translate(vpn).filter(usable) {
+        return Some(pte.ppn());
+    }
+    // the page table may not be the current one, e.g. for backtraces
+    if !crate::task::fault_in_user(page_table.token(), vpn) {
+        return None;
+    }
+    page_table
+        .translate(vpn)
+        .filter(usable)
+        .map(|pte| pte.ppn())
+}
+
+/// The pages of the user buffer `ptr..ptr + len`, as long as the user may
+/// read all of them, else `None`.
+pub fn translated_byte_buffer(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+) -> Option<Vec<&'static mut [u8]>> {
+    user_byte_buffer(token, ptr, len, PTEFlags::U | PTEFlags::R)
+}
+
+/// Like `translated_byte_buffer`, but the user must be allowed to write to
+/// every page as well, for the kernel to fill the buffer.
+pub fn translated_byte_buffer_mut(
+    token: usize,
+    ptr: *mut u8,
+    len: usize,
+) -> Option<Vec<&'static mut [u8]>> {
+    user_byte_buffer(token, ptr, len, PTEFlags::U | PTEFlags::W)
+}
+
+/// The part of the user buffer `ptr..ptr + len` in front of the first
+/// page the user may not read, for transfers that stop short at a bad page.
+pub fn translated_byte_buffer_prefix(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+) -> Vec<&'static mut [u8]> {
+    user_byte_buffer_prefix(token, ptr, len, PTEFlags::U | PTEFlags::R)
+}
+
+/// Like `translated_byte_buffer_prefix`, for the kernel to fill the buffer.
+pub fn translated_byte_buffer_mut_prefix(
+    token: usize,
+    ptr: *mut u8,
+    len: usize,
+) -> Vec<&'static mut [u8]> {
+    user_byte_buffer_prefix(token, ptr, len, PTEFlags::U | PTEFlags::W)
+}
+
+fn user_byte_buffer(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+    flags: PTEFlags,
+) -> Option<Vec<&'static mut [u8]>> {
+    let v = user_byte_buffer_prefix(token, ptr, len, flags);
+    if v.iter().map(|buffer| buffer.len()).sum::<usize>() == len {
+        Some(v)
+    } else {
+        None
+    }
+}
+
+fn user_byte_buffer_prefix(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+    flags: PTEFlags,
+) -> Vec<&'static mut [u8]> {
     let page_table = PageTable::from_token(token);
     let mut start = ptr as usize;
-    let end = start + len;
+    // user space is the lower half, higher addresses would be truncated
+    let end = start.saturating_add(len).min(1 << (VA_WIDTH_SV39 - 1));
     let mut v = Vec::new();
     while start < end {
         let start_va = VirtAddr::from(start);
         let mut vpn = start_va.floor();
-        let ppn = page_table.translate(vpn).unwrap().ppn();
+        let ppn = match try_user_ppn(&page_table, vpn, flags) {
+            Some(ppn) => ppn,
+            None => break,
+        };
         vpn.step();
         let mut end_va: VirtAddr = vpn.into();
         end_va = end_va.min(VirtAddr::from(end));
@@ -160,39 +269,34 @@ pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&
 }
 
 /// Load a string from other address spaces into kernel space without an end `\0`.
-pub fn translated_str(token: usize, ptr: *const u8) -> String {
+/// Return `None` if the user may not read some byte up to the `\0`.
+pub fn translated_str(token: usize, ptr: *const u8) -> Option<String> {
     let page_table = PageTable::from_token(token);
     let mut string = String::new();
     let mut va = ptr as usize;
-    loop {
-        let ch: u8 = *(page_table
-            .translate_va(VirtAddr::from(va))
-            .unwrap()
-            .get_mut());
-        if ch == 0 {
-            break;
+    // user space is the lower half, higher addresses would be truncated
+    while va < 1 << (VA_WIDTH_SV39 - 1) {
+        let start_va = VirtAddr::from(va);
+        let ppn = try_user_ppn(&page_table, start_va.floor(), PTEFlags::U | PTEFlags::R)?;
+        for &ch in &ppn.get_bytes_array()[start_va.page_offset()..] {
+            if ch == 0 {
+                return Some(string);
+            }
+            string.push(ch as char);
+            va += 1;
         }
-        string.push(ch as char);
-        va += 1;
     }
-    string
+    None
 }
 
-pub fn translated_ref<T>(token: usize, ptr: *const T) -> &'static T {
-    let page_table = PageTable::from_token(token);
-    page_table
-        .translate_va(VirtAddr::from(ptr as usize))
-        .unwrap()
-        .get_ref()
-}
-
-pub fn translated_refmut<T>(token: usize, ptr: *mut T) -> &'static mut T {
-    let page_table = PageTable::from_token(token);
-    let va = ptr as usize;
-    page_table
-        .translate_va(VirtAddr::from(va))
-        .unwrap()
-        .get_mut()
+/// The user's `T` at `ptr`, `None` unless the user may write it and it lies
+/// in a single page, as an aligned value no larger than its alignment does.
+pub fn translated_refmut<T>(token: usize, ptr: *mut T) -> Option<&'static mut T> {
+    let mut buffers = translated_byte_buffer_mut(token, ptr as *mut u8, size_of::<T>())?;
+    match buffers.as_mut_slice() {
+        [buffer] => Some(unsafe { &mut *(buffer.as_mut_ptr() as *mut T) }),
+        _ => None,
+    }
 }
 
 pub struct UserBuffer {
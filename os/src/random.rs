@@ -0,0 +1,32 @@
+//! A xorshift64 generator for `sys_getrandom`, not meant for cryptography.
+
+use crate::sync::UPIntrFreeCell;
+use crate::timer::get_time;
+use lazy_static::*;
+
+lazy_static! {
+    /// never 0, xorshift would be stuck there
+    static ref STATE: UPIntrFreeCell<u64> = unsafe { UPIntrFreeCell::new(get_time() as u64 | 1) };
+}
+
+fn xorshift64(mut x: u64) -> u64 {
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    x
+}
+
+/// Fill `buf` with pseudo-random bytes.
+pub fn fill_random(buf: &mut [u8]) {
+    STATE.exclusive_session(|state| {
+        // stir in the time so that the sequence depends on when it is asked for
+        *state = xorshift64(*state ^ get_time() as u64);
+        if *state == 0 {
+            *state = 1;
+        }
+        for chunk in buf.chunks_mut(8) {
+            *state = xorshift64(*state);
+            chunk.copy_from_slice(&state.to_le_bytes()[..chunk.len()]);
+        }
+    });
+}
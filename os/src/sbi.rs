@@ -11,6 +11,9 @@ const SBI_REMOTE_FENCE_I: usize = 5;
 const SBI_REMOTE_SFENCE_VMA: usize = 6;
 const SBI_REMOTE_SFENCE_VMA_ASID: usize = 7;
 const SBI_SHUTDOWN: usize = 8;
+/// System Reset extension, its only function `system_reset` has id 0.
+const SBI_EXT_SRST: usize = 0x5352_5354;
+const SRST_TYPE_WARM_REBOOT: usize = 2;
 
 #[inline(always)]
 fn sbi_call(which: usize, arg0: usize, arg1: usize, arg2: usize) -> usize {
@@ -40,6 +43,11 @@ pub fn console_getchar() -> usize {
     sbi_call(SBI_CONSOLE_GETCHAR, 0, 0, 0)
 }
 
+/// Ask the SBI for a warm reboot, return only if it does not support SRST.
+pub fn warm_reboot() {
+    sbi_call(SBI_EXT_SRST, SRST_TYPE_WARM_REBOOT, 0, 0);
+}
+
 use crate::board::QEMUExit;
 pub fn shutdown(exit_code: usize) -> ! {
     crate::board::QEMU_EXIT_HANDLE.exit_failure()
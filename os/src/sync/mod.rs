@@ -1,9 +1,11 @@
 mod condvar;
+mod msg_queue;
 mod mutex;
 mod semaphore;
 mod up;
 
 pub use condvar::Condvar;
+pub use msg_queue::{mq_recv, mq_send, MQ_MSG_MAX};
 pub use mutex::{Mutex, MutexBlocking, MutexSpin};
 pub use semaphore::Semaphore;
 pub use up::{UPIntrFreeCell, UPIntrRefMut};
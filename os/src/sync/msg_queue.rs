@@ -0,0 +1,86 @@
+use crate::sync::UPIntrFreeCell;
+use crate::syscall::Errno;
+use crate::task::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// Messages a queue holds before senders block.
+pub const MQ_CAPACITY: usize = 16;
+/// Longest message in bytes.
+pub const MQ_MSG_MAX: usize = 256;
+/// Queues that may exist at a time, since they are never removed.
+pub const MQ_MAX_QUEUES: usize = 64;
+
+/// A bounded queue of messages, each received as a whole.
+pub struct MsgQueue {
+    messages: VecDeque<Vec<u8>>,
+    /// tasks waiting for room to send
+    senders: VecDeque<Arc<TaskControlBlock>>,
+    /// tasks waiting for a message
+    receivers: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl MsgQueue {
+    pub fn new() -> Self {
+        Self {
+            messages: VecDeque::new(),
+            senders: VecDeque::new(),
+            receivers: VecDeque::new(),
+        }
+    }
+}
+
+lazy_static! {
+    /// Message queues shared by all processes, created by the first send to an id.
+    pub static ref MSG_QUEUES: UPIntrFreeCell<BTreeMap<usize, MsgQueue>> =
+        unsafe { UPIntrFreeCell::new(BTreeMap::new()) };
+}
+
+/// Append `msg` to queue `id`, blocking while the queue is full.
+/// Fail with ENOSPC if the queue does not exist and no more can be created.
+pub fn mq_send(id: usize, msg: Vec<u8>) -> Result<(), Errno> {
+    loop {
+        let mut queues = MSG_QUEUES.exclusive_access();
+        if !queues.contains_key(&id) && queues.len() >= MQ_MAX_QUEUES {
+            return Err(Errno::ENOSPC);
+        }
+        let queue = queues.entry(id).or_insert_with(MsgQueue::new);
+        if queue.messages.len() < MQ_CAPACITY {
+            queue.messages.push_back(msg);
+            if let Some(task) = queue.receivers.pop_front() {
+                wakeup_task(task);
+            }
+            return Ok(());
+        }
+        queue.senders.push_back(current_task().unwrap());
+        drop(queues);
+        block_current_and_run_next();
+    }
+}
+
+/// Take the oldest message of queue `id`, blocking while the queue is empty.
+/// Fail with ENOENT if nothing was ever sent to `id`, and with EMSGSIZE if
+/// the message is longer than `max_len`, which leaves it queued.
+pub fn mq_recv(id: usize, max_len: usize) -> Result<Vec<u8>, Errno> {
+    loop {
+        let mut queues = MSG_QUEUES.exclusive_access();
+        let queue = match queues.get_mut(&id) {
+            Some(queue) => queue,
+            None => return Err(Errno::ENOENT),
+        };
+        if let Some(len) = queue.messages.front().map(Vec::len) {
+            if len > max_len {
+                return Err(Errno::EMSGSIZE);
+            }
+            if let Some(task) = queue.senders.pop_front() {
+                wakeup_task(task);
+            }
+            return Ok(queue.messages.pop_front().unwrap());
+        }
+        queue.receivers.push_back(current_task().unwrap());
+        drop(queues);
+        block_current_and_run_next();
+    }
+}
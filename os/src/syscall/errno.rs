@@ -0,0 +1,31 @@
+/// Error numbers of syscalls, the values follow Linux.
+/// A failed syscall returns `-(errno as isize)`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(isize)]
+#[allow(unused, clippy::upper_case_acronyms)]
+pub enum Errno {
+    EPERM = 1,
+    ENOENT = 2,
+    ESRCH = 3,
+    EINTR = 4,
+    EIO = 5,
+    ENOEXEC = 8,
+    EBADF = 9,
+    ECHILD = 10,
+    EAGAIN = 11,
+    ENOMEM = 12,
+    EACCES = 13,
+    EFAULT = 14,
+    EEXIST = 17,
+    EXDEV = 18,
+    ENOTDIR = 20,
+    EISDIR = 21,
+    EINVAL = 22,
+    ENOTTY = 25,
+    EFBIG = 27,
+    ENOSPC = 28,
+    ERANGE = 34,
+    EDEADLK = 35,
+    ENOSYS = 38,
+    EMSGSIZE = 90,
+}
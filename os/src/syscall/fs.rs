@@ -1,86 +1,254 @@
-use crate::fs::{make_pipe, open_file, OpenFlags};
-use crate::mm::{translated_byte_buffer, translated_refmut, translated_str, UserBuffer};
+use super::errno::Errno;
+use crate::config::MAX_FDS;
+use crate::fs::{
+    cache_stats, console_termios, find_inode, inode_stat, link_file, make_pipe, open_file_in,
+    open_proc_file, open_tmp_file, proc_name, resolve_path, same_fs, set_console_termios, sync_all,
+    tmp_find, tmp_link, tmp_name, tmp_unlink, unlink_file, CacheStats, File, OSInode, OpenFlags,
+    PollEvents, PollFd, Stat, StatMode, Termios, Winsize, CONSOLE_WINSIZE,
+};
+use crate::mm::{
+    translated_byte_buffer, translated_byte_buffer_mut, translated_byte_buffer_mut_prefix,
+    translated_byte_buffer_prefix, translated_str, UserBuffer,
+};
 use crate::task::{current_process, current_user_token};
+use crate::timer::{block_current_until, get_time_ms};
 use alloc::sync::Arc;
+use core::mem::size_of;
+
+/// `dirfd` of `openat` meaning the current directory, which is always `/`.
+const AT_FDCWD: isize = -100;
+
+const F_GETFD: usize = 1;
+const F_SETFD: usize = 2;
+const FD_CLOEXEC: usize = 1;
+
+/// `mode` bits of `faccessat`, `F_OK` (0) only checks that the file exists.
+const R_OK: u32 = 4;
+const W_OK: u32 = 2;
+const X_OK: u32 = 1;
+
+const TCGETS: usize = 0x5401;
+const TCSETS: usize = 0x5402;
+const TIOCGWINSZ: usize = 0x5413;
 
 pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
     let token = current_user_token();
     let process = current_process();
     let inner = process.inner_exclusive_access();
     if fd >= inner.fd_table.len() {
-        return -1;
+        return -(Errno::EBADF as isize);
     }
     if let Some(file) = &inner.fd_table[fd] {
         if !file.writable() {
-            return -1;
+            return -(Errno::EBADF as isize);
         }
         let file = file.clone();
         // release current task TCB manually to avoid multi-borrow
         drop(inner);
-        file.write(UserBuffer::new(translated_byte_buffer(token, buf, len))) as isize
+        // write up to the first page the user may not read
+        let buffers = translated_byte_buffer_prefix(token, buf, len);
+        if buffers.is_empty() && len > 0 {
+            return -(Errno::EFAULT as isize);
+        }
+        match file.write(UserBuffer::new(buffers)) {
+            Ok(0) if len > 0 && file.is_regular() => -(Errno::ENOSPC as isize),
+            Ok(write_size) => write_size as isize,
+            Err(errno) => -(errno as isize),
+        }
     } else {
-        -1
+        -(Errno::EBADF as isize)
     }
 }
 
-pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> isize {
+pub fn sys_read(fd: usize, buf: *mut u8, len: usize) -> isize {
     let token = current_user_token();
     let process = current_process();
     let inner = process.inner_exclusive_access();
     if fd >= inner.fd_table.len() {
-        return -1;
+        return -(Errno::EBADF as isize);
     }
     if let Some(file) = &inner.fd_table[fd] {
         let file = file.clone();
         if !file.readable() {
-            return -1;
+            return -(Errno::EBADF as isize);
         }
         // release current task TCB manually to avoid multi-borrow
         drop(inner);
-        file.read(UserBuffer::new(translated_byte_buffer(token, buf, len))) as isize
+        // read up to the first page the user may not write
+        let buffers = translated_byte_buffer_mut_prefix(token, buf, len);
+        if buffers.is_empty() && len > 0 {
+            return -(Errno::EFAULT as isize);
+        }
+        match file.read(UserBuffer::new(buffers)) {
+            Ok(read_size) => read_size as isize,
+            Err(errno) => -(errno as isize),
+        }
     } else {
-        -1
+        -(Errno::EBADF as isize)
     }
 }
 
-pub fn sys_open(path: *const u8, flags: u32) -> isize {
+/// Copy the path of the current directory into `buf` with a trailing '\0',
+/// return its length including the '\0', or `ERANGE` if `len` is too small.
+pub fn sys_getcwd(buf: *mut u8, len: usize) -> isize {
+    let token = current_user_token();
+    let mut path = current_process().inner_exclusive_access().cwd.path();
+    path.push('\0');
+    if path.len() > len {
+        return -(Errno::ERANGE as isize);
+    }
+    let buffers = match translated_byte_buffer_mut(token, buf, path.len()) {
+        Some(buffers) => buffers,
+        None => return -(Errno::EFAULT as isize),
+    };
+    let mut copied = 0;
+    for buffer in buffers {
+        buffer.copy_from_slice(&path.as_bytes()[copied..copied + buffer.len()]);
+        copied += buffer.len();
+    }
+    path.len() as isize
+}
+
+/// Open `path` relative to the directory open at `dirfd`, or to the current
+/// directory if it is `AT_FDCWD`; an absolute `path` ignores `dirfd`.
+/// "" and "." open the directory itself, read-only.
+pub fn sys_openat(dirfd: isize, path: *const u8, flags: u32) -> isize {
     let process = current_process();
     let token = current_user_token();
-    let path = translated_str(token, path);
-    if let Some(inode) = open_file(path.as_str(), OpenFlags::from_bits(flags).unwrap()) {
-        let mut inner = process.inner_exclusive_access();
-        let fd = inner.alloc_fd();
-        inner.fd_table[fd] = Some(inode);
-        fd as isize
+    let path = match translated_str(token, path) {
+        Some(path) => path,
+        None => return -(Errno::EFAULT as isize),
+    };
+    let flags = match OpenFlags::from_bits(flags) {
+        Some(flags) => flags,
+        None => return -(Errno::EINVAL as isize),
+    };
+    let (dir, name) = if dirfd == AT_FDCWD || path.starts_with('/') {
+        if let Some(name) = tmp_name(path.as_str()) {
+            return open_tmp(name, flags);
+        }
+        if let Some(name) = proc_name(path.as_str()) {
+            return open_proc(name, flags);
+        }
+        resolve_path(path.as_str())
+    } else {
+        let file = usize::try_from(dirfd).ok().and_then(|dirfd| {
+            let inner = process.inner_exclusive_access();
+            inner.fd_table.get(dirfd).cloned().flatten()
+        });
+        match file.map(|file| file.dir()) {
+            Some(Some(dir)) => (dir, path.as_str()),
+            Some(None) => return -(Errno::ENOTDIR as isize),
+            None => return -(Errno::EBADF as isize),
+        }
+    };
+    let name = name.strip_prefix("./").unwrap_or(name);
+    // every directory is the root of its fs, there is nothing above it
+    if name == ".." || name.starts_with("../") {
+        return -(Errno::EINVAL as isize);
+    }
+    let file: Arc<dyn File + Send + Sync> = if name.is_empty() || name == "." {
+        if flags.read_write().1 || flags.contains(OpenFlags::TRUNC) {
+            return -(Errno::EISDIR as isize);
+        }
+        Arc::new(OSInode::new(true, false, dir))
     } else {
-        -1
+        if flags.read_write().1 || flags.contains(OpenFlags::TRUNC) {
+            if let Some(inode) = dir.find(name) {
+                if inode.mode() as u32 & StatMode::S_IWUSR.bits() == 0 {
+                    return -(Errno::EACCES as isize);
+                }
+            }
+        }
+        let umask = process.inner_exclusive_access().umask;
+        match open_file_in(&dir, name, flags, umask) {
+            Some(inode) => inode,
+            None if flags.contains(OpenFlags::CREATE | OpenFlags::EXCL) => {
+                return -(Errno::EEXIST as isize)
+            }
+            None => return -(Errno::ENOENT as isize),
+        }
+    };
+    install_fd(file, flags)
+}
+
+/// `sys_openat` of a file on the tmpfs.
+fn open_tmp(name: &str, flags: OpenFlags) -> isize {
+    if flags.read_write().1 || flags.contains(OpenFlags::TRUNC) {
+        if let Some(inode) = tmp_find(name) {
+            if inode.mode() as u32 & StatMode::S_IWUSR.bits() == 0 {
+                return -(Errno::EACCES as isize);
+            }
+        }
     }
+    let umask = current_process().inner_exclusive_access().umask;
+    let file = match open_tmp_file(name, flags, umask) {
+        Some(file) => file,
+        None if flags.contains(OpenFlags::CREATE | OpenFlags::EXCL) => {
+            return -(Errno::EEXIST as isize)
+        }
+        None => return -(Errno::ENOENT as isize),
+    };
+    install_fd(file, flags)
+}
+
+/// `sys_openat` of a file under /proc, which can only be read.
+fn open_proc(name: &str, flags: OpenFlags) -> isize {
+    let file = match open_proc_file(name) {
+        Some(file) => file,
+        None => return -(Errno::ENOENT as isize),
+    };
+    if flags.read_write().1 || flags.contains(OpenFlags::TRUNC) {
+        return -(Errno::EACCES as isize);
+    }
+    install_fd(Arc::new(file), flags)
+}
+
+/// Put `file` in the lowest free fd, to be closed on exec if `flags` asks so.
+fn install_fd(file: Arc<dyn File + Send + Sync>, flags: OpenFlags) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    let fd = inner.alloc_fd();
+    inner.fd_table[fd] = Some(file);
+    if flags.contains(OpenFlags::CLOEXEC) {
+        inner.cloexec_fds.insert(fd);
+    }
+    fd as isize
 }
 
 pub fn sys_close(fd: usize) -> isize {
     let process = current_process();
     let mut inner = process.inner_exclusive_access();
     if fd >= inner.fd_table.len() {
-        return -1;
+        return -(Errno::EBADF as isize);
     }
     if inner.fd_table[fd].is_none() {
-        return -1;
+        return -(Errno::EBADF as isize);
     }
     inner.fd_table[fd].take();
+    inner.cloexec_fds.remove(&fd);
     0
 }
 
 pub fn sys_pipe(pipe: *mut usize) -> isize {
     let process = current_process();
     let token = current_user_token();
+    // check before any fd is taken, faulting the page in needs the PCB
+    let buffers = match translated_byte_buffer_mut(token, pipe as *mut u8, 2 * size_of::<usize>()) {
+        Some(buffers) => buffers,
+        None => return -(Errno::EFAULT as isize),
+    };
     let mut inner = process.inner_exclusive_access();
     let (pipe_read, pipe_write) = make_pipe();
     let read_fd = inner.alloc_fd();
     inner.fd_table[read_fd] = Some(pipe_read);
     let write_fd = inner.alloc_fd();
     inner.fd_table[write_fd] = Some(pipe_write);
-    *translated_refmut(token, pipe) = read_fd;
-    *translated_refmut(token, unsafe { pipe.add(1) }) = write_fd;
+    drop(inner);
+    let bytes = [read_fd, write_fd].into_iter().flat_map(usize::to_ne_bytes);
+    for (dst, byte) in buffers.into_iter().flatten().zip(bytes) {
+        *dst = byte;
+    }
     0
 }
 
@@ -88,12 +256,424 @@ pub fn sys_dup(fd: usize) -> isize {
     let process = current_process();
     let mut inner = process.inner_exclusive_access();
     if fd >= inner.fd_table.len() {
-        return -1;
+        return -(Errno::EBADF as isize);
     }
     if inner.fd_table[fd].is_none() {
-        return -1;
+        return -(Errno::EBADF as isize);
     }
     let new_fd = inner.alloc_fd();
     inner.fd_table[new_fd] = Some(Arc::clone(inner.fd_table[fd].as_ref().unwrap()));
     new_fd as isize
 }
+
+/// Like `sys_dup`, but the duplicate is placed at `new_fd`, which is closed first if open.
+/// `new_fd` must be below `MAX_FDS`.
+pub fn sys_dup2(old_fd: usize, new_fd: usize) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if old_fd >= inner.fd_table.len() {
+        return -(Errno::EBADF as isize);
+    }
+    if inner.fd_table[old_fd].is_none() {
+        return -(Errno::EBADF as isize);
+    }
+    if new_fd >= MAX_FDS {
+        return -(Errno::EBADF as isize);
+    }
+    if old_fd == new_fd {
+        return new_fd as isize;
+    }
+    while inner.fd_table.len() <= new_fd {
+        inner.fd_table.push(None);
+    }
+    inner.fd_table[new_fd] = Some(Arc::clone(inner.fd_table[old_fd].as_ref().unwrap()));
+    inner.cloexec_fds.remove(&new_fd);
+    new_fd as isize
+}
+
+/// Only `F_GETFD`/`F_SETFD` with the `FD_CLOEXEC` flag are supported.
+pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -(Errno::EBADF as isize);
+    }
+    if inner.fd_table[fd].is_none() {
+        return -(Errno::EBADF as isize);
+    }
+    match cmd {
+        F_GETFD => {
+            if inner.cloexec_fds.contains(&fd) {
+                FD_CLOEXEC as isize
+            } else {
+                0
+            }
+        }
+        F_SETFD => {
+            if arg & FD_CLOEXEC != 0 {
+                inner.cloexec_fds.insert(fd);
+            } else {
+                inner.cloexec_fds.remove(&fd);
+            }
+            0
+        }
+        _ => -(Errno::EINVAL as isize),
+    }
+}
+
+/// Terminal control of the console, only `Stdin` and `Stdout` accept it.
+pub fn sys_ioctl(fd: usize, request: usize, arg: usize) -> isize {
+    let token = current_user_token();
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -(Errno::EBADF as isize);
+    }
+    let file = match &inner.fd_table[fd] {
+        Some(file) => file.clone(),
+        None => return -(Errno::EBADF as isize),
+    };
+    drop(inner);
+    if !file.is_tty() {
+        return -(Errno::ENOTTY as isize);
+    }
+    match request {
+        TCGETS => copy_out(token, arg as *mut Termios, &console_termios()),
+        TCSETS => {
+            let mut termios = Termios::default();
+            match copy_in(token, arg as *const Termios, &mut termios) {
+                Some(()) => {
+                    set_console_termios(&termios);
+                    0
+                }
+                None => -(Errno::EFAULT as isize),
+            }
+        }
+        TIOCGWINSZ => copy_out(token, arg as *mut Winsize, &CONSOLE_WINSIZE),
+        _ => -(Errno::EINVAL as isize),
+    }
+}
+
+/// Create `new_path` as a hard link to the file `old_path`.
+pub fn sys_linkat(old_path: *const u8, new_path: *const u8) -> isize {
+    let token = current_user_token();
+    let (old_path, new_path) = match (
+        translated_str(token, old_path),
+        translated_str(token, new_path),
+    ) {
+        (Some(old_path), Some(new_path)) => (old_path, new_path),
+        _ => return -(Errno::EFAULT as isize),
+    };
+    if !same_fs(old_path.as_str(), new_path.as_str()) {
+        return -(Errno::EXDEV as isize);
+    }
+    if let (Some(old_name), Some(new_name)) =
+        (tmp_name(old_path.as_str()), tmp_name(new_path.as_str()))
+    {
+        if tmp_find(new_name).is_some() {
+            return -(Errno::EEXIST as isize);
+        }
+        return match tmp_link(old_name, new_name) {
+            Some(()) => 0,
+            None => -(Errno::ENOENT as isize),
+        };
+    }
+    if find_inode(new_path.as_str()).is_some() {
+        return -(Errno::EEXIST as isize);
+    }
+    match link_file(old_path.as_str(), new_path.as_str()) {
+        Some(()) => 0,
+        None => -(Errno::ENOENT as isize),
+    }
+}
+
+/// Remove the dirent `path`, the file goes away with its last link.
+pub fn sys_unlinkat(path: *const u8) -> isize {
+    let token = current_user_token();
+    let path = match translated_str(token, path) {
+        Some(path) => path,
+        None => return -(Errno::EFAULT as isize),
+    };
+    if let Some(name) = tmp_name(path.as_str()) {
+        return match tmp_unlink(name) {
+            Some(()) => 0,
+            None => -(Errno::ENOENT as isize),
+        };
+    }
+    match find_inode(path.as_str()) {
+        None => -(Errno::ENOENT as isize),
+        Some(inode) if inode.is_dir() => -(Errno::EISDIR as isize),
+        Some(_) => match unlink_file(path.as_str()) {
+            Some(()) => 0,
+            None => -(Errno::ENOENT as isize),
+        },
+    }
+}
+
+/// Set the permission bits of `path` to `mode & 0o777`.
+pub fn sys_chmod(path: *const u8, mode: u32) -> isize {
+    let token = current_user_token();
+    let path = match translated_str(token, path) {
+        Some(path) => path,
+        None => return -(Errno::EFAULT as isize),
+    };
+    if let Some(name) = tmp_name(path.as_str()) {
+        return match tmp_find(name) {
+            Some(inode) => {
+                inode.set_mode((mode & 0o777) as u16);
+                0
+            }
+            None => -(Errno::ENOENT as isize),
+        };
+    }
+    match find_inode(path.as_str()) {
+        Some(inode) => {
+            inode.set_mode((mode & 0o777) as u16);
+            0
+        }
+        None => -(Errno::ENOENT as isize),
+    }
+}
+
+/// Set the permission bits new files of this process go without, return
+/// the previous mask.
+pub fn sys_umask(mask: u32) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    let old_mask = inner.umask;
+    inner.umask = (mask & 0o777) as u16;
+    old_mask as isize
+}
+
+pub fn sys_fstat(fd: usize, st: *mut Stat) -> isize {
+    let token = current_user_token();
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -(Errno::EBADF as isize);
+    }
+    let stat = match &inner.fd_table[fd] {
+        Some(file) => match file.stat() {
+            Some(stat) => stat,
+            None => return -(Errno::EINVAL as isize),
+        },
+        None => return -(Errno::EBADF as isize),
+    };
+    drop(inner);
+    copy_out(token, st, &stat)
+}
+
+/// Set the size of the file `fd` to `len` bytes, dropping the data past it
+/// or growing the file with zeros. Fail with `EINVAL` if `fd` is not open for
+/// writing, is not a regular file, or can not grow to `len`.
+pub fn sys_ftruncate(fd: usize, len: usize) -> isize {
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -(Errno::EBADF as isize);
+    }
+    let file = match &inner.fd_table[fd] {
+        Some(file) => file.clone(),
+        None => return -(Errno::EBADF as isize),
+    };
+    drop(inner);
+    if !file.writable() {
+        return -(Errno::EINVAL as isize);
+    }
+    match file.truncate(len) {
+        Ok(()) => 0,
+        Err(errno) => -(errno as isize),
+    }
+}
+
+/// Like `sys_fstat`, but look `path` up directly instead of using an open fd.
+pub fn sys_stat(path: *const u8, st: *mut Stat) -> isize {
+    let token = current_user_token();
+    let path = match translated_str(token, path) {
+        Some(path) => path,
+        None => return -(Errno::EFAULT as isize),
+    };
+    if let Some(name) = tmp_name(path.as_str()) {
+        return match tmp_find(name) {
+            Some(inode) => copy_out(token, st, &inode.stat()),
+            None => -(Errno::ENOENT as isize),
+        };
+    }
+    match find_inode(path.as_str()) {
+        Some(inode) => copy_out(token, st, &inode_stat(&inode)),
+        None => -(Errno::ENOENT as isize),
+    }
+}
+
+/// Check that `path` exists and its owner has the permissions in `mode`,
+/// without opening it. A mount point names the root of its fs.
+pub fn sys_faccessat(path: *const u8, mode: u32) -> isize {
+    if mode & !(R_OK | W_OK | X_OK) != 0 {
+        return -(Errno::EINVAL as isize);
+    }
+    let token = current_user_token();
+    let path = match translated_str(token, path) {
+        Some(path) => path,
+        None => return -(Errno::EFAULT as isize),
+    };
+    let perm = if let Some(name) = tmp_name(path.as_str()) {
+        if name.is_empty() {
+            Some(0o755)
+        } else {
+            tmp_find(name).map(|inode| inode.mode())
+        }
+    } else if let Some(name) = proc_name(path.as_str()) {
+        if name.is_empty() {
+            Some(0o555)
+        } else {
+            open_proc_file(name).map(|_| 0o444)
+        }
+    } else {
+        let (root_inode, name) = resolve_path(path.as_str());
+        if name.is_empty() {
+            Some(root_inode.mode())
+        } else {
+            root_inode.find(name).map(|inode| inode.mode())
+        }
+    };
+    match perm {
+        Some(perm) if (perm as u32 >> 6) & mode == mode => 0,
+        Some(_) => -(Errno::EACCES as isize),
+        None => -(Errno::ENOENT as isize),
+    }
+}
+
+/// Copy `value` to the user pointer `dst`, `EFAULT` if it is not writable.
+pub fn copy_out<T>(token: usize, dst: *mut T, value: &T) -> isize {
+    // copy byte by byte since `T` may cross a page boundary
+    let value_bytes =
+        unsafe { core::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) };
+    let buffers = match translated_byte_buffer_mut(token, dst as *mut u8, size_of::<T>()) {
+        Some(buffers) => buffers,
+        None => return -(Errno::EFAULT as isize),
+    };
+    let mut copied = 0;
+    for buffer in buffers {
+        buffer.copy_from_slice(&value_bytes[copied..copied + buffer.len()]);
+        copied += buffer.len();
+    }
+    0
+}
+
+/// Fill `value` from the user pointer `src`, `None` if it is not readable.
+pub fn copy_in<T>(token: usize, src: *const T, value: &mut T) -> Option<()> {
+    let value_bytes =
+        unsafe { core::slice::from_raw_parts_mut(value as *mut T as *mut u8, size_of::<T>()) };
+    let mut copied = 0;
+    for buffer in translated_byte_buffer(token, src as *const u8, size_of::<T>())? {
+        value_bytes[copied..copied + buffer.len()].copy_from_slice(buffer);
+        copied += buffer.len();
+    }
+    Some(())
+}
+
+/// Fill in `revents` of every entry, return how many have any bit set,
+/// `None` if the array is not readable and writable.
+fn poll_once(token: usize, fds: *mut PollFd, nfds: usize) -> Option<usize> {
+    let process = current_process();
+    let mut ready = 0;
+    for i in 0..nfds {
+        let entry = fds.wrapping_add(i);
+        let mut pollfd = PollFd {
+            fd: 0,
+            events: 0,
+            revents: 0,
+        };
+        copy_in(token, entry, &mut pollfd)?;
+        let events = PollEvents::from_bits_truncate(pollfd.events);
+        let inner = process.inner_exclusive_access();
+        let file = match inner.fd_table.get(pollfd.fd as usize) {
+            Some(Some(file)) if pollfd.fd >= 0 => Some(file.clone()),
+            _ => None,
+        };
+        drop(inner);
+        let revents = match file {
+            Some(file) => {
+                let mut revents = PollEvents::empty();
+                if events.contains(PollEvents::POLLIN) && file.readable_now() {
+                    revents |= PollEvents::POLLIN;
+                }
+                if events.contains(PollEvents::POLLOUT) && file.writable_now() {
+                    revents |= PollEvents::POLLOUT;
+                }
+                revents
+            }
+            None => PollEvents::POLLNVAL,
+        };
+        pollfd.revents = revents.bits();
+        if copy_out(token, entry, &pollfd) != 0 {
+            return None;
+        }
+        if !revents.is_empty() {
+            ready += 1;
+        }
+    }
+    Some(ready)
+}
+
+/// Wait until one of the `nfds` fds is ready or `timeout` ms have passed, a
+/// negative `timeout` waits forever. Return the number of ready fds, 0 on
+/// timeout.
+///
+/// The task is not registered on the wait queues of the files, since a task
+/// can only be blocked on one queue at a time. Instead it sleeps on the timer
+/// and re-checks every fd on each tick, so a waiting poll costs `nfds`
+/// readiness checks per tick, and readiness is noticed up to a tick late.
+pub fn sys_poll(fds: *mut PollFd, nfds: usize, timeout: isize) -> isize {
+    let token = current_user_token();
+    let deadline = if timeout >= 0 {
+        Some(get_time_ms() + timeout as usize)
+    } else {
+        None
+    };
+    loop {
+        let ready = match poll_once(token, fds, nfds) {
+            Some(ready) => ready,
+            None => return -(Errno::EFAULT as isize),
+        };
+        if ready > 0 {
+            return ready as isize;
+        }
+        let now = get_time_ms();
+        match deadline {
+            Some(deadline) if now >= deadline => return 0,
+            // anything in the future wakes us up at the next tick
+            _ => block_current_until(now + 1),
+        }
+    }
+}
+
+/// Write the cached blocks of the file `fd` back to its disk.
+pub fn sys_fsync(fd: usize) -> isize {
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -(Errno::EBADF as isize);
+    }
+    let file = match &inner.fd_table[fd] {
+        Some(file) => file.clone(),
+        None => return -(Errno::EBADF as isize),
+    };
+    drop(inner);
+    match file.sync() {
+        Some(()) => 0,
+        None => -(Errno::EINVAL as isize),
+    }
+}
+
+/// Write every dirty cached block of every easy-fs back to its disk.
+pub fn sys_sync() -> isize {
+    sync_all();
+    0
+}
+
+/// Copy the block cache counters to `stats`, see `easy_fs::CacheStats`.
+pub fn sys_fscachestats(stats: *mut CacheStats) -> isize {
+    let token = current_user_token();
+    copy_out(token, stats, &cache_stats())
+}
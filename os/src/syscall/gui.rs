@@ -1,3 +1,4 @@
+use super::errno::Errno;
 use crate::drivers::GPU_DEVICE;
 use crate::mm::{MapArea, MapPermission, MapType, PhysAddr, VirtAddr};
 use crate::task::current_process;
@@ -16,15 +17,21 @@ pub fn sys_framebuffer() -> isize {
 
     let current_process = current_process();
     let mut inner = current_process.inner_exclusive_access();
-    inner.memory_set.push(
-        MapArea::new(
-            (FB_VADDR as usize).into(),
-            (FB_VADDR + len as usize).into(),
-            MapType::Linear(pn_offset),
-            MapPermission::R | MapPermission::W | MapPermission::U,
-        ),
-        None,
-    );
+    if inner
+        .memory_set
+        .push(
+            MapArea::new(
+                (FB_VADDR as usize).into(),
+                (FB_VADDR + len as usize).into(),
+                MapType::Linear(pn_offset),
+                MapPermission::R | MapPermission::W | MapPermission::U,
+            ),
+            None,
+        )
+        .is_none()
+    {
+        return -(Errno::ENOMEM as isize);
+    }
     FB_VADDR as isize
 }
 
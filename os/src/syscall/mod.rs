@@ -1,21 +1,62 @@
+const SYSCALL_GETCWD: usize = 17;
+const SYSCALL_DUP2: usize = 23;
 const SYSCALL_DUP: usize = 24;
-const SYSCALL_OPEN: usize = 56;
+const SYSCALL_FCNTL: usize = 25;
+const SYSCALL_IOCTL: usize = 29;
+const SYSCALL_PAUSE: usize = 34;
+const SYSCALL_UNLINKAT: usize = 35;
+const SYSCALL_LINKAT: usize = 37;
+const SYSCALL_FTRUNCATE: usize = 46;
+const SYSCALL_FACCESSAT: usize = 48;
+const SYSCALL_CHMOD: usize = 53;
+const SYSCALL_OPENAT: usize = 56;
 const SYSCALL_CLOSE: usize = 57;
 const SYSCALL_PIPE: usize = 59;
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
+const SYSCALL_POLL: usize = 73;
+const SYSCALL_STAT: usize = 79;
+const SYSCALL_FSTAT: usize = 80;
+const SYSCALL_SYNC: usize = 81;
+const SYSCALL_FSYNC: usize = 82;
 const SYSCALL_EXIT: usize = 93;
 const SYSCALL_SLEEP: usize = 101;
+const SYSCALL_SETITIMER: usize = 103;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
+const SYSCALL_CLOCK_NANOSLEEP: usize = 115;
 const SYSCALL_YIELD: usize = 124;
 const SYSCALL_KILL: usize = 129;
+const SYSCALL_SIGACTION: usize = 134;
+const SYSCALL_SIGRETURN: usize = 139;
+const SYSCALL_TIMES: usize = 153;
+const SYSCALL_UMASK: usize = 166;
 const SYSCALL_GET_TIME: usize = 169;
 const SYSCALL_GETPID: usize = 172;
+const SYSCALL_GETPPID: usize = 173;
+const SYSCALL_SYSINFO: usize = 179;
+const SYSCALL_MUNMAP: usize = 215;
 const SYSCALL_FORK: usize = 220;
 const SYSCALL_EXEC: usize = 221;
+const SYSCALL_MMAP: usize = 222;
+const SYSCALL_MPROTECT: usize = 226;
+const SYSCALL_MADVISE: usize = 233;
 const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_GETRANDOM: usize = 278;
+const SYSCALL_BACKTRACE: usize = 500;
+const SYSCALL_LISTTASKS: usize = 501;
+const SYSCALL_YIELD_TO: usize = 502;
+const SYSCALL_FSCACHESTATS: usize = 503;
+const SYSCALL_TIME: usize = 504;
+const SYSCALL_SET_TIME_SLICE: usize = 505;
+const SYSCALL_SHUTDOWN: usize = 506;
+const SYSCALL_REBOOT: usize = 507;
+const SYSCALL_SET_STACK_ASLR: usize = 508;
+const SYSCALL_FORK_EXEC: usize = 509;
+const SYSCALL_WAITALL: usize = 510;
 const SYSCALL_THREAD_CREATE: usize = 1000;
 const SYSCALL_GETTID: usize = 1001;
 const SYSCALL_WAITTID: usize = 1002;
+const SYSCALL_CLONE: usize = 1003;
 const SYSCALL_MUTEX_CREATE: usize = 1010;
 const SYSCALL_MUTEX_LOCK: usize = 1011;
 const SYSCALL_MUTEX_UNLOCK: usize = 1012;
@@ -25,11 +66,14 @@ const SYSCALL_SEMAPHORE_DOWN: usize = 1022;
 const SYSCALL_CONDVAR_CREATE: usize = 1030;
 const SYSCALL_CONDVAR_SIGNAL: usize = 1031;
 const SYSCALL_CONDVAR_WAIT: usize = 1032;
+const SYSCALL_MQ_SEND: usize = 1040;
+const SYSCALL_MQ_RECV: usize = 1041;
 const SYSCALL_FRAMEBUFFER: usize = 2000;
 const SYSCALL_FRAMEBUFFER_FLUSH: usize = 2001;
 const SYSCALL_EVENT_GET: usize = 3000;
 const SYSCALL_KEY_PRESSED: usize = 3001;
 
+mod errno;
 mod fs;
 mod gui;
 mod input;
@@ -37,6 +81,9 @@ mod process;
 mod sync;
 mod thread;
 
+use crate::fs::{CacheStats, PollFd, Stat};
+use crate::task::{current_process, TaskInfo};
+pub use errno::Errno;
 use fs::*;
 use gui::*;
 use input::*;
@@ -44,26 +91,69 @@ use process::*;
 use sync::*;
 use thread::*;
 
-pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
+pub fn syscall(syscall_id: usize, args: [usize; 4]) -> isize {
     match syscall_id {
+        SYSCALL_GETCWD => sys_getcwd(args[0] as *mut u8, args[1]),
+        SYSCALL_DUP2 => sys_dup2(args[0], args[1]),
         SYSCALL_DUP => sys_dup(args[0]),
-        SYSCALL_OPEN => sys_open(args[0] as *const u8, args[1] as u32),
+        SYSCALL_FCNTL => sys_fcntl(args[0], args[1], args[2]),
+        SYSCALL_IOCTL => sys_ioctl(args[0], args[1], args[2]),
+        SYSCALL_UNLINKAT => sys_unlinkat(args[0] as *const u8),
+        SYSCALL_LINKAT => sys_linkat(args[0] as *const u8, args[1] as *const u8),
+        SYSCALL_FACCESSAT => sys_faccessat(args[0] as *const u8, args[1] as u32),
+        SYSCALL_CHMOD => sys_chmod(args[0] as *const u8, args[1] as u32),
+        SYSCALL_OPENAT => sys_openat(args[0] as isize, args[1] as *const u8, args[2] as u32),
         SYSCALL_CLOSE => sys_close(args[0]),
         SYSCALL_PIPE => sys_pipe(args[0] as *mut usize),
-        SYSCALL_READ => sys_read(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_READ => sys_read(args[0], args[1] as *mut u8, args[2]),
         SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_POLL => sys_poll(args[0] as *mut PollFd, args[1], args[2] as isize),
+        SYSCALL_STAT => sys_stat(args[0] as *const u8, args[1] as *mut Stat),
+        SYSCALL_FSTAT => sys_fstat(args[0], args[1] as *mut Stat),
+        SYSCALL_SYNC => sys_sync(),
+        SYSCALL_FSYNC => sys_fsync(args[0]),
+        SYSCALL_FTRUNCATE => sys_ftruncate(args[0], args[1]),
         SYSCALL_EXIT => sys_exit(args[0] as i32),
         SYSCALL_SLEEP => sys_sleep(args[0]),
+        SYSCALL_SETITIMER => sys_setitimer(args[0], args[1]),
+        SYSCALL_CLOCK_GETTIME => sys_clock_gettime(args[0], args[1] as *mut TimeSpec),
+        SYSCALL_CLOCK_NANOSLEEP => {
+            sys_clock_nanosleep(args[0], args[1], args[2] as *const TimeSpec)
+        }
         SYSCALL_YIELD => sys_yield(),
         SYSCALL_KILL => sys_kill(args[0], args[1] as u32),
+        SYSCALL_PAUSE => sys_pause(),
+        SYSCALL_SIGACTION => sys_sigaction(args[0], args[1]),
+        SYSCALL_SIGRETURN => sys_sigreturn(),
+        SYSCALL_TIMES => sys_times(args[0] as *mut Tms),
+        SYSCALL_UMASK => sys_umask(args[0] as u32),
         SYSCALL_GET_TIME => sys_get_time(),
         SYSCALL_GETPID => sys_getpid(),
+        SYSCALL_GETPPID => sys_getppid(),
+        SYSCALL_SYSINFO => sys_sysinfo(args[0] as *mut SysInfo),
+        SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
         SYSCALL_FORK => sys_fork(),
         SYSCALL_EXEC => sys_exec(args[0] as *const u8, args[1] as *const usize),
+        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2], args[3]),
+        SYSCALL_MADVISE => sys_madvise(args[0], args[1], args[2]),
+        SYSCALL_MPROTECT => sys_mprotect(args[0], args[1], args[2]),
         SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
+        SYSCALL_GETRANDOM => sys_getrandom(args[0] as *mut u8, args[1]),
+        SYSCALL_BACKTRACE => sys_backtrace(args[0], args[1] as *mut usize, args[2]),
+        SYSCALL_LISTTASKS => sys_listtasks(args[0] as *mut TaskInfo, args[1]),
+        SYSCALL_YIELD_TO => sys_yield_to(args[0]),
+        SYSCALL_FSCACHESTATS => sys_fscachestats(args[0] as *mut CacheStats),
+        SYSCALL_TIME => sys_time(),
+        SYSCALL_SET_TIME_SLICE => sys_set_time_slice(args[0]),
+        SYSCALL_SHUTDOWN => sys_shutdown(args[0]),
+        SYSCALL_REBOOT => sys_reboot(),
+        SYSCALL_SET_STACK_ASLR => sys_set_stack_aslr(args[0]),
+        SYSCALL_FORK_EXEC => sys_fork_exec(args[0] as *const u8, args[1] as *const usize),
+        SYSCALL_WAITALL => sys_waitall(args[0] as *mut i32, args[1]),
         SYSCALL_THREAD_CREATE => sys_thread_create(args[0], args[1]),
+        SYSCALL_CLONE => sys_clone(args[0], args[1], args[2]),
         SYSCALL_GETTID => sys_gettid(),
-        SYSCALL_WAITTID => sys_waittid(args[0]) as isize,
+        SYSCALL_WAITTID => sys_waittid(args[0]),
         SYSCALL_MUTEX_CREATE => sys_mutex_create(args[0] == 1),
         SYSCALL_MUTEX_LOCK => sys_mutex_lock(args[0]),
         SYSCALL_MUTEX_UNLOCK => sys_mutex_unlock(args[0]),
@@ -73,10 +163,19 @@ pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
         SYSCALL_CONDVAR_CREATE => sys_condvar_create(args[0]),
         SYSCALL_CONDVAR_SIGNAL => sys_condvar_signal(args[0]),
         SYSCALL_CONDVAR_WAIT => sys_condvar_wait(args[0], args[1]),
+        SYSCALL_MQ_SEND => sys_mq_send(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_MQ_RECV => sys_mq_recv(args[0], args[1] as *mut u8, args[2]),
         SYSCALL_FRAMEBUFFER => sys_framebuffer(),
         SYSCALL_FRAMEBUFFER_FLUSH => sys_framebuffer_flush(),
         SYSCALL_EVENT_GET => sys_event_get(),
         SYSCALL_KEY_PRESSED => sys_key_pressed(),
-        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+        _ => {
+            warn!(
+                "[kernel] Unsupported syscall_id: {} from pid {}",
+                syscall_id,
+                current_process().getpid()
+            );
+            -(Errno::ENOSYS as isize)
+        }
     }
 }
@@ -1,75 +1,250 @@
-use crate::fs::{open_file, OpenFlags};
-use crate::mm::{translated_ref, translated_refmut, translated_str};
+use super::errno::Errno;
+use super::fs::{copy_in, copy_out};
+use crate::config::{MAX_TIME_SLICE, PAGE_SIZE};
+use crate::drivers::rtc::unix_time;
+use crate::fs::{open_file, OpenFlags, StatMode};
+use crate::mm::{
+    frame_stats, frames_allocated, set_stack_randomized, translated_byte_buffer_mut,
+    translated_str, MapPermission, MemorySet, PageTable, VirtAddr, VirtPageNum,
+};
+use crate::random::fill_random;
 use crate::task::{
-    current_process, current_task, current_user_token, exit_current_and_run_next, pid2process,
-    suspend_current_and_run_next, SignalFlags,
+    current_process, current_task, current_trap_cx, current_user_token, exit_current_and_run_next,
+    list_tasks, pid2process, reboot_system, schedule, send_signal, set_next_hint, shutdown_system,
+    suspend_current_and_run_next, task_count, wait_signal_current, SignalFlags, TaskInfo,
+    TaskStatus, IDLE_PID, MAX_SIG, SIGSTOP,
 };
-use crate::timer::get_time_ms;
+use crate::timer::{get_time_ms, get_time_ns};
 use alloc::string::String;
-use alloc::sync::Arc;
 use alloc::vec::Vec;
 
+#[repr(C)]
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
+/// Upper bound of frames walked by `sys_backtrace`, the fp chain may be garbage.
+const BACKTRACE_MAX_DEPTH: usize = 64;
+
+#[repr(C)]
+pub struct SysInfo {
+    pub total_frames: usize,
+    pub free_frames: usize,
+    pub num_tasks: usize,
+    /// frames allocated since boot, to compare the cost of two ways of
+    /// doing the same thing
+    pub frames_allocated: usize,
+}
+
+/// CPU time of a process, in us rather than clock ticks.
+#[repr(C)]
+pub struct Tms {
+    pub utime: usize,
+    pub stime: usize,
+    /// sum of the times of the reaped children
+    pub cutime: usize,
+    pub cstime: usize,
+}
+
 pub fn sys_exit(exit_code: i32) -> ! {
     exit_current_and_run_next(exit_code);
     panic!("Unreachable in sys_exit!");
 }
 
+/// Only the initial process may power off or reboot the machine or change
+/// system-wide settings, so that ordinary programs can not; in debug builds
+/// anyone may, for test harnesses.
+fn is_privileged() -> bool {
+    cfg!(debug_assertions) || current_process().getpid() == IDLE_PID
+}
+
+/// Write the file systems back and leave qemu with `exit_code`.
+pub fn sys_shutdown(exit_code: usize) -> isize {
+    if !is_privileged() {
+        return -(Errno::EPERM as isize);
+    }
+    shutdown_system(exit_code as u32)
+}
+
+/// Write the file systems back and boot again.
+pub fn sys_reboot() -> isize {
+    if !is_privileged() {
+        return -(Errno::EPERM as isize);
+    }
+    reboot_system()
+}
+
 pub fn sys_yield() -> isize {
     suspend_current_and_run_next();
     0
 }
 
+/// Let the calling thread run `ticks` timer ticks before it is preempted.
+pub fn sys_set_time_slice(ticks: usize) -> isize {
+    if !(1..=MAX_TIME_SLICE).contains(&ticks) {
+        return -(Errno::EINVAL as isize);
+    }
+    current_task().unwrap().inner_exclusive_access().time_slice = ticks;
+    0
+}
+
+/// Randomize the user stack base of the programs loaded from now on if
+/// `enabled` is not 0, return 1 if it was randomized before, else 0.
+/// Privileged like `sys_shutdown`, return -EPERM otherwise.
+pub fn sys_set_stack_aslr(enabled: usize) -> isize {
+    if !is_privileged() {
+        return -(Errno::EPERM as isize);
+    }
+    set_stack_randomized(enabled != 0) as isize
+}
+
+/// Yield to a ready task of process `pid`. If there is no such process,
+/// return -ESRCH, if it has no ready task, return -EAGAIN; in both cases
+/// the cpu is still given up as in `sys_yield`.
+pub fn sys_yield_to(pid: usize) -> isize {
+    let ret = if pid2process(pid).is_none() {
+        -(Errno::ESRCH as isize)
+    } else if !set_next_hint(pid) {
+        -(Errno::EAGAIN as isize)
+    } else {
+        0
+    };
+    suspend_current_and_run_next();
+    ret
+}
+
 pub fn sys_get_time() -> isize {
     get_time_ms() as isize
 }
 
+/// Wall-clock seconds since the Unix epoch.
+pub fn sys_time() -> isize {
+    unix_time() as isize
+}
+
+/// Both clocks count from boot, so `clock_id` is ignored.
+pub fn sys_clock_gettime(_clock_id: usize, ts: *mut TimeSpec) -> isize {
+    let token = current_user_token();
+    let ns = get_time_ns();
+    let value = TimeSpec {
+        sec: ns / 1_000_000_000,
+        nsec: ns % 1_000_000_000,
+    };
+    copy_out(token, ts, &value)
+}
+
 pub fn sys_getpid() -> isize {
     current_task().unwrap().process.upgrade().unwrap().getpid() as isize
 }
 
+/// Orphans are adopted by initproc, so this returns its pid once the parent exits.
+/// initproc has no parent and gets its own pid.
+pub fn sys_getppid() -> isize {
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    match inner.parent.as_ref().and_then(|parent| parent.upgrade()) {
+        Some(parent) => parent.getpid() as isize,
+        None => process.getpid() as isize,
+    }
+}
+
 pub fn sys_fork() -> isize {
     let current_process = current_process();
-    let new_process = current_process.fork();
-    let new_pid = new_process.getpid();
-    // modify trap context of new_task, because it returns immediately after switching
-    let new_process_inner = new_process.inner_exclusive_access();
-    let task = new_process_inner.tasks[0].as_ref().unwrap();
-    let trap_cx = task.inner_exclusive_access().get_trap_cx();
+    let new_process = match current_process.fork() {
+        Some(new_process) => new_process,
+        None => return -(Errno::ENOMEM as isize),
+    };
+    // the child returns 0, see `ProcessControlBlock::fork`;
     // we do not have to move to next instruction since we have done it before
-    // for child process, fork returns 0
-    trap_cx.x[10] = 0;
-    new_pid as isize
+    new_process.getpid() as isize
 }
 
-pub fn sys_exec(path: *const u8, mut args: *const usize) -> isize {
-    let token = current_user_token();
-    let path = translated_str(token, path);
+/// Copy the null-terminated array of strings `args` from user space,
+/// `None` if the array or one of the strings is not readable.
+fn translated_args(token: usize, mut args: *const usize) -> Option<Vec<String>> {
     let mut args_vec: Vec<String> = Vec::new();
     loop {
-        let arg_str_ptr = *translated_ref(token, args);
+        let mut arg_str_ptr = 0usize;
+        copy_in(token, args, &mut arg_str_ptr)?;
         if arg_str_ptr == 0 {
             break;
         }
-        args_vec.push(translated_str(token, arg_str_ptr as *const u8));
-        unsafe {
-            args = args.add(1);
-        }
+        args_vec.push(translated_str(token, arg_str_ptr as *const u8)?);
+        args = args.wrapping_add(1);
     }
-    if let Some(app_inode) = open_file(path.as_str(), OpenFlags::RDONLY) {
-        let all_data = app_inode.read_all();
-        let process = current_process();
-        let argc = args_vec.len();
-        process.exec(all_data.as_slice(), args_vec);
-        // return argc because cx.x[10] will be covered with it later
-        argc as isize
-    } else {
-        -1
+    Some(args_vec)
+}
+
+/// Read the executable file at `path`, an elf file with the exec bit set.
+fn read_program(path: &str) -> Result<Vec<u8>, Errno> {
+    let app_inode = open_file(path, OpenFlags::RDONLY).ok_or(Errno::ENOENT)?;
+    if app_inode.is_dir() || app_inode.mode() as u32 & StatMode::S_IXUSR.bits() == 0 {
+        return Err(Errno::EACCES);
+    }
+    let all_data = app_inode.read_all();
+    if !MemorySet::is_elf(all_data.as_slice()) {
+        return Err(Errno::ENOEXEC);
+    }
+    Ok(all_data)
+}
+
+pub fn sys_exec(path: *const u8, args: *const usize) -> isize {
+    let token = current_user_token();
+    let (path, args_vec) = match (translated_str(token, path), translated_args(token, args)) {
+        (Some(path), Some(args_vec)) => (path, args_vec),
+        _ => return -(Errno::EFAULT as isize),
+    };
+    // check before the old image is torn down, so that the caller survives
+    let all_data = match read_program(path.as_str()) {
+        Ok(all_data) => all_data,
+        Err(errno) => return -(errno as isize),
+    };
+    let process = current_process();
+    let argc = args_vec.len();
+    if process.exec(all_data.as_slice(), args_vec).is_none() {
+        println!("[kernel] Out of memory during exec, killed");
+        drop(all_data);
+        drop(process);
+        exit_current_and_run_next(-(Errno::ENOMEM as i32));
+    }
+    // return argc because cx.x[10] will be covered with it later
+    argc as isize
+}
+
+/// Start the program `path` with `args` in a new child and return its pid,
+/// like a fork followed by an exec in the child but without copying the
+/// address space of the caller. Fail with the errors of `sys_exec` or ENOMEM.
+pub fn sys_fork_exec(path: *const u8, args: *const usize) -> isize {
+    let token = current_user_token();
+    let (path, args_vec) = match (translated_str(token, path), translated_args(token, args)) {
+        (Some(path), Some(args_vec)) => (path, args_vec),
+        _ => return -(Errno::EFAULT as isize),
+    };
+    let all_data = match read_program(path.as_str()) {
+        Ok(all_data) => all_data,
+        Err(errno) => return -(errno as isize),
+    };
+    match current_process().fork_exec(all_data.as_slice(), args_vec) {
+        Some(child) => child.getpid() as isize,
+        None => -(Errno::ENOMEM as isize),
     }
 }
 
-/// If there is not a child process whose pid is same as given, return -1.
-/// Else if there is a child process but it is still running, return -2.
+/// Reap any child if `pid` is -1, or else only the child `pid`.
+/// If there is not a child process whose pid is same as given, return -ECHILD.
+/// Else if there is a child process but it is still running, return -EAGAIN.
+/// The exit code is not stored if `exit_code_ptr` is null, and the child is
+/// left alone with -EFAULT if `exit_code_ptr` is not writable.
 pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
+    let token = current_user_token();
+    // check before a child is reaped, faulting the page in needs the PCB
+    if !exit_code_ptr.is_null()
+        && translated_byte_buffer_mut(token, exit_code_ptr as *mut u8, core::mem::size_of::<i32>())
+            .is_none()
+    {
+        return -(Errno::EFAULT as isize);
+    }
     let process = current_process();
     // find a child process
 
@@ -79,7 +254,7 @@ pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
         .iter()
         .any(|p| pid == -1 || pid as usize == p.getpid())
     {
-        return -1;
+        return -(Errno::ECHILD as isize);
         // ---- release current PCB
     }
     let pair = inner.children.iter().enumerate().find(|(_, p)| {
@@ -88,30 +263,411 @@ pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
         // ++++ release child PCB
     });
     if let Some((idx, _)) = pair {
-        let child = inner.children.remove(idx);
-        // confirm that child will be deallocated after being removed from children list
-        assert_eq!(Arc::strong_count(&child), 1);
-        let found_pid = child.getpid();
-        // ++++ temporarily access child PCB exclusively
-        let exit_code = child.inner_exclusive_access().exit_code;
-        // ++++ release child PCB
-        *translated_refmut(inner.memory_set.token(), exit_code_ptr) = exit_code;
+        let (found_pid, exit_code) = inner.reap_child(idx);
+        // the page may have to be faulted in, which needs the PCB
+        drop(inner);
+        if !exit_code_ptr.is_null() {
+            copy_out(token, exit_code_ptr, &exit_code);
+        }
         found_pid as isize
     } else {
-        -2
+        -(Errno::EAGAIN as isize)
     }
     // ---- release current PCB automatically
 }
 
+/// Reap up to `max` exited children at once, storing their exit codes in
+/// the array `codes`. Return how many were reaped, 0 if none has exited yet,
+/// or -ECHILD if there are no children at all.
+pub fn sys_waitall(codes: *mut i32, max: usize) -> isize {
+    let token = current_user_token();
+    let process = current_process();
+    if process.inner_exclusive_access().children.is_empty() {
+        return -(Errno::ECHILD as isize);
+    }
+    // check the array before any child is gone, faulting it in needs the PCB
+    let buffers = match max
+        .checked_mul(core::mem::size_of::<i32>())
+        .and_then(|len| translated_byte_buffer_mut(token, codes as *mut u8, len))
+    {
+        Some(buffers) => buffers,
+        None => return -(Errno::EFAULT as isize),
+    };
+    let mut inner = process.inner_exclusive_access();
+    let mut exit_codes = Vec::new();
+    let mut idx = 0;
+    while idx < inner.children.len() && exit_codes.len() < max {
+        if inner.children[idx].inner_exclusive_access().is_zombie {
+            exit_codes.push(inner.reap_child(idx).1);
+        } else {
+            idx += 1;
+        }
+    }
+    drop(inner);
+    let bytes = exit_codes.iter().flat_map(|code| code.to_ne_bytes());
+    for (dst, byte) in buffers.into_iter().flatten().zip(bytes) {
+        *dst = byte;
+    }
+    exit_codes.len() as isize
+}
+
 pub fn sys_kill(pid: usize, signal: u32) -> isize {
     if let Some(process) = pid2process(pid) {
         if let Some(flag) = SignalFlags::from_bits(signal) {
-            process.inner_exclusive_access().signals |= flag;
+            send_signal(&process, flag);
             0
         } else {
-            -1
+            -(Errno::EINVAL as isize)
         }
     } else {
-        -1
+        -(Errno::ESRCH as isize)
+    }
+}
+
+/// Arm the interval timer of the current process: SIGALRM is raised after
+/// `delay_ms` and then every `interval_ms`. A zero `delay_ms` disarms it.
+pub fn sys_setitimer(delay_ms: usize, interval_ms: usize) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    inner.alarm_expire_ms = if delay_ms == 0 {
+        None
+    } else {
+        Some(get_time_ms() + delay_ms)
+    };
+    inner.alarm_interval_ms = interval_ms;
+    0
+}
+
+/// Install `handler` for `signum` and return the previous one; 0 restores the default action.
+pub fn sys_sigaction(signum: usize, handler: usize) -> isize {
+    // SIGSTOP can not be caught
+    if signum == 0 || signum > MAX_SIG || signum == SIGSTOP {
+        return -(Errno::EINVAL as isize);
+    }
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    let old_handler = inner.signal_actions[signum];
+    inner.signal_actions[signum] = handler;
+    old_handler as isize
+}
+
+/// Wait until a signal is sent to the current process, which always
+/// interrupts the call. A caught signal runs its handler before the return.
+pub fn sys_pause() -> isize {
+    schedule(wait_signal_current(TaskStatus::Blocking));
+    -(Errno::EINTR as isize)
+}
+
+/// Return from a signal handler to the interrupted user context.
+pub fn sys_sigreturn() -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if let Some(trap_ctx) = inner.trap_ctx_backup.take() {
+        inner.handling_sig = None;
+        let trap_cx = current_trap_cx();
+        *trap_cx = trap_ctx;
+        // a0 is overwritten by the return value of this syscall
+        trap_cx.x[10] as isize
+    } else {
+        -(Errno::EINVAL as isize)
+    }
+}
+
+/// Fill `tms` with the CPU time of the current process and its reaped
+/// children, see `Tms`.
+pub fn sys_times(tms: *mut Tms) -> isize {
+    let token = current_user_token();
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    let (utime, stime) = inner.runtime();
+    let (cutime, cstime) = (
+        inner.children_runtime_in_user,
+        inner.children_runtime_in_kernel,
+    );
+    // the page may have to be faulted in, which needs the PCB
+    drop(inner);
+    let value = Tms {
+        utime,
+        stime,
+        cutime,
+        cstime,
+    };
+    copy_out(token, tms, &value)
+}
+
+pub fn sys_sysinfo(info: *mut SysInfo) -> isize {
+    let token = current_user_token();
+    let (total_frames, free_frames) = frame_stats();
+    let value = SysInfo {
+        total_frames,
+        free_frames,
+        num_tasks: task_count(),
+        frames_allocated: frames_allocated(),
+    };
+    copy_out(token, info, &value)
+}
+
+/// Fill `buf` with `len` pseudo-random bytes, return how many were written.
+pub fn sys_getrandom(buf: *mut u8, len: usize) -> isize {
+    let token = current_user_token();
+    let buffers = match translated_byte_buffer_mut(token, buf, len) {
+        Some(buffers) => buffers,
+        None => return -(Errno::EFAULT as isize),
+    };
+    for buffer in buffers {
+        fill_random(buffer);
+    }
+    len as isize
+}
+
+/// Linux `PROT_*` bits, which are `MapPermission` shifted right by one.
+const PROT_MASK: usize = 0b111;
+
+/// `mmap` only hands out the lower half of sv39, well below the trap
+/// contexts and the trampoline.
+const MMAP_END: usize = 1 << 38;
+
+/// User page permission of `prot`, `None` if no pte can have it.
+fn prot_to_permission(prot: usize) -> Option<MapPermission> {
+    if prot & !PROT_MASK != 0 {
+        return None;
+    }
+    let permission = MapPermission::from_bits_truncate((prot << 1) as u8) | MapPermission::U;
+    // a leaf pte needs R or X, and W without R is reserved in sv39
+    if !permission.intersects(MapPermission::R | MapPermission::X)
+        || (permission.contains(MapPermission::W) && !permission.contains(MapPermission::R))
+    {
+        return None;
+    }
+    Some(permission)
+}
+
+/// `sys_mmap` flags, exactly one of them must be given.
+const MAP_SHARED: usize = 0x01;
+const MAP_PRIVATE: usize = 0x02;
+
+/// Map `len` bytes of zeroed memory at the page aligned `start`, or at a free
+/// place the kernel picks if `start` is 0, and return the address.
+/// `len` is rounded up to whole pages, like Linux does, and the range may
+/// neither wrap around nor overlap any area of the process.
+/// Private pages share one zero frame until they are first written, and
+/// are copied by fork. `MAP_SHARED` pages are shared with forked children.
+pub fn sys_mmap(start: usize, len: usize, prot: usize, flags: usize) -> isize {
+    let start_va = VirtAddr::from(start);
+    if !start_va.aligned() || len == 0 || len > MMAP_END {
+        return -(Errno::EINVAL as isize);
+    }
+    let shared = match flags {
+        MAP_SHARED => true,
+        MAP_PRIVATE => false,
+        _ => return -(Errno::EINVAL as isize),
+    };
+    let permission = match prot_to_permission(prot) {
+        Some(permission) => permission,
+        None => return -(Errno::EINVAL as isize),
+    };
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    let (start_vpn, end_vpn) = if start == 0 {
+        let pages = VirtAddr::from(len).ceil().0;
+        // the null page stays unmapped
+        match inner.memory_set.find_free_area(
+            pages,
+            VirtAddr::from(PAGE_SIZE).floor(),
+            VirtAddr::from(MMAP_END).floor(),
+        ) {
+            Some(start_vpn) => (start_vpn, VirtPageNum(start_vpn.0 + pages)),
+            None => return -(Errno::ENOMEM as isize),
+        }
+    } else {
+        let end_va = match start.checked_add(len) {
+            Some(end) if end <= MMAP_END => VirtAddr::from(end),
+            _ => return -(Errno::EINVAL as isize),
+        };
+        let (start_vpn, end_vpn) = (start_va.floor(), end_va.ceil());
+        if inner.memory_set.overlaps(start_vpn, end_vpn) {
+            return -(Errno::EEXIST as isize);
+        }
+        (start_vpn, end_vpn)
+    };
+    match inner
+        .memory_set
+        .mmap(start_vpn, end_vpn, permission, shared)
+    {
+        Some(()) => VirtAddr::from(start_vpn).0 as isize,
+        None => -(Errno::ENOMEM as isize),
+    }
+}
+
+/// Unmap `[start, start + len)`, which must have been mapped by `sys_mmap`.
+pub fn sys_munmap(start: usize, len: usize) -> isize {
+    let start_va = VirtAddr::from(start);
+    if !start_va.aligned() || len == 0 {
+        return -(Errno::EINVAL as isize);
+    }
+    let end_va = match start.checked_add(len) {
+        Some(end) if end <= MMAP_END => VirtAddr::from(end),
+        _ => return -(Errno::EINVAL as isize),
+    };
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    match inner.memory_set.munmap(start_va.floor(), end_va.ceil()) {
+        Some(()) => 0,
+        None => -(Errno::EINVAL as isize),
+    }
+}
+
+/// `sys_madvise` advice: the pages in the range are not needed any more.
+const MADV_DONTNEED: usize = 4;
+
+/// Only `MADV_DONTNEED` is supported: free the frames of the pages in
+/// `[start, start + len)`, which must be mapped by a private `mmap`. The
+/// mapping stays, and the pages read as zero until written again.
+pub fn sys_madvise(start: usize, len: usize, advice: usize) -> isize {
+    let start_va = VirtAddr::from(start);
+    if !start_va.aligned() || advice != MADV_DONTNEED {
+        return -(Errno::EINVAL as isize);
+    }
+    let end_va = match start.checked_add(len) {
+        Some(end) if end <= MMAP_END => VirtAddr::from(end),
+        _ => return -(Errno::EINVAL as isize),
+    };
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    match inner
+        .memory_set
+        .madvise_dontneed(start_va.floor(), end_va.ceil())
+    {
+        Some(_) => 0,
+        None => -(Errno::EINVAL as isize),
+    }
+}
+
+/// Change the permission of the user pages in `[start, start + len)`.
+pub fn sys_mprotect(start: usize, len: usize, prot: usize) -> isize {
+    let start_va = VirtAddr::from(start);
+    if !start_va.aligned() {
+        return -(Errno::EINVAL as isize);
+    }
+    let permission = match prot_to_permission(prot) {
+        Some(permission) => permission,
+        None => return -(Errno::EINVAL as isize),
+    };
+    let end_va = match start.checked_add(len) {
+        Some(end) => VirtAddr::from(end),
+        None => return -(Errno::EINVAL as isize),
+    };
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    match inner
+        .memory_set
+        .mprotect(start_va.floor(), end_va.ceil(), permission)
+    {
+        Some(()) => 0,
+        None => -(Errno::ENOMEM as isize),
+    }
+}
+
+/// Read a usize from a user page of the space `token`, `None` if it is not there.
+fn read_user_usize(token: usize, va: usize) -> Option<usize> {
+    if va % core::mem::size_of::<usize>() != 0 {
+        return None;
+    }
+    let page_table = PageTable::from_token(token);
+    let va = VirtAddr::from(va);
+    let pte = page_table.translate(va.floor())?;
+    if !pte.is_valid() || !pte.readable() || !pte.user_accessible() {
+        return None;
+    }
+    let pa: usize = page_table.translate_va(va)?.into();
+    Some(unsafe { (pa as *const usize).read_volatile() })
+}
+
+/// Walk the user stack of the main thread of process `pid` along its saved fp chain,
+/// write the pc and then return addresses to `buf`, at most `max` entries.
+/// Return the number of entries written, -ESRCH if there is no such process,
+/// -EPERM if it is neither the caller nor a child of it, or -EFAULT if `buf`
+/// is not writable.
+pub fn sys_backtrace(pid: usize, buf: *mut usize, max: usize) -> isize {
+    let target = match pid2process(pid) {
+        Some(target) => target,
+        None => return -(Errno::ESRCH as isize),
+    };
+    let caller_pid = current_process().getpid();
+    let parent_pid = target
+        .inner_exclusive_access()
+        .parent
+        .as_ref()
+        .and_then(|parent| parent.upgrade())
+        .map(|parent| parent.getpid());
+    if pid != caller_pid && parent_pid != Some(caller_pid) {
+        return -(Errno::EPERM as isize);
+    }
+    let mut addrs: Vec<usize> = Vec::new();
+    let target_inner = target.inner_exclusive_access();
+    let target_token = target_inner.get_user_token();
+    let task = target_inner.get_task(0);
+    drop(target_inner);
+    let task_inner = task.inner_exclusive_access();
+    let trap_cx = task_inner.get_trap_cx();
+    let pc = trap_cx.sepc;
+    // s0 is the frame pointer
+    let mut fp = trap_cx.x[8];
+    drop(task_inner);
+    let max = max.min(BACKTRACE_MAX_DEPTH);
+    if max > 0 {
+        addrs.push(pc);
+    }
+    // ra is saved at fp - 8 and the caller's fp at fp - 16
+    while addrs.len() < max && fp != 0 {
+        let ra = match read_user_usize(target_token, fp.wrapping_sub(8)) {
+            Some(ra) => ra,
+            None => break,
+        };
+        let prev_fp = match read_user_usize(target_token, fp.wrapping_sub(16)) {
+            Some(prev_fp) => prev_fp,
+            None => break,
+        };
+        if ra == 0 {
+            break;
+        }
+        addrs.push(ra);
+        // the stack grows downwards, so a sane chain only moves up
+        if prev_fp <= fp {
+            break;
+        }
+        fp = prev_fp;
+    }
+    let token = current_user_token();
+    for (i, addr) in addrs.iter().enumerate() {
+        if copy_out(token, buf.wrapping_add(i), addr) != 0 {
+            return -(Errno::EFAULT as isize);
+        }
+    }
+    addrs.len() as isize
+}
+
+/// Fill `buf` with at most `max` entries, one per live thread,
+/// and return the number of live threads.
+pub fn sys_listtasks(buf: *mut TaskInfo, max: usize) -> isize {
+    let token = current_user_token();
+    let infos = list_tasks();
+    let count = infos.len().min(max);
+    let info_bytes = unsafe {
+        core::slice::from_raw_parts(
+            infos.as_ptr() as *const u8,
+            count * core::mem::size_of::<TaskInfo>(),
+        )
+    };
+    // the user buffer may span several pages
+    let buffers = match translated_byte_buffer_mut(token, buf as *mut u8, info_bytes.len()) {
+        Some(buffers) => buffers,
+        None => return -(Errno::EFAULT as isize),
+    };
+    let mut copied = 0;
+    for buffer in buffers {
+        buffer.copy_from_slice(&info_bytes[copied..copied + buffer.len()]);
+        copied += buffer.len();
     }
+    infos.len() as isize
 }
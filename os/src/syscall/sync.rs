@@ -1,14 +1,54 @@
-use crate::sync::{Condvar, Mutex, MutexBlocking, MutexSpin, Semaphore};
-use crate::task::{block_current_and_run_next, current_process, current_task};
-use crate::timer::{add_timer, get_time_ms};
+use super::errno::Errno;
+use super::fs::copy_in;
+use super::process::TimeSpec;
+use crate::mm::{translated_byte_buffer, translated_byte_buffer_mut};
+use crate::sync::{
+    mq_recv, mq_send, Condvar, Mutex, MutexBlocking, MutexSpin, Semaphore, MQ_MSG_MAX,
+};
+use crate::task::{current_process, current_user_token};
+use crate::timer::{block_current_until, get_time_ms, get_time_ns};
 use alloc::sync::Arc;
 
 pub fn sys_sleep(ms: usize) -> isize {
     let expire_ms = get_time_ms() + ms;
-    let task = current_task().unwrap();
-    add_timer(expire_ms, task);
-    block_current_and_run_next();
-    0
+    block_current_until(expire_ms);
+    // the timer only wakes us once the deadline has passed, anything else
+    // cut the sleep short and gets the remaining time back
+    expire_ms.saturating_sub(get_time_ms()) as isize
+}
+
+/// `flags` bit of `clock_nanosleep`: `req` is a deadline, not a duration.
+const TIMER_ABSTIME: usize = 1;
+
+/// Sleep until the clock of `clock_gettime` reaches `req`, or for `req` if
+/// `flags` lacks `TIMER_ABSTIME`. A deadline already passed returns at once.
+/// Return -EINTR if something else wakes the task before the deadline,
+/// -EFAULT if `req` is not readable.
+pub fn sys_clock_nanosleep(_clock_id: usize, flags: usize, req: *const TimeSpec) -> isize {
+    let token = current_user_token();
+    let mut value = TimeSpec { sec: 0, nsec: 0 };
+    if copy_in(token, req, &mut value).is_none() {
+        return -(Errno::EFAULT as isize);
+    }
+    let TimeSpec { sec, nsec } = value;
+    if nsec >= 1_000_000_000 {
+        return -(Errno::EINVAL as isize);
+    }
+    let mut deadline_ns = sec.saturating_mul(1_000_000_000).saturating_add(nsec);
+    if flags & TIMER_ABSTIME == 0 {
+        deadline_ns = deadline_ns.saturating_add(get_time_ns());
+    }
+    if get_time_ns() >= deadline_ns {
+        return 0;
+    }
+    // timers count in ms, round up so that the deadline is never early
+    let expire_ms = deadline_ns.saturating_add(999_999) / 1_000_000;
+    block_current_until(expire_ms);
+    if get_time_ms() < expire_ms {
+        -(Errno::EINTR as isize)
+    } else {
+        0
+    }
 }
 
 pub fn sys_mutex_create(blocking: bool) -> isize {
@@ -132,3 +172,48 @@ pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
     condvar.wait_with_mutex(mutex);
     0
 }
+
+/// Send `len` bytes at `buf` as one message to queue `id`, creating it
+/// first if needed, and block while the queue is full. Return -ENOSPC if it
+/// would be one queue too many.
+pub fn sys_mq_send(id: usize, buf: *const u8, len: usize) -> isize {
+    if len > MQ_MSG_MAX {
+        return -(Errno::EMSGSIZE as isize);
+    }
+    let msg = match translated_byte_buffer(current_user_token(), buf, len) {
+        Some(buffers) => buffers
+            .into_iter()
+            .flat_map(|slice| slice.iter().copied())
+            .collect(),
+        None => return -(Errno::EFAULT as isize),
+    };
+    match mq_send(id, msg) {
+        Ok(()) => 0,
+        Err(errno) => -(errno as isize),
+    }
+}
+
+/// Receive the oldest message of queue `id` into `buf`, blocking while the
+/// queue is empty, and return its length. A message longer than `len` is
+/// left in the queue. Return -ENOENT if nothing was ever sent to `id`.
+pub fn sys_mq_recv(id: usize, buf: *mut u8, len: usize) -> isize {
+    // checked before a message is taken off the queue, so that none is lost
+    if translated_byte_buffer_mut(current_user_token(), buf, len).is_none() {
+        return -(Errno::EFAULT as isize);
+    }
+    let msg = match mq_recv(id, len) {
+        Ok(msg) => msg,
+        Err(errno) => return -(errno as isize),
+    };
+    let mut copied = 0;
+    // again, another thread may have unmapped it while this one was blocked
+    let buffers = match translated_byte_buffer_mut(current_user_token(), buf, msg.len()) {
+        Some(buffers) => buffers,
+        None => return -(Errno::EFAULT as isize),
+    };
+    for slice in buffers {
+        slice.copy_from_slice(&msg[copied..copied + slice.len()]);
+        copied += slice.len();
+    }
+    msg.len() as isize
+}
@@ -1,3 +1,4 @@
+use super::errno::Errno;
 use crate::{
     mm::kernel_token,
     task::{add_task, current_task, TaskControlBlock},
@@ -6,18 +7,28 @@ use crate::{
 use alloc::sync::Arc;
 
 pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
+    sys_clone(entry, 0, arg)
+}
+
+/// Like `sys_thread_create`, but the new thread starts on the caller-provided
+/// `stack` (its initial sp). A `stack` of 0 uses the thread's own user stack.
+pub fn sys_clone(entry: usize, stack: usize, arg: usize) -> isize {
+    if stack % 16 != 0 {
+        return -(Errno::EINVAL as isize);
+    }
     let task = current_task().unwrap();
     let process = task.process.upgrade().unwrap();
     // create a new thread
-    let new_task = Arc::new(TaskControlBlock::new(
-        Arc::clone(&process),
-        task.inner_exclusive_access()
-            .res
-            .as_ref()
-            .unwrap()
-            .ustack_base,
-        true,
-    ));
+    let ustack_base = task
+        .inner_exclusive_access()
+        .res
+        .as_ref()
+        .unwrap()
+        .ustack_base;
+    let new_task = match TaskControlBlock::new(Arc::clone(&process), ustack_base, true) {
+        Some(new_task) => Arc::new(new_task),
+        None => return -(Errno::ENOMEM as isize),
+    };
     // add new task to scheduler
     add_task(Arc::clone(&new_task));
     let new_task_inner = new_task.inner_exclusive_access();
@@ -31,9 +42,14 @@ pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
     }
     tasks[new_task_tid] = Some(Arc::clone(&new_task));
     let new_task_trap_cx = new_task_inner.get_trap_cx();
+    let sp = if stack != 0 {
+        stack
+    } else {
+        new_task_res.ustack_top()
+    };
     *new_task_trap_cx = TrapContext::app_init_context(
         entry,
-        new_task_res.ustack_top(),
+        sp,
         kernel_token(),
         new_task.kstack.get_top(),
         trap_handler as usize,
@@ -52,34 +68,35 @@ pub fn sys_gettid() -> isize {
         .tid as isize
 }
 
-/// thread does not exist, return -1
-/// thread has not exited yet, return -2
+/// thread is the caller, return -EDEADLK
+/// thread does not exist, return -ESRCH
+/// thread has not exited yet, return -EAGAIN
 /// otherwise, return thread's exit code
-pub fn sys_waittid(tid: usize) -> i32 {
+pub fn sys_waittid(tid: usize) -> isize {
     let task = current_task().unwrap();
     let process = task.process.upgrade().unwrap();
     let task_inner = task.inner_exclusive_access();
     let mut process_inner = process.inner_exclusive_access();
     // a thread cannot wait for itself
     if task_inner.res.as_ref().unwrap().tid == tid {
-        return -1;
+        return -(Errno::EDEADLK as isize);
     }
     let mut exit_code: Option<i32> = None;
-    let waited_task = process_inner.tasks[tid].as_ref();
+    let waited_task = process_inner.tasks.get(tid).and_then(|task| task.as_ref());
     if let Some(waited_task) = waited_task {
         if let Some(waited_exit_code) = waited_task.inner_exclusive_access().exit_code {
             exit_code = Some(waited_exit_code);
         }
     } else {
         // waited thread does not exist
-        return -1;
+        return -(Errno::ESRCH as isize);
     }
     if let Some(exit_code) = exit_code {
         // dealloc the exited thread
         process_inner.tasks[tid] = None;
-        exit_code
+        exit_code as isize
     } else {
         // waited thread has not exited
-        -2
+        -(Errno::EAGAIN as isize)
     }
 }
@@ -1,6 +1,6 @@
 use super::ProcessControlBlock;
 use crate::config::{KERNEL_STACK_SIZE, PAGE_SIZE, TRAMPOLINE, TRAP_CONTEXT_BASE, USER_STACK_SIZE};
-use crate::mm::{MapPermission, PhysPageNum, VirtAddr, KERNEL_SPACE};
+use crate::mm::{MapPermission, PhysPageNum, VirtAddr, VirtPageNum, KERNEL_SPACE};
 use crate::sync::UPIntrFreeCell;
 use alloc::{
     sync::{Arc, Weak},
@@ -37,6 +37,10 @@ impl RecycleAllocator {
         );
         self.recycled.push(id);
     }
+    /// Number of ids handed out and not deallocated yet.
+    pub fn in_use(&self) -> usize {
+        self.current - self.recycled.len()
+    }
 }
 
 lazy_static! {
@@ -54,6 +58,12 @@ pub fn pid_alloc() -> PidHandle {
     PidHandle(PID_ALLOCATOR.exclusive_access().alloc())
 }
 
+/// Number of live pids, the idle one included. A pid is freed when its
+/// process is reaped, so this also counts zombies.
+pub fn pids_in_use() -> usize {
+    PID_ALLOCATOR.exclusive_access().in_use()
+}
+
 impl Drop for PidHandle {
     fn drop(&mut self) {
         PID_ALLOCATOR.exclusive_access().dealloc(self.0);
@@ -69,15 +79,20 @@ pub fn kernel_stack_position(kstack_id: usize) -> (usize, usize) {
 
 pub struct KernelStack(pub usize);
 
-pub fn kstack_alloc() -> KernelStack {
+/// Return `None` when out of frames.
+pub fn kstack_alloc() -> Option<KernelStack> {
     let kstack_id = KSTACK_ALLOCATOR.exclusive_access().alloc();
     let (kstack_bottom, kstack_top) = kernel_stack_position(kstack_id);
-    KERNEL_SPACE.exclusive_access().insert_framed_area(
+    let mapped = KERNEL_SPACE.exclusive_access().insert_framed_area(
         kstack_bottom.into(),
         kstack_top.into(),
         MapPermission::R | MapPermission::W,
     );
-    KernelStack(kstack_id)
+    if mapped.is_none() {
+        KSTACK_ALLOCATOR.exclusive_access().dealloc(kstack_id);
+        return None;
+    }
+    Some(KernelStack(kstack_id))
 }
 
 impl Drop for KernelStack {
@@ -128,7 +143,7 @@ impl TaskUserRes {
         process: Arc<ProcessControlBlock>,
         ustack_base: usize,
         alloc_user_res: bool,
-    ) -> Self {
+    ) -> Option<Self> {
         let tid = process.inner_exclusive_access().alloc_tid();
         let task_user_res = Self {
             tid,
@@ -136,12 +151,14 @@ impl TaskUserRes {
             process: Arc::downgrade(&process),
         };
         if alloc_user_res {
-            task_user_res.alloc_user_res();
+            // on failure, drop releases the tid and whatever has been mapped
+            task_user_res.alloc_user_res()?;
         }
-        task_user_res
+        Some(task_user_res)
     }
 
-    pub fn alloc_user_res(&self) {
+    /// Return `None` when out of frames.
+    pub fn alloc_user_res(&self) -> Option<()> {
         let process = self.process.upgrade().unwrap();
         let mut process_inner = process.inner_exclusive_access();
         // alloc user stack
@@ -151,7 +168,7 @@ impl TaskUserRes {
             ustack_bottom.into(),
             ustack_top.into(),
             MapPermission::R | MapPermission::W | MapPermission::U,
-        );
+        )?;
         // alloc trap_cx
         let trap_cx_bottom = trap_cx_bottom_from_tid(self.tid);
         let trap_cx_top = trap_cx_bottom + PAGE_SIZE;
@@ -159,7 +176,7 @@ impl TaskUserRes {
             trap_cx_bottom.into(),
             trap_cx_top.into(),
             MapPermission::R | MapPermission::W,
-        );
+        )
     }
 
     fn dealloc_user_res(&self) {
@@ -215,6 +232,10 @@ impl TaskUserRes {
     pub fn ustack_top(&self) -> usize {
         ustack_bottom_from_tid(self.ustack_base, self.tid) + USER_STACK_SIZE
     }
+    /// The unmapped page right below the user stack, which an overflow hits first.
+    pub fn stack_guard_vpn(&self) -> VirtPageNum {
+        VirtAddr::from(ustack_bottom_from_tid(self.ustack_base, self.tid) - PAGE_SIZE).floor()
+    }
 }
 
 impl Drop for TaskUserRes {
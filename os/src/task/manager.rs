@@ -1,11 +1,14 @@
-use super::{ProcessControlBlock, TaskControlBlock};
+use super::{ProcessControlBlock, TaskControlBlock, TaskInfo, TaskStatus};
 use crate::sync::UPIntrFreeCell;
 use alloc::collections::{BTreeMap, VecDeque};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use lazy_static::*;
 
 pub struct TaskManager {
     ready_queue: VecDeque<Arc<TaskControlBlock>>,
+    /// pid of the process to run next, set by `sys_yield_to`
+    next_hint: Option<usize>,
 }
 
 /// A simple FIFO scheduler.
@@ -13,13 +16,51 @@ impl TaskManager {
     pub fn new() -> Self {
         Self {
             ready_queue: VecDeque::new(),
+            next_hint: None,
+        }
+    }
+    /// Whether `task` belongs to process `pid` and can run now.
+    fn is_ready_in(task: &TaskControlBlock, pid: usize) -> bool {
+        task.inner_exclusive_access().task_status == TaskStatus::Ready
+            && task
+                .process
+                .upgrade()
+                .map_or(false, |process| process.getpid() == pid)
+    }
+    /// Let the next `fetch` pick a ready task of process `pid` first.
+    /// Return false and change nothing if it has no ready task.
+    pub fn set_next_hint(&mut self, pid: usize) -> bool {
+        if self
+            .ready_queue
+            .iter()
+            .any(|task| Self::is_ready_in(task, pid))
+        {
+            self.next_hint = Some(pid);
+            true
+        } else {
+            false
         }
     }
     pub fn add(&mut self, task: Arc<TaskControlBlock>) {
         self.ready_queue.push_back(task);
     }
     pub fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
-        self.ready_queue.pop_front()
+        if let Some(pid) = self.next_hint.take() {
+            if let Some(idx) = self
+                .ready_queue
+                .iter()
+                .position(|task| Self::is_ready_in(task, pid))
+            {
+                return self.ready_queue.remove(idx);
+            }
+        }
+        // a blocked task is held by whoever will wake it up
+        while let Some(task) = self.ready_queue.pop_front() {
+            if task.inner_exclusive_access().task_status != TaskStatus::Blocking {
+                return Some(task);
+            }
+        }
+        None
     }
 }
 
@@ -38,6 +79,11 @@ pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
     TASK_MANAGER.exclusive_access().fetch()
 }
 
+/// Schedule a ready task of process `pid` next, false if there is none.
+pub fn set_next_hint(pid: usize) -> bool {
+    TASK_MANAGER.exclusive_access().set_next_hint(pid)
+}
+
 pub fn pid2process(pid: usize) -> Option<Arc<ProcessControlBlock>> {
     let map = PID2PCB.exclusive_access();
     map.get(&pid).map(Arc::clone)
@@ -47,6 +93,47 @@ pub fn insert_into_pid2process(pid: usize, process: Arc<ProcessControlBlock>) {
     PID2PCB.exclusive_access().insert(pid, process);
 }
 
+/// Number of threads that have not exited in all alive processes.
+pub fn task_count() -> usize {
+    let map = PID2PCB.exclusive_access();
+    map.values()
+        .map(|process| {
+            let inner = process.inner_exclusive_access();
+            inner
+                .tasks
+                .iter()
+                .flatten()
+                .filter(|task| task.inner_exclusive_access().exit_code.is_none())
+                .count()
+        })
+        .sum()
+}
+
+/// Snapshot of the threads that have not exited in all alive processes.
+pub fn list_tasks() -> Vec<TaskInfo> {
+    let map = PID2PCB.exclusive_access();
+    let mut infos = Vec::new();
+    for (&pid, process) in map.iter() {
+        let inner = process.inner_exclusive_access();
+        for task in inner.tasks.iter().flatten() {
+            let task_inner = task.inner_exclusive_access();
+            if task_inner.exit_code.is_some() {
+                continue;
+            }
+            infos.push(TaskInfo {
+                pid,
+                tid: task_inner.res.as_ref().unwrap().tid,
+                status: task_inner.task_status as usize,
+                user_time_us: task_inner.runtime_in_user,
+                kernel_time_us: task_inner.runtime_in_kernel,
+                minor_faults: task_inner.minor_faults,
+                major_faults: task_inner.major_faults,
+            });
+        }
+    }
+    infos
+}
+
 pub fn remove_from_pid2process(pid: usize) {
     let mut map = PID2PCB.exclusive_access();
     if map.remove(&pid).is_none() {
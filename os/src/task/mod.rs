@@ -3,28 +3,37 @@ mod id;
 mod manager;
 mod process;
 mod processor;
+mod reclaim;
 mod signal;
 mod switch;
 #[allow(clippy::module_inception)]
 mod task;
+mod wait_queue;
 
 use self::id::TaskUserRes;
 use crate::fs::{open_file, OpenFlags};
+use crate::timer::get_time_ms;
 use alloc::{sync::Arc, vec::Vec};
 use lazy_static::*;
-use manager::fetch_task;
+use manager::{fetch_task, PID2PCB};
 use process::ProcessControlBlock;
+use processor::take_need_resched;
 use switch::__switch;
 
 pub use context::TaskContext;
-pub use id::{kstack_alloc, pid_alloc, KernelStack, PidHandle, IDLE_PID};
-pub use manager::{add_task, pid2process, remove_from_pid2process};
+pub use id::{kstack_alloc, pid_alloc, pids_in_use, KernelStack, PidHandle, IDLE_PID};
+pub use manager::{
+    add_task, list_tasks, pid2process, remove_from_pid2process, set_next_hint, task_count,
+};
 pub use processor::{
     current_kstack_top, current_process, current_task, current_trap_cx, current_trap_cx_user_va,
-    current_user_token, run_tasks, schedule, take_current_task,
+    current_user_token, run_tasks, schedule, set_need_resched, take_current_task,
+    tick_current_task,
 };
-pub use signal::SignalFlags;
-pub use task::{TaskControlBlock, TaskStatus};
+pub use reclaim::{fault_in_current, fault_in_user, reclaim_frames};
+pub use signal::{SignalFlags, MAX_SIG, SIGCONT, SIGSTOP};
+pub use task::{TaskControlBlock, TaskInfo, TaskStatus};
+pub use wait_queue::WaitQueue;
 
 pub fn suspend_current_and_run_next() {
     // There must be an application running.
@@ -44,6 +53,15 @@ pub fn suspend_current_and_run_next() {
     schedule(task_cx_ptr);
 }
 
+/// Switch away if the time slice ran out while the current task was in
+/// a long syscall. The kernel never preempts itself at other places, so
+/// callers must not hold any borrow or lock here.
+pub fn preempt_point() {
+    if take_need_resched() {
+        suspend_current_and_run_next();
+    }
+}
+
 /// This function must be followed by a schedule
 pub fn block_current_task() -> *mut TaskContext {
     let task = take_current_task().unwrap();
@@ -56,6 +74,12 @@ pub fn block_current_and_run_next() {
     let task_cx_ptr = block_current_task();
     schedule(task_cx_ptr);
 }
+
+/// Mark a blocked task as ready and put it back to the ready queue.
+pub fn wakeup_task(task: Arc<TaskControlBlock>) {
+    task.inner_exclusive_access().task_status = TaskStatus::Ready;
+    add_task(task);
+}
 use crate::board::QEMUExit;
 
 pub fn exit_current_and_run_next(exit_code: i32) {
@@ -79,13 +103,7 @@ pub fn exit_current_and_run_next(exit_code: i32) {
                 "[kernel] Idle process exit with exit_code {} ...",
                 exit_code
             );
-            if exit_code != 0 {
-                //crate::sbi::shutdown(255); //255 == -1 for err hint
-                crate::board::QEMU_EXIT_HANDLE.exit_failure();
-            } else {
-                //crate::sbi::shutdown(0); //0 for success hint
-                crate::board::QEMU_EXIT_HANDLE.exit_success();
-            }
+            shutdown_system(if exit_code != 0 { 1 } else { 0 });
         }
         remove_from_pid2process(pid);
         let mut process_inner = process.inner_exclusive_access();
@@ -126,6 +144,7 @@ pub fn exit_current_and_run_next(exit_code: i32) {
         process_inner.memory_set.recycle_data_pages();
         // drop file descriptors
         process_inner.fd_table.clear();
+        process_inner.signal_waiters.clear();
     }
     drop(process);
     // we do not have to save task context
@@ -141,14 +160,131 @@ lazy_static! {
     };
 }
 
+/// Write the dirty blocks back, report the block cache statistics and the
+/// pids left, then leave qemu with `exit_code`.
+pub fn shutdown_system(exit_code: u32) -> ! {
+    crate::fs::sync_all();
+    crate::fs::log_cache_stats();
+    debug!("[kernel] {} pids in use", pids_in_use());
+    crate::console::console_flush();
+    if exit_code == 0 {
+        crate::board::QEMU_EXIT_HANDLE.exit_success()
+    } else {
+        crate::board::QEMU_EXIT_HANDLE.exit(exit_code)
+    }
+}
+
+/// Write the dirty blocks back and boot again, through the SBI if it can.
+pub fn reboot_system() -> ! {
+    crate::fs::sync_all();
+    crate::console::console_flush();
+    crate::sbi::warm_reboot();
+    crate::board::QEMU_EXIT_HANDLE.reset()
+}
+
 pub fn add_initproc() {
     let _initproc = INITPROC.clone();
 }
 
 pub fn check_signals_of_current() -> Option<(i32, &'static str)> {
+    current_process().inner_exclusive_access().fatal_signal()
+}
+
+/// Send `signal` to `process` and wake its threads waiting for a signal.
+///
+/// SIGSTOP and SIGCONT take effect at once instead of becoming pending;
+/// SIGCONT is still delivered to a user handler if there is one.
+pub fn send_signal(process: &ProcessControlBlock, signal: SignalFlags) {
+    let mut process_inner = process.inner_exclusive_access();
+    let mut pending = signal - SignalFlags::SIGSTOP;
+    if signal.contains(SignalFlags::SIGSTOP) {
+        process_inner.stopped = true;
+    }
+    if signal.contains(SignalFlags::SIGCONT) {
+        process_inner.stopped = false;
+        if process_inner.signal_actions[SIGCONT] == 0 {
+            pending.remove(SignalFlags::SIGCONT);
+        }
+    }
+    process_inner.signals |= pending;
+    let waiters = core::mem::take(&mut process_inner.signal_waiters);
+    drop(process_inner);
+    waiters.into_iter().for_each(wakeup_task);
+}
+
+/// Park the current thread until the next signal is sent to its process,
+/// showing `status` meanwhile. This function must be followed by a schedule.
+pub fn wait_signal_current(status: TaskStatus) -> *mut TaskContext {
     let process = current_process();
-    let process_inner = process.inner_exclusive_access();
-    process_inner.signals.check_error()
+    let mut process_inner = process.inner_exclusive_access();
+    let task = take_current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    task_inner.task_status = status;
+    let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
+    drop(task_inner);
+    process_inner.signal_waiters.push(task);
+    task_cx_ptr
+}
+
+/// Keep the current thread out of user mode while its process is stopped.
+/// A fatal signal ends the wait so that the process can be killed.
+pub fn wait_while_stopped() {
+    loop {
+        {
+            let process = current_process();
+            let process_inner = process.inner_exclusive_access();
+            if !process_inner.stopped || process_inner.fatal_signal().is_some() {
+                return;
+            }
+        }
+        schedule(wait_signal_current(TaskStatus::Stopped));
+    }
+}
+
+/// Redirect the current thread to the user handler of a pending signal.
+///
+/// The interrupted trap context is backed up and restored by `sys_sigreturn`.
+pub fn handle_signals() {
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    // handlers do not nest
+    if process_inner.handling_sig.is_some() {
+        return;
+    }
+    for signum in 0..=MAX_SIG {
+        let signal = SignalFlags::from_bits_truncate(1 << signum);
+        let handler = process_inner.signal_actions[signum];
+        if handler != 0 && process_inner.signals.contains(signal) {
+            process_inner.signals.remove(signal);
+            process_inner.handling_sig = Some(signum);
+            let trap_cx = current_trap_cx();
+            process_inner.trap_ctx_backup = Some(*trap_cx);
+            trap_cx.sepc = handler;
+            trap_cx.x[10] = signum;
+            break;
+        }
+    }
+}
+
+/// Raise SIGALRM for every process whose interval timer has expired.
+pub fn check_itimers() {
+    let current_ms = get_time_ms();
+    PID2PCB.exclusive_session(|map| {
+        for process in map.values() {
+            let mut process_inner = process.inner_exclusive_access();
+            if let Some(expire_ms) = process_inner.alarm_expire_ms {
+                if expire_ms <= current_ms {
+                    process_inner.alarm_expire_ms = if process_inner.alarm_interval_ms > 0 {
+                        Some(current_ms + process_inner.alarm_interval_ms)
+                    } else {
+                        None
+                    };
+                    drop(process_inner);
+                    send_signal(process, SignalFlags::SIGALRM);
+                }
+            }
+        }
+    });
 }
 
 pub fn current_add_signal(signal: SignalFlags) {
@@ -1,16 +1,18 @@
 use super::id::RecycleAllocator;
 use super::manager::insert_into_pid2process;
 use super::TaskControlBlock;
-use super::{add_task, SignalFlags};
+use super::{add_task, SignalFlags, MAX_SIG};
 use super::{pid_alloc, PidHandle};
-use crate::fs::{File, Stdin, Stdout};
+use crate::fs::{File, Stdin, Stdout, ROOT_INODE};
 use crate::mm::{translated_refmut, MemorySet, KERNEL_SPACE};
 use crate::sync::{Condvar, Mutex, Semaphore, UPIntrFreeCell, UPIntrRefMut};
 use crate::trap::{trap_handler, TrapContext};
+use alloc::collections::BTreeSet;
 use alloc::string::String;
 use alloc::sync::{Arc, Weak};
 use alloc::vec;
 use alloc::vec::Vec;
+use easy_fs::Inode;
 
 pub struct ProcessControlBlock {
     // immutable
@@ -26,7 +28,30 @@ pub struct ProcessControlBlockInner {
     pub children: Vec<Arc<ProcessControlBlock>>,
     pub exit_code: i32,
     pub fd_table: Vec<Option<Arc<dyn File + Send + Sync>>>,
+    /// fds closed automatically on exec
+    pub cloexec_fds: BTreeSet<usize>,
+    /// the current directory, inherited by children
+    pub cwd: Arc<Inode>,
+    /// permission bits cleared from files this process creates, inherited
+    pub umask: u16,
     pub signals: SignalFlags,
+    /// user handler address of each signal, 0 means default action
+    pub signal_actions: [usize; MAX_SIG + 1],
+    /// the signal whose user handler is running now
+    pub handling_sig: Option<usize>,
+    /// trap context of the interrupted thread, restored by sigreturn
+    pub trap_ctx_backup: Option<TrapContext>,
+    /// when the interval timer fires next, in ms
+    pub alarm_expire_ms: Option<usize>,
+    /// reload value of the interval timer, 0 means one-shot
+    pub alarm_interval_ms: usize,
+    /// set by SIGSTOP and cleared by SIGCONT, threads do not return to user mode meanwhile
+    pub stopped: bool,
+    /// threads in `sys_pause` or stopped, woken by the next signal
+    pub signal_waiters: Vec<Arc<TaskControlBlock>>,
+    /// user and kernel time of reaped children and their descendants, in us
+    pub children_runtime_in_user: usize,
+    pub children_runtime_in_kernel: usize,
     pub tasks: Vec<Option<Arc<TaskControlBlock>>>,
     pub task_res_allocator: RecycleAllocator,
     pub mutex_list: Vec<Option<Arc<dyn Mutex>>>,
@@ -35,18 +60,21 @@ pub struct ProcessControlBlockInner {
 }
 
 impl ProcessControlBlockInner {
-    #[allow(unused)]
     pub fn get_user_token(&self) -> usize {
         self.memory_set.token()
     }
 
     pub fn alloc_fd(&mut self) -> usize {
-        if let Some(fd) = (0..self.fd_table.len()).find(|fd| self.fd_table[*fd].is_none()) {
+        let fd = if let Some(fd) = (0..self.fd_table.len()).find(|fd| self.fd_table[*fd].is_none())
+        {
             fd
         } else {
             self.fd_table.push(None);
             self.fd_table.len() - 1
-        }
+        };
+        // a new fd never inherits the flag of a closed one
+        self.cloexec_fds.remove(&fd);
+        fd
     }
 
     pub fn alloc_tid(&mut self) -> usize {
@@ -57,6 +85,46 @@ impl ProcessControlBlockInner {
         self.task_res_allocator.dealloc(tid)
     }
 
+    /// (user, kernel) time of the threads still in `tasks`, in us.
+    pub fn runtime(&self) -> (usize, usize) {
+        self.tasks
+            .iter()
+            .flatten()
+            .fold((0, 0), |(user, kernel), task| {
+                let task_inner = task.inner_exclusive_access();
+                (
+                    user + task_inner.runtime_in_user,
+                    kernel + task_inner.runtime_in_kernel,
+                )
+            })
+    }
+
+    /// Remove the zombie `children[idx]` and charge its time to this process,
+    /// return its pid and exit code.
+    pub fn reap_child(&mut self, idx: usize) -> (usize, i32) {
+        let child = self.children.remove(idx);
+        // confirm that child will be deallocated after being removed from children list
+        assert_eq!(Arc::strong_count(&child), 1);
+        // ++++ temporarily access child PCB exclusively
+        let child_inner = child.inner_exclusive_access();
+        let (user, kernel) = child_inner.runtime();
+        self.children_runtime_in_user += user + child_inner.children_runtime_in_user;
+        self.children_runtime_in_kernel += kernel + child_inner.children_runtime_in_kernel;
+        (child.getpid(), child_inner.exit_code)
+    }
+
+    /// The pending signal that kills the process, if any.
+    pub fn fatal_signal(&self) -> Option<(i32, &'static str)> {
+        let mut signals = self.signals;
+        // signals caught by a user handler are never fatal
+        for signum in 0..=MAX_SIG {
+            if self.signal_actions[signum] != 0 {
+                signals.remove(SignalFlags::from_bits_truncate(1 << signum));
+            }
+        }
+        signals.check_error()
+    }
+
     pub fn thread_count(&self) -> usize {
         self.tasks.len()
     }
@@ -71,9 +139,13 @@ impl ProcessControlBlock {
         self.inner.exclusive_access()
     }
 
+    pub fn inner_try_exclusive_access(&self) -> Option<UPIntrRefMut<'_, ProcessControlBlockInner>> {
+        self.inner.try_exclusive_access()
+    }
+
     pub fn new(elf_data: &[u8]) -> Arc<Self> {
         // memory_set with elf program headers/trampoline/trap context/user stack
-        let (memory_set, ustack_base, entry_point) = MemorySet::from_elf(elf_data);
+        let (memory_set, ustack_base, entry_point) = MemorySet::from_elf(elf_data).unwrap();
         // allocate a pid
         let pid_handle = pid_alloc();
         let process = Arc::new(Self {
@@ -93,7 +165,19 @@ impl ProcessControlBlock {
                         // 2 -> stderr
                         Some(Arc::new(Stdout)),
                     ],
+                    cloexec_fds: BTreeSet::new(),
+                    cwd: ROOT_INODE.clone(),
+                    umask: 0o022,
                     signals: SignalFlags::empty(),
+                    signal_actions: [0; MAX_SIG + 1],
+                    handling_sig: None,
+                    trap_ctx_backup: None,
+                    alarm_expire_ms: None,
+                    alarm_interval_ms: 0,
+                    stopped: false,
+                    signal_waiters: Vec::new(),
+                    children_runtime_in_user: 0,
+                    children_runtime_in_kernel: 0,
                     tasks: Vec::new(),
                     task_res_allocator: RecycleAllocator::new(),
                     mutex_list: Vec::new(),
@@ -103,11 +187,7 @@ impl ProcessControlBlock {
             },
         });
         // create a main thread, we should allocate ustack and trap_cx here
-        let task = Arc::new(TaskControlBlock::new(
-            Arc::clone(&process),
-            ustack_base,
-            true,
-        ));
+        let task = Arc::new(TaskControlBlock::new(Arc::clone(&process), ustack_base, true).unwrap());
         // prepare trap_cx of main thread
         let task_inner = task.inner_exclusive_access();
         let trap_cx = task_inner.get_trap_cx();
@@ -132,64 +212,53 @@ impl ProcessControlBlock {
     }
 
     /// Only support processes with a single thread.
-    pub fn exec(self: &Arc<Self>, elf_data: &[u8], args: Vec<String>) {
+    ///
+    /// Return `None` when out of frames. The old image is gone by then,
+    /// so the caller can only kill the process.
+    pub fn exec(self: &Arc<Self>, elf_data: &[u8], args: Vec<String>) -> Option<()> {
         assert_eq!(self.inner_exclusive_access().thread_count(), 1);
+        // release frames of the old image first, so that the old and the new one
+        // never occupy physical memory at the same time
+        // elf_data and args have been copied into kernel heap by sys_exec
+        self.inner_exclusive_access()
+            .memory_set
+            .recycle_data_pages();
         // memory_set with elf program headers/trampoline/trap context/user stack
-        let (memory_set, ustack_base, entry_point) = MemorySet::from_elf(elf_data);
+        let (memory_set, ustack_base, entry_point) = MemorySet::from_elf(elf_data)?;
         let new_token = memory_set.token();
         // substitute memory_set
-        self.inner_exclusive_access().memory_set = memory_set;
+        let mut inner = self.inner_exclusive_access();
+        inner.memory_set = memory_set;
+        // user handlers are gone together with the old image
+        inner.signal_actions = [0; MAX_SIG + 1];
+        inner.handling_sig = None;
+        inner.trap_ctx_backup = None;
+        // other fds stay open across exec
+        for fd in core::mem::take(&mut inner.cloexec_fds) {
+            inner.fd_table[fd].take();
+        }
+        drop(inner);
         // then we alloc user resource for main thread again
         // since memory_set has been changed
         let task = self.inner_exclusive_access().get_task(0);
         let mut task_inner = task.inner_exclusive_access();
         task_inner.res.as_mut().unwrap().ustack_base = ustack_base;
-        task_inner.res.as_mut().unwrap().alloc_user_res();
+        task_inner.res.as_mut().unwrap().alloc_user_res()?;
         task_inner.trap_cx_ppn = task_inner.res.as_mut().unwrap().trap_cx_ppn();
-        // push arguments on user stack
-        let mut user_sp = task_inner.res.as_mut().unwrap().ustack_top();
-        user_sp -= (args.len() + 1) * core::mem::size_of::<usize>();
-        let argv_base = user_sp;
-        let mut argv: Vec<_> = (0..=args.len())
-            .map(|arg| {
-                translated_refmut(
-                    new_token,
-                    (argv_base + arg * core::mem::size_of::<usize>()) as *mut usize,
-                )
-            })
-            .collect();
-        *argv[args.len()] = 0;
-        for i in 0..args.len() {
-            user_sp -= args[i].len() + 1;
-            *argv[i] = user_sp;
-            let mut p = user_sp;
-            for c in args[i].as_bytes() {
-                *translated_refmut(new_token, p as *mut u8) = *c;
-                p += 1;
-            }
-            *translated_refmut(new_token, p as *mut u8) = 0;
-        }
-        // make the user_sp aligned to 8B for k210 platform
-        user_sp -= user_sp % core::mem::size_of::<usize>();
-        // initialize trap_cx
-        let mut trap_cx = TrapContext::app_init_context(
-            entry_point,
-            user_sp,
-            KERNEL_SPACE.exclusive_access().token(),
-            task.kstack.get_top(),
-            trap_handler as usize,
-        );
-        trap_cx.x[10] = args.len();
-        trap_cx.x[11] = argv_base;
-        *task_inner.get_trap_cx() = trap_cx;
+        task_inner.stack_guard_vpn = task_inner.res.as_ref().unwrap().stack_guard_vpn();
+        drop(task_inner);
+        init_main_thread(&task, new_token, entry_point, args);
+        Some(())
     }
 
-    /// Only support processes with a single thread.
-    pub fn fork(self: &Arc<Self>) -> Arc<Self> {
-        let mut parent = self.inner_exclusive_access();
-        assert_eq!(parent.thread_count(), 1);
-        // clone parent's memory_set completely including trampoline/ustacks/trap_cxs
-        let memory_set = MemorySet::from_existed_user(&parent.memory_set);
+    /// A child of this process running `memory_set`, with no thread yet and
+    /// not yet known to this process or the scheduler. It inherits the fds,
+    /// the working directory and the signal actions.
+    fn child_of(
+        self: &Arc<Self>,
+        parent: &ProcessControlBlockInner,
+        memory_set: MemorySet,
+    ) -> Arc<Self> {
         // alloc a pid
         let pid = pid_alloc();
         // copy fd table
@@ -202,7 +271,7 @@ impl ProcessControlBlock {
             }
         }
         // create child process pcb
-        let child = Arc::new(Self {
+        Arc::new(Self {
             pid,
             inner: unsafe {
                 UPIntrFreeCell::new(ProcessControlBlockInner {
@@ -212,7 +281,19 @@ impl ProcessControlBlock {
                     children: Vec::new(),
                     exit_code: 0,
                     fd_table: new_fd_table,
+                    cloexec_fds: parent.cloexec_fds.clone(),
+                    cwd: parent.cwd.clone(),
+                    umask: parent.umask,
                     signals: SignalFlags::empty(),
+                    signal_actions: parent.signal_actions,
+                    handling_sig: None,
+                    trap_ctx_backup: None,
+                    alarm_expire_ms: None,
+                    alarm_interval_ms: 0,
+                    stopped: false,
+                    signal_waiters: Vec::new(),
+                    children_runtime_in_user: 0,
+                    children_runtime_in_kernel: 0,
                     tasks: Vec::new(),
                     task_res_allocator: RecycleAllocator::new(),
                     mutex_list: Vec::new(),
@@ -220,9 +301,49 @@ impl ProcessControlBlock {
                     condvar_list: Vec::new(),
                 })
             },
-        });
-        // add child
+        })
+    }
+
+    /// Create a child running the program `elf_data` with `args`, as a fork
+    /// followed by an exec in the child would, but without copying this
+    /// address space first. Return `None` when out of frames.
+    pub fn fork_exec(self: &Arc<Self>, elf_data: &[u8], args: Vec<String>) -> Option<Arc<Self>> {
+        let (memory_set, ustack_base, entry_point) = MemorySet::from_elf(elf_data)?;
+        let token = memory_set.token();
+        let mut parent = self.inner_exclusive_access();
+        let child = self.child_of(&parent, memory_set);
+        let mut child_inner = child.inner_exclusive_access();
+        // what exec drops from the image of the fork
+        child_inner.signal_actions = [0; MAX_SIG + 1];
+        for fd in core::mem::take(&mut child_inner.cloexec_fds) {
+            child_inner.fd_table[fd].take();
+        }
+        drop(child_inner);
+        // unlike in fork, the user stack and trap_cx are allocated anew
+        let task = Arc::new(TaskControlBlock::new(
+            Arc::clone(&child),
+            ustack_base,
+            true,
+        )?);
+        init_main_thread(&task, token, entry_point, args);
         parent.children.push(Arc::clone(&child));
+        child
+            .inner_exclusive_access()
+            .tasks
+            .push(Some(Arc::clone(&task)));
+        insert_into_pid2process(child.getpid(), Arc::clone(&child));
+        add_task(task);
+        Some(child)
+    }
+
+    /// Only support processes with a single thread.
+    /// Return `None` when out of frames.
+    pub fn fork(self: &Arc<Self>) -> Option<Arc<Self>> {
+        let mut parent = self.inner_exclusive_access();
+        assert_eq!(parent.thread_count(), 1);
+        // clone parent's memory_set completely including trampoline/ustacks/trap_cxs
+        let memory_set = MemorySet::from_existed_user(&parent.memory_set)?;
+        let child = self.child_of(&parent, memory_set);
         // create main thread of child process
         let task = Arc::new(TaskControlBlock::new(
             Arc::clone(&child),
@@ -236,7 +357,9 @@ impl ProcessControlBlock {
             // here we do not allocate trap_cx or ustack again
             // but mention that we allocate a new kstack here
             false,
-        ));
+        )?);
+        // add child only when nothing can fail any more
+        parent.children.push(Arc::clone(&child));
         // attach task to child process
         let mut child_inner = child.inner_exclusive_access();
         child_inner.tasks.push(Some(Arc::clone(&task)));
@@ -245,14 +368,67 @@ impl ProcessControlBlock {
         let task_inner = task.inner_exclusive_access();
         let trap_cx = task_inner.get_trap_cx();
         trap_cx.kernel_sp = task.kstack.get_top();
+        // the trap_cx is a copy of the parent's, fork returns 0 in the child
+        // and this must be in place before the child can be scheduled
+        trap_cx.x[10] = 0;
         drop(task_inner);
         insert_into_pid2process(child.getpid(), Arc::clone(&child));
         // add this thread to scheduler
         add_task(task);
-        child
+        Some(child)
     }
 
     pub fn getpid(&self) -> usize {
         self.pid.0
     }
 }
+
+/// Push `args` on the user stack of `task`, the main thread of an image just
+/// loaded into the address space `token`, and let it start at `entry_point`
+/// with argc and argv in a0 and a1.
+fn init_main_thread(
+    task: &Arc<TaskControlBlock>,
+    token: usize,
+    entry_point: usize,
+    args: Vec<String>,
+) {
+    let mut task_inner = task.inner_exclusive_access();
+    // push arguments on user stack
+    let mut user_sp = task_inner.res.as_mut().unwrap().ustack_top();
+    user_sp -= (args.len() + 1) * core::mem::size_of::<usize>();
+    let argv_base = user_sp;
+    // the stack has just been mapped for the new image, so it is writable
+    let mut argv: Vec<_> = (0..=args.len())
+        .map(|arg| {
+            translated_refmut(
+                token,
+                (argv_base + arg * core::mem::size_of::<usize>()) as *mut usize,
+            )
+            .unwrap()
+        })
+        .collect();
+    *argv[args.len()] = 0;
+    for i in 0..args.len() {
+        user_sp -= args[i].len() + 1;
+        *argv[i] = user_sp;
+        let mut p = user_sp;
+        for c in args[i].as_bytes() {
+            *translated_refmut(token, p as *mut u8).unwrap() = *c;
+            p += 1;
+        }
+        *translated_refmut(token, p as *mut u8).unwrap() = 0;
+    }
+    // make the user_sp aligned to 8B for k210 platform
+    user_sp -= user_sp % core::mem::size_of::<usize>();
+    // initialize trap_cx
+    let mut trap_cx = TrapContext::app_init_context(
+        entry_point,
+        user_sp,
+        KERNEL_SPACE.exclusive_access().token(),
+        task.kstack.get_top(),
+        trap_handler as usize,
+    );
+    trap_cx.x[10] = args.len();
+    trap_cx.x[11] = argv_base;
+    *task_inner.get_trap_cx() = trap_cx;
+}
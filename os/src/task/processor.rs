@@ -2,6 +2,7 @@ use super::__switch;
 use super::{fetch_task, TaskStatus};
 use super::{ProcessControlBlock, TaskContext, TaskControlBlock};
 use crate::sync::UPIntrFreeCell;
+use crate::timer::get_time_us;
 use crate::trap::TrapContext;
 use alloc::sync::Arc;
 use lazy_static::*;
@@ -9,6 +10,8 @@ use lazy_static::*;
 pub struct Processor {
     current: Option<Arc<TaskControlBlock>>,
     idle_task_cx: TaskContext,
+    /// The time slice ran out while the current task was in the kernel.
+    need_resched: bool,
 }
 
 impl Processor {
@@ -16,6 +19,7 @@ impl Processor {
         Self {
             current: None,
             idle_task_cx: TaskContext::zero_init(),
+            need_resched: false,
         }
     }
     fn get_idle_task_cx_ptr(&mut self) -> *mut TaskContext {
@@ -42,14 +46,20 @@ pub fn run_tasks() {
             // access coming task TCB exclusively
             let next_task_cx_ptr = task.inner.exclusive_session(|task_inner| {
                 task_inner.task_status = TaskStatus::Running;
+                // the time the task was switched out is not charged to it
+                task_inner.runtime_start = get_time_us();
+                task_inner.ticks_left = task_inner.time_slice;
                 &task_inner.task_cx as *const TaskContext
             });
-            processor.current = Some(task);
+            processor.current = Some(Arc::clone(&task));
+            processor.need_resched = false;
             // release processor manually
             drop(processor);
             unsafe {
                 __switch(idle_task_cx_ptr, next_task_cx_ptr);
             }
+            // the task gave up the cpu in kernel mode
+            task.inner_exclusive_access().account_kernel_time();
         } else {
             println!("no tasks available in run_tasks");
         }
@@ -64,6 +74,29 @@ pub fn current_task() -> Option<Arc<TaskControlBlock>> {
     PROCESSOR.exclusive_access().current()
 }
 
+/// Charge a timer tick to the current task, return whether its time slice
+/// is used up. Without a current task there is nothing to keep running.
+pub fn tick_current_task() -> bool {
+    match current_task() {
+        Some(task) => {
+            let mut task_inner = task.inner_exclusive_access();
+            task_inner.ticks_left = task_inner.ticks_left.saturating_sub(1);
+            task_inner.ticks_left == 0
+        }
+        None => true,
+    }
+}
+
+/// Ask the current task to give up the cpu at its next `preempt_point`.
+pub fn set_need_resched() {
+    PROCESSOR.exclusive_access().need_resched = true;
+}
+
+/// Clear the request of `set_need_resched`, return whether there was one.
+pub fn take_need_resched() -> bool {
+    core::mem::take(&mut PROCESSOR.exclusive_access().need_resched)
+}
+
 pub fn current_process() -> Arc<ProcessControlBlock> {
     current_task().unwrap().process.upgrade().unwrap()
 }
@@ -0,0 +1,86 @@
+use super::manager::PID2PCB;
+use super::{current_process, current_task, ProcessControlBlock};
+use crate::mm::VirtPageNum;
+use alloc::{sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// pid of the process swept last, the next sweep starts after it
+static CLOCK_HAND: AtomicUsize = AtomicUsize::new(0);
+
+/// Reclaim hook of the frame allocator, free up to `wanted` zero pages.
+/// The current process and processes with a thread inside a syscall are
+/// skipped, since the kernel may hold slices of their pages.
+pub fn reclaim_frames(wanted: usize) -> usize {
+    let current = current_task().and_then(|task| task.process.upgrade());
+    let processes: Vec<Arc<ProcessControlBlock>> = match PID2PCB.try_exclusive_access() {
+        Some(map) => {
+            let hand = CLOCK_HAND.load(Ordering::Relaxed);
+            map.range(hand + 1..)
+                .chain(map.range(..=hand))
+                .map(|(_, process)| process.clone())
+                .collect()
+        }
+        None => return 0,
+    };
+    let mut freed = 0;
+    // the first lap may only clear the accessed bits of recently used pages
+    for _ in 0..2 {
+        for process in processes.iter() {
+            if freed == wanted {
+                return freed;
+            }
+            if current
+                .as_ref()
+                .map_or(false, |current| Arc::ptr_eq(current, process))
+            {
+                continue;
+            }
+            let mut inner = match process.inner_try_exclusive_access() {
+                Some(inner) => inner,
+                None => continue,
+            };
+            let in_syscall = inner.tasks.iter().flatten().any(|task| {
+                task.inner
+                    .try_exclusive_access()
+                    .map_or(true, |task_inner| task_inner.in_syscall)
+            });
+            if in_syscall {
+                continue;
+            }
+            freed += inner.memory_set.reclaim_zero_pages(wanted - freed);
+            CLOCK_HAND.store(process.getpid(), Ordering::Relaxed);
+        }
+    }
+    freed
+}
+
+/// Map back a reclaimed page of the current process, or move it off the
+/// zero frame, see `MemorySet::fault_in`. Return false if there was nothing
+/// to do for `vpn`, or if the process is borrowed already, e.g. by the
+/// caller, rather than panicking; its page then stays as it is.
+pub fn fault_in_current(vpn: VirtPageNum) -> bool {
+    fault_in(&current_process(), vpn)
+}
+
+/// Like `fault_in_current`, for the process whose address space is `token`,
+/// which need not be the current one.
+pub fn fault_in_user(token: usize, vpn: VirtPageNum) -> bool {
+    let processes: Vec<Arc<ProcessControlBlock>> = match PID2PCB.try_exclusive_access() {
+        Some(map) => map.values().cloned().collect(),
+        None => return false,
+    };
+    processes
+        .iter()
+        .find(|process| {
+            process
+                .inner_try_exclusive_access()
+                .map_or(false, |inner| inner.memory_set.token() == token)
+        })
+        .map_or(false, |process| fault_in(process, vpn))
+}
+
+fn fault_in(process: &ProcessControlBlock, vpn: VirtPageNum) -> bool {
+    process
+        .inner_try_exclusive_access()
+        .map_or(false, |mut inner| inner.memory_set.fault_in(vpn).is_some())
+}
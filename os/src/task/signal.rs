@@ -1,12 +1,20 @@
 use bitflags::*;
 
+pub const MAX_SIG: usize = 31;
+pub const SIGCONT: usize = 18;
+pub const SIGSTOP: usize = 19;
+
 bitflags! {
     pub struct SignalFlags: u32 {
         const SIGINT    = 1 << 2;
         const SIGILL    = 1 << 4;
         const SIGABRT   = 1 << 6;
+        const SIGBUS    = 1 << 7;
         const SIGFPE    = 1 << 8;
         const SIGSEGV   = 1 << 11;
+        const SIGALRM   = 1 << 14;
+        const SIGCONT   = 1 << 18;
+        const SIGSTOP   = 1 << 19;
     }
 }
 
@@ -18,10 +26,14 @@ impl SignalFlags {
             Some((-4, "Illegal Instruction, SIGILL=4"))
         } else if self.contains(Self::SIGABRT) {
             Some((-6, "Aborted, SIGABRT=6"))
+        } else if self.contains(Self::SIGBUS) {
+            Some((-7, "Bus Error, SIGBUS=7"))
         } else if self.contains(Self::SIGFPE) {
             Some((-8, "Erroneous Arithmetic Operation, SIGFPE=8"))
         } else if self.contains(Self::SIGSEGV) {
             Some((-11, "Segmentation Fault, SIGSEGV=11"))
+        } else if self.contains(Self::SIGALRM) {
+            Some((-14, "Alarm Clock, SIGALRM=14"))
         } else {
             None
         }
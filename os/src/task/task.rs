@@ -1,8 +1,10 @@
 use super::id::TaskUserRes;
 use super::{kstack_alloc, KernelStack, ProcessControlBlock, TaskContext};
+use crate::timer::get_time_us;
 use crate::trap::TrapContext;
 use crate::{
-    mm::PhysPageNum,
+    config::DEFAULT_TIME_SLICE,
+    mm::{PhysPageNum, VirtPageNum},
     sync::{UPIntrFreeCell, UPIntrRefMut},
 };
 use alloc::sync::{Arc, Weak};
@@ -33,6 +35,25 @@ pub struct TaskControlBlockInner {
     pub task_cx: TaskContext,
     pub task_status: TaskStatus,
     pub exit_code: Option<i32>,
+    /// time spent in user mode and in kernel mode, in microseconds
+    pub runtime_in_user: usize,
+    pub runtime_in_kernel: usize,
+    /// start of the user or kernel run not yet accounted
+    pub runtime_start: usize,
+    /// set while running a syscall, whose user buffers must stay mapped
+    pub in_syscall: bool,
+    /// page faults served without disk io: a reclaimed page mapped back
+    /// or an anonymous page moved off the zero frame
+    pub minor_faults: usize,
+    /// page faults that had to read the page from disk, there are no
+    /// file-backed mappings yet so this stays 0
+    pub major_faults: usize,
+    /// timer ticks a run lasts before the task is preempted
+    pub time_slice: usize,
+    /// ticks left in the current run, refilled whenever the task is scheduled
+    pub ticks_left: usize,
+    /// guard page below the user stack, a fault there is a stack overflow
+    pub stack_guard_vpn: VirtPageNum,
 }
 
 impl TaskControlBlockInner {
@@ -44,6 +65,20 @@ impl TaskControlBlockInner {
     fn get_status(&self) -> TaskStatus {
         self.task_status
     }
+
+    /// Charge the time since `runtime_start` to user mode.
+    pub fn account_user_time(&mut self) {
+        let now = get_time_us();
+        self.runtime_in_user += now - self.runtime_start;
+        self.runtime_start = now;
+    }
+
+    /// Charge the time since `runtime_start` to kernel mode.
+    pub fn account_kernel_time(&mut self) {
+        let now = get_time_us();
+        self.runtime_in_kernel += now - self.runtime_start;
+        self.runtime_start = now;
+    }
 }
 
 impl TaskControlBlock {
@@ -51,12 +86,13 @@ impl TaskControlBlock {
         process: Arc<ProcessControlBlock>,
         ustack_base: usize,
         alloc_user_res: bool,
-    ) -> Self {
-        let res = TaskUserRes::new(Arc::clone(&process), ustack_base, alloc_user_res);
+    ) -> Option<Self> {
+        let res = TaskUserRes::new(Arc::clone(&process), ustack_base, alloc_user_res)?;
         let trap_cx_ppn = res.trap_cx_ppn();
-        let kstack = kstack_alloc();
+        let stack_guard_vpn = res.stack_guard_vpn();
+        let kstack = kstack_alloc()?;
         let kstack_top = kstack.get_top();
-        Self {
+        Some(Self {
             process: Arc::downgrade(&process),
             kstack,
             inner: unsafe {
@@ -66,15 +102,40 @@ impl TaskControlBlock {
                     task_cx: TaskContext::goto_trap_return(kstack_top),
                     task_status: TaskStatus::Ready,
                     exit_code: None,
+                    runtime_in_user: 0,
+                    runtime_in_kernel: 0,
+                    runtime_start: 0,
+                    in_syscall: false,
+                    minor_faults: 0,
+                    major_faults: 0,
+                    time_slice: DEFAULT_TIME_SLICE,
+                    ticks_left: DEFAULT_TIME_SLICE,
+                    stack_guard_vpn,
                 })
             },
-        }
+        })
     }
 }
 
+/// What `sys_listtasks` reports of a thread.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct TaskInfo {
+    pub pid: usize,
+    pub tid: usize,
+    /// `TaskStatus as usize`
+    pub status: usize,
+    pub user_time_us: usize,
+    pub kernel_time_us: usize,
+    pub minor_faults: usize,
+    pub major_faults: usize,
+}
+
 #[derive(Copy, Clone, PartialEq)]
 pub enum TaskStatus {
     Ready,
     Running,
     Blocking,
+    /// its process got SIGSTOP and waits for SIGCONT
+    Stopped,
 }
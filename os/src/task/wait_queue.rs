@@ -0,0 +1,45 @@
+use super::{block_current_task, current_task, schedule, wakeup_task, TaskControlBlock};
+use crate::sync::UPIntrFreeCell;
+use alloc::{collections::VecDeque, sync::Arc};
+
+/// Tasks blocked until some event happens.
+pub struct WaitQueue {
+    tasks: UPIntrFreeCell<VecDeque<Arc<TaskControlBlock>>>,
+}
+
+impl WaitQueue {
+    pub fn new() -> Self {
+        Self {
+            tasks: unsafe { UPIntrFreeCell::new(VecDeque::new()) },
+        }
+    }
+
+    /// Park the current task on this queue and switch to the next task.
+    /// Interrupts stay disabled until the task is blocked, so a wakeup
+    /// from an interrupt handler can not be lost.
+    pub fn block_current(&self) {
+        let task_cx_ptr = self.tasks.exclusive_session(|tasks| {
+            tasks.push_back(current_task().unwrap());
+            block_current_task()
+        });
+        schedule(task_cx_ptr);
+    }
+
+    /// Wake the task blocked the longest, return false if there is none.
+    #[allow(unused)]
+    pub fn wake_one(&self) -> bool {
+        let task = self.tasks.exclusive_access().pop_front();
+        match task {
+            Some(task) => {
+                wakeup_task(task);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn wake_all(&self) {
+        let tasks = core::mem::take(&mut *self.tasks.exclusive_access());
+        tasks.into_iter().for_each(wakeup_task);
+    }
+}
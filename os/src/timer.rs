@@ -3,7 +3,7 @@ use core::cmp::Ordering;
 use crate::config::CLOCK_FREQ;
 use crate::sbi::set_timer;
 use crate::sync::UPIntrFreeCell;
-use crate::task::{add_task, TaskControlBlock};
+use crate::task::{block_current_task, current_task, schedule, wakeup_task, TaskControlBlock};
 use alloc::collections::BinaryHeap;
 use alloc::sync::Arc;
 use lazy_static::*;
@@ -11,13 +11,28 @@ use riscv::register::time;
 
 const TICKS_PER_SEC: usize = 100;
 const MSEC_PER_SEC: usize = 1000;
+const USEC_PER_SEC: usize = 1_000_000;
+const NSEC_PER_SEC: usize = 1_000_000_000;
 
 pub fn get_time() -> usize {
     time::read()
 }
 
+/// Convert timer ticks to `unit_per_sec` units without overflowing the product.
+fn ticks_to(ticks: usize, unit_per_sec: usize) -> usize {
+    ticks / CLOCK_FREQ * unit_per_sec + ticks % CLOCK_FREQ * unit_per_sec / CLOCK_FREQ
+}
+
 pub fn get_time_ms() -> usize {
-    time::read() / (CLOCK_FREQ / MSEC_PER_SEC)
+    ticks_to(time::read(), MSEC_PER_SEC)
+}
+
+pub fn get_time_us() -> usize {
+    ticks_to(time::read(), USEC_PER_SEC)
+}
+
+pub fn get_time_ns() -> usize {
+    ticks_to(time::read(), NSEC_PER_SEC)
 }
 
 pub fn set_next_trigger() {
@@ -54,9 +69,14 @@ lazy_static! {
         unsafe { UPIntrFreeCell::new(BinaryHeap::<TimerCondVar>::new()) };
 }
 
-pub fn add_timer(expire_ms: usize, task: Arc<TaskControlBlock>) {
-    let mut timers = TIMERS.exclusive_access();
-    timers.push(TimerCondVar { expire_ms, task });
+/// Block the current task until `expire_ms`, see `WaitQueue::block_current`.
+pub fn block_current_until(expire_ms: usize) {
+    let task_cx_ptr = TIMERS.exclusive_session(|timers| {
+        let task = current_task().unwrap();
+        timers.push(TimerCondVar { expire_ms, task });
+        block_current_task()
+    });
+    schedule(task_cx_ptr);
 }
 
 pub fn check_timer() {
@@ -64,7 +84,7 @@ pub fn check_timer() {
     TIMERS.exclusive_session(|timers| {
         while let Some(timer) = timers.peek() {
             if timer.expire_ms <= current_ms {
-                add_task(Arc::clone(&timer.task));
+                wakeup_task(Arc::clone(&timer.task));
                 timers.pop();
             } else {
                 break;
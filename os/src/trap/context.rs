@@ -1,7 +1,7 @@
-use riscv::register::sstatus::{self, Sstatus, SPP};
+use riscv::register::sstatus::{self, Sstatus, FS, SPP};
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct TrapContext {
     pub x: [usize; 32],
     pub sstatus: Sstatus,
@@ -9,6 +9,9 @@ pub struct TrapContext {
     pub kernel_satp: usize,
     pub kernel_sp: usize,
     pub trap_handler: usize,
+    /// f0~f31, saved on a trap only if the app changed them, see `trap.S`
+    pub f: [usize; 32],
+    pub fcsr: usize,
 }
 
 impl TrapContext {
@@ -22,6 +25,10 @@ impl TrapContext {
         kernel_sp: usize,
         trap_handler: usize,
     ) -> Self {
+        // fp registers start zeroed, they are loaded on the first return
+        unsafe {
+            sstatus::set_fs(FS::Initial);
+        }
         let mut sstatus = sstatus::read();
         // set CPU privilege to User after trapping back
         sstatus.set_spp(SPP::User);
@@ -32,6 +39,8 @@ impl TrapContext {
             kernel_satp,
             kernel_sp,
             trap_handler,
+            f: [0; 32],
+            fcsr: 0,
         };
         cx.set_sp(sp);
         cx
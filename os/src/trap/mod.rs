@@ -1,10 +1,13 @@
 mod context;
 
 use crate::config::TRAMPOLINE;
+use crate::mm::{PageTable, VirtAddr};
 use crate::syscall::syscall;
 use crate::task::{
-    check_signals_of_current, current_add_signal, current_trap_cx, current_trap_cx_user_va,
-    current_user_token, exit_current_and_run_next, suspend_current_and_run_next, SignalFlags,
+    check_itimers, check_signals_of_current, current_add_signal, current_process, current_task,
+    current_trap_cx, current_trap_cx_user_va, current_user_token, exit_current_and_run_next,
+    fault_in_current, handle_signals, set_need_resched, suspend_current_and_run_next,
+    tick_current_task, wait_while_stopped, SignalFlags,
 };
 use crate::timer::{check_timer, set_next_trigger};
 use core::arch::{asm, global_asm};
@@ -56,9 +59,48 @@ fn disable_supervisor_interrupt() {
     }
 }
 
+/// `scause` of a misaligned load, the riscv crate has no variant for it.
+const LOAD_MISALIGNED: usize = 4;
+
+/// Log an exception the user program can not recover from by itself and
+/// send `signal` to its process, whose default action kills it.
+fn user_fault(cause: &str, stval: usize, signal: SignalFlags) {
+    error!(
+        "{:?}: {} in pid {}, stval = {:#x}, sepc = {:#x}",
+        signal,
+        cause,
+        current_process().getpid(),
+        stval,
+        current_trap_cx().sepc,
+    );
+    current_add_signal(signal);
+}
+
+/// Whether `va` is in a user page that is mapped but not writable.
+fn is_read_only_page(va: usize) -> bool {
+    PageTable::from_token(current_user_token())
+        .translate(VirtAddr::from(va).floor())
+        .map_or(false, |pte| {
+            pte.is_valid() && pte.user_accessible() && !pte.writable()
+        })
+}
+
+/// Whether `va` is in the guard page below the user stack of the current thread.
+fn is_stack_guard_page(va: usize) -> bool {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .stack_guard_vpn
+        == VirtAddr::from(va).floor()
+}
+
 #[no_mangle]
 pub fn trap_handler() -> ! {
     set_kernel_trap_entry();
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .account_user_time();
     let scause = scause::read();
     let stval = stval::read();
     // println!("into {:?}", scause.cause());
@@ -67,38 +109,79 @@ pub fn trap_handler() -> ! {
             // jump to next instruction anyway
             let mut cx = current_trap_cx();
             cx.sepc += 4;
+            current_task().unwrap().inner_exclusive_access().in_syscall = true;
 
             enable_supervisor_interrupt();
 
             // get system call return value
-            let result = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12]]);
+            let result = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12], cx.x[13]]);
             // cx is changed during sys_exec, so we have to call it again
             cx = current_trap_cx();
             cx.x[10] = result as usize;
+            current_task().unwrap().inner_exclusive_access().in_syscall = false;
         }
-        Trap::Exception(Exception::StoreFault)
-        | Trap::Exception(Exception::StorePageFault)
-        | Trap::Exception(Exception::InstructionFault)
+        // a reclaimed page is mapped back, a write to the zero frame gets a
+        // private frame, and the instruction is retried
+        Trap::Exception(Exception::StorePageFault)
         | Trap::Exception(Exception::InstructionPageFault)
-        | Trap::Exception(Exception::LoadFault)
-        | Trap::Exception(Exception::LoadPageFault) => {
-            /*
-            println!(
-                "[kernel] {:?} in application, bad addr = {:#x}, bad instruction = {:#x}, kernel killed it.",
-                scause.cause(),
+        | Trap::Exception(Exception::LoadPageFault)
+            if fault_in_current(VirtAddr::from(stval).floor()) =>
+        {
+            current_task()
+                .unwrap()
+                .inner_exclusive_access()
+                .minor_faults += 1;
+        }
+        Trap::Exception(Exception::StorePageFault) | Trap::Exception(Exception::LoadPageFault)
+            if is_stack_guard_page(stval) =>
+        {
+            user_fault("stack overflow", stval, SignalFlags::SIGSEGV);
+        }
+        Trap::Exception(Exception::StorePageFault) if is_read_only_page(stval) => {
+            user_fault(
+                "store page fault, the page is read-only",
                 stval,
-                current_trap_cx().sepc,
+                SignalFlags::SIGSEGV,
             );
-            */
-            current_add_signal(SignalFlags::SIGSEGV);
         }
+        Trap::Exception(Exception::StorePageFault) => {
+            user_fault("store page fault", stval, SignalFlags::SIGSEGV);
+        }
+        Trap::Exception(Exception::LoadPageFault) => {
+            user_fault("load page fault", stval, SignalFlags::SIGSEGV);
+        }
+        Trap::Exception(Exception::InstructionPageFault) => {
+            user_fault("instruction page fault", stval, SignalFlags::SIGSEGV);
+        }
+        Trap::Exception(Exception::StoreFault) => {
+            user_fault("store access fault", stval, SignalFlags::SIGSEGV);
+        }
+        Trap::Exception(Exception::LoadFault) => {
+            user_fault("load access fault", stval, SignalFlags::SIGSEGV);
+        }
+        Trap::Exception(Exception::InstructionFault) => {
+            user_fault("instruction access fault", stval, SignalFlags::SIGSEGV);
+        }
+        // stval holds the instruction itself
         Trap::Exception(Exception::IllegalInstruction) => {
-            current_add_signal(SignalFlags::SIGILL);
+            user_fault("illegal instruction", stval, SignalFlags::SIGILL);
+        }
+        Trap::Exception(Exception::StoreMisaligned) => {
+            user_fault("misaligned store or amo", stval, SignalFlags::SIGBUS);
+        }
+        Trap::Exception(Exception::InstructionMisaligned) => {
+            user_fault("misaligned instruction", stval, SignalFlags::SIGBUS);
+        }
+        Trap::Exception(Exception::Unknown) if scause.code() == LOAD_MISALIGNED => {
+            user_fault("misaligned load", stval, SignalFlags::SIGBUS);
         }
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
             set_next_trigger();
             check_timer();
-            suspend_current_and_run_next();
+            check_itimers();
+            if tick_current_task() {
+                suspend_current_and_run_next();
+            }
         }
         Trap::Interrupt(Interrupt::SupervisorExternal) => {
             crate::board::irq_handler();
@@ -111,6 +194,10 @@ pub fn trap_handler() -> ! {
             );
         }
     }
+    // a stopped process waits for SIGCONT here
+    wait_while_stopped();
+    // run user handlers of caught signals
+    handle_signals();
     // check signals
     if let Some((errno, msg)) = check_signals_of_current() {
         println!("[kernel] {}", msg);
@@ -122,6 +209,10 @@ pub fn trap_handler() -> ! {
 #[no_mangle]
 pub fn trap_return() -> ! {
     disable_supervisor_interrupt();
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .account_kernel_time();
     set_user_trap_entry();
     let trap_cx_user_va = current_trap_cx_user_va();
     let user_satp = current_user_token();
@@ -154,7 +245,11 @@ pub fn trap_from_kernel(_trap_cx: &TrapContext) {
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
             set_next_trigger();
             check_timer();
-            // do not schedule now
+            check_itimers();
+            // do not schedule now, a long syscall yields at its next preempt_point
+            if tick_current_task() {
+                set_need_resched();
+            }
         }
         _ => {
             panic!(
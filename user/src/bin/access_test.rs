@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{access, chmod, close, open, unlink, Errno, OpenFlags, F_OK, R_OK, W_OK, X_OK};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let name = "access_file\0";
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+
+    assert_eq!(access(name, F_OK), 0);
+    assert_eq!(access("access_missing\0", F_OK), Errno::ENOENT.ret());
+    assert_eq!(access("/tmp/access_missing\0", F_OK), Errno::ENOENT.ret());
+    // directories and mount points exist too
+    for dir in ["/\0", "/mnt/\0", "/tmp/\0", "/proc/\0"] {
+        assert_eq!(access(dir, F_OK), 0);
+    }
+    assert_eq!(access("/proc/meminfo\0", R_OK), 0);
+
+    // 0o644 allows reading and writing, but not executing
+    assert_eq!(access(name, R_OK | W_OK), 0);
+    assert_eq!(access(name, X_OK), Errno::EACCES.ret());
+    assert_eq!(chmod(name, 0o444), 0);
+    assert_eq!(access(name, R_OK), 0);
+    assert_eq!(access(name, W_OK), Errno::EACCES.ret());
+    assert_eq!(access(name, 8), Errno::EINVAL.ret());
+
+    // no fd was left behind
+    let fd2 = open(name, OpenFlags::RDONLY);
+    assert_eq!(fd2, fd);
+    close(fd2 as usize);
+    assert_eq!(unlink(name), 0);
+    println!("access_test passed!");
+    0
+}
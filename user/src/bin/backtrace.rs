@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{backtrace, fork, getpid, kill, waitpid, yield_, Errno, SignalFlags};
+
+const DEPTH: usize = 5;
+
+/// Never set, it only keeps the compiler from seeing that `nest` diverges.
+static mut STOP: bool = false;
+
+#[inline(never)]
+fn nest(depth: usize) -> usize {
+    if depth == 0 {
+        while !unsafe { core::ptr::read_volatile(&STOP) } {
+            yield_();
+        }
+        return 0;
+    }
+    // read back through memory so the recursion cannot be turned into a loop
+    let inner = nest(depth - 1);
+    unsafe { core::ptr::read_volatile(&inner) + depth }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        return nest(DEPTH) as i32;
+    }
+    // let the child reach the innermost frame
+    for _ in 0..10 {
+        yield_();
+    }
+    let mut addrs = [0usize; 32];
+    let n = backtrace(pid as usize, &mut addrs);
+    println!("backtrace of process {}:", pid);
+    for addr in &addrs[..n as usize] {
+        println!("    {:#x}", addr);
+    }
+    // pc plus at least one return address per nested frame
+    assert!(n as usize > DEPTH);
+    // a short buffer is never overrun
+    let mut short = [0usize; 2];
+    assert_eq!(backtrace(pid as usize, &mut short), 2);
+    // our own stack is fine too
+    assert!(backtrace(getpid() as usize, &mut addrs) > 0);
+    assert_eq!(backtrace(100000, &mut addrs), Errno::ESRCH.ret());
+    // initproc is no child of ours
+    assert_eq!(backtrace(0, &mut addrs), Errno::EPERM.ret());
+    kill(pid as usize, SignalFlags::SIGINT.bits());
+    let mut exit_code: i32 = 0;
+    waitpid(pid as usize, &mut exit_code);
+    println!("backtrace passed!");
+    0
+}
@@ -0,0 +1,94 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, mmap, munmap, open, pipe, read, unlink, waitpid, write, Errno, OpenFlags,
+    PROT_READ, PROT_WRITE,
+};
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x2a00_0000;
+
+fn buffer(start: usize, len: usize) -> &'static mut [u8] {
+    unsafe { core::slice::from_raw_parts_mut(start as *mut u8, len) }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(
+        mmap(START, PAGE_SIZE, PROT_READ | PROT_WRITE),
+        START as isize
+    );
+    buffer(START, PAGE_SIZE).fill(b'x');
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    // the last byte of the mapped page is fine, a buffer running off its end
+    // is cut short there, one starting past it is refused
+    assert_eq!(write(pipe_fd[1], buffer(START + PAGE_SIZE - 1, 1)), 1);
+    assert_eq!(write(pipe_fd[1], buffer(START + PAGE_SIZE - 1, 2)), 1);
+    assert_eq!(
+        write(pipe_fd[1], buffer(START + PAGE_SIZE, 1)),
+        Errno::EFAULT.ret()
+    );
+    assert_eq!(
+        read(pipe_fd[0], buffer(START + PAGE_SIZE, 1)),
+        Errno::EFAULT.ret()
+    );
+    // nothing was consumed by the failed read
+    assert_eq!(read(pipe_fd[0], buffer(START + PAGE_SIZE - 1, 2)), 1);
+    assert_eq!(read(pipe_fd[0], buffer(START, 1)), 1);
+    close(pipe_fd[0]);
+    close(pipe_fd[1]);
+    // a file can not be read into a read-only page
+    assert_eq!(
+        mmap(START + PAGE_SIZE, PAGE_SIZE, PROT_READ),
+        (START + PAGE_SIZE) as isize
+    );
+    let fd = open("bad_buffer_file\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    // a huge length stops at the end of the mapped pages
+    assert_eq!(write(fd, buffer(START, 1 << 40)), 2 * PAGE_SIZE as isize);
+    close(fd);
+    let fd = open("bad_buffer_file\0", OpenFlags::RDONLY) as usize;
+    assert_eq!(read(fd, buffer(START + PAGE_SIZE, 1)), Errno::EFAULT.ret());
+    assert_eq!(read(fd, buffer(START, 1 << 40)), PAGE_SIZE as isize);
+    // but it can be written from one
+    close(fd);
+    let fd = open("bad_buffer_file\0", OpenFlags::WRONLY) as usize;
+    assert_eq!(write(fd, buffer(START + PAGE_SIZE, 1)), 1);
+    close(fd);
+    assert_eq!(unlink("bad_buffer_file\0"), 0);
+    // kernel addresses and buffers wrapping around are refused as well
+    assert_eq!(write(1, buffer(0x8020_0000, 1)), Errno::EFAULT.ret());
+    assert_eq!(write(1, buffer(usize::MAX - 4, 16)), Errno::EFAULT.ret());
+    assert_eq!(munmap(START, 2 * PAGE_SIZE), 0);
+    // so are paths and out-pointers, a path must not run off its page either
+    let bad_path = unsafe { core::str::from_utf8_unchecked(buffer(START, 1)) };
+    assert_eq!(open(bad_path, OpenFlags::RDONLY), Errno::EFAULT.ret());
+    assert_eq!(
+        mmap(START, PAGE_SIZE, PROT_READ | PROT_WRITE),
+        START as isize
+    );
+    buffer(START, PAGE_SIZE).fill(b'x');
+    let long_path = unsafe { core::str::from_utf8_unchecked(buffer(START + PAGE_SIZE - 1, 1)) };
+    assert_eq!(open(long_path, OpenFlags::RDONLY), Errno::EFAULT.ret());
+    assert_eq!(munmap(START, PAGE_SIZE), 0);
+    let bad_fds = unsafe { core::slice::from_raw_parts_mut(START as *mut usize, 2) };
+    assert_eq!(pipe(bad_fds), Errno::EFAULT.ret());
+    // a failed wait leaves the child to be reaped later
+    let pid = fork();
+    if pid == 0 {
+        exit(7);
+    }
+    let bad_code = unsafe { &mut *(START as *mut i32) };
+    assert_eq!(waitpid(pid as usize, bad_code), Errno::EFAULT.ret());
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+    println!("bad_buffer passed!");
+    0
+}
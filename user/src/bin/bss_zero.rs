@@ -0,0 +1,25 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+/// Not page aligned, so part of it may share a page with .data.
+static mut BSS_ARRAY: [u8; 5000] = [0; 5000];
+const LARGE_BSS_SIZE: usize = 64 * 1024;
+/// Spans whole pages that hold nothing but .bss.
+static mut LARGE_BSS_ARRAY: [u8; LARGE_BSS_SIZE] = [0; LARGE_BSS_SIZE];
+static DATA_WORD: usize = 0x5a5a_5a5a;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(unsafe { core::ptr::read_volatile(&DATA_WORD) }, 0x5a5a_5a5a);
+    for i in 0..5000 {
+        assert_eq!(unsafe { core::ptr::read_volatile(&BSS_ARRAY[i]) }, 0);
+    }
+    for i in 0..LARGE_BSS_SIZE {
+        assert_eq!(unsafe { core::ptr::read_volatile(&LARGE_BSS_ARRAY[i]) }, 0);
+    }
+    println!("bss_zero passed!");
+    0
+}
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{chmod, close, fstat, open, Errno, OpenFlags, Stat, StatMode};
+
+const PERM_MASK: u32 = 0o777;
+
+fn mode_of(path: &str) -> StatMode {
+    let fd = open(path, OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let mut stat = Stat::new();
+    assert_eq!(fstat(fd as usize, &mut stat), 0);
+    close(fd as usize);
+    stat.mode
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let name = "chmod_file\0";
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    let mode = mode_of(name);
+    assert!(mode.contains(StatMode::FILE));
+    assert_eq!(mode.bits() & PERM_MASK, 0o644);
+
+    // read-only files can not be opened for writing or truncated
+    assert_eq!(chmod(name, 0o444), 0);
+    assert_eq!(mode_of(name).bits() & PERM_MASK, 0o444);
+    assert_eq!(open(name, OpenFlags::WRONLY), Errno::EACCES.ret());
+    assert_eq!(open(name, OpenFlags::RDWR), Errno::EACCES.ret());
+    assert_eq!(open(name, OpenFlags::TRUNC), Errno::EACCES.ret());
+
+    assert_eq!(chmod(name, 0o644), 0);
+    let fd = open(name, OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    assert_eq!(chmod("chmod_missing\0", 0o644), Errno::ENOENT.ret());
+    println!("chmod_test passed!");
+    0
+}
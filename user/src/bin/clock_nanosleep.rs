@@ -0,0 +1,74 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{clock_nanosleep, get_time_ns, Errno, TimeSpec, CLOCK_MONOTONIC, TIMER_ABSTIME};
+
+const NS_PER_MS: usize = 1_000_000;
+const PERIOD_NS: usize = 40 * NS_PER_MS;
+/// Work done in every round, relative sleeps would add it up.
+const WORK_NS: usize = 10 * NS_PER_MS;
+
+fn time_spec(ns: usize) -> TimeSpec {
+    TimeSpec {
+        sec: ns / 1_000_000_000,
+        nsec: ns % 1_000_000_000,
+    }
+}
+
+fn spin(ns: usize) {
+    let end = get_time_ns() + ns;
+    while get_time_ns() < end {}
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = get_time_ns();
+    let mut target = start;
+    for _ in 0..2 {
+        spin(WORK_NS);
+        target += PERIOD_NS;
+        assert_eq!(
+            clock_nanosleep(CLOCK_MONOTONIC, TIMER_ABSTIME, &time_spec(target)),
+            0
+        );
+        assert!(get_time_ns() >= target);
+    }
+    let elapsed = get_time_ns() - start;
+    println!("two periods of 40ms took {}us", elapsed / 1000);
+    // the work is hidden in the periods, it would have been 100ms otherwise
+    assert!(elapsed < 2 * PERIOD_NS + 15 * NS_PER_MS);
+
+    // a deadline in the past returns at once
+    let now = get_time_ns();
+    assert_eq!(
+        clock_nanosleep(CLOCK_MONOTONIC, TIMER_ABSTIME, &time_spec(start)),
+        0
+    );
+    assert!(get_time_ns() - now < 5 * NS_PER_MS);
+    // without TIMER_ABSTIME it is a duration
+    let now = get_time_ns();
+    assert_eq!(
+        clock_nanosleep(CLOCK_MONOTONIC, 0, &time_spec(20 * NS_PER_MS)),
+        0
+    );
+    assert!(get_time_ns() - now >= 20 * NS_PER_MS);
+    let bad = TimeSpec {
+        sec: 0,
+        nsec: 1_000_000_000,
+    };
+    assert_eq!(
+        clock_nanosleep(CLOCK_MONOTONIC, 0, &bad),
+        Errno::EINVAL.ret()
+    );
+    // a kernel address is not readable by the user
+    let unreadable = unsafe { &*(0x8020_0000 as *const TimeSpec) };
+    assert_eq!(
+        clock_nanosleep(CLOCK_MONOTONIC, 0, unreadable),
+        Errno::EFAULT.ret()
+    );
+    println!("clock_nanosleep passed!");
+    0
+}
@@ -0,0 +1,81 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use user_lib::{
+    close, exec, fcntl, fork, open, waitpid, write, Errno, OpenFlags, FD_CLOEXEC, F_GETFD, F_SETFD,
+};
+
+/// Runs in the exec'ed image: `argv[1]` and `argv[3]` are closed on exec,
+/// `argv[2]` is inherited.
+fn check_after_exec(argv: &[&str]) -> i32 {
+    let closed_fd: usize = argv[1].parse().unwrap();
+    let kept_fd: usize = argv[2].parse().unwrap();
+    let opened_cloexec_fd: usize = argv[3].parse().unwrap();
+    assert_eq!(write(closed_fd, b"closed"), Errno::EBADF.ret());
+    assert_eq!(write(opened_cloexec_fd, b"closed"), Errno::EBADF.ret());
+    assert_eq!(write(kept_fd, b"kept"), 4);
+    assert_eq!(fcntl(kept_fd, F_GETFD, 0), 0);
+    close(kept_fd);
+    0
+}
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    if argc == 4 {
+        return check_after_exec(argv);
+    }
+    println!("into cloexec test!");
+    let closed_fd = open("cloexec_a\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    let kept_fd = open("cloexec_b\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(closed_fd > 0 && kept_fd > 0);
+    let (closed_fd, kept_fd) = (closed_fd as usize, kept_fd as usize);
+    assert_eq!(fcntl(closed_fd, F_SETFD, FD_CLOEXEC), 0);
+    assert_eq!(fcntl(closed_fd, F_GETFD, 0), FD_CLOEXEC as isize);
+    // the same flag set at open time
+    let opened_cloexec_fd = open(
+        "cloexec_c\0",
+        OpenFlags::CREATE | OpenFlags::WRONLY | OpenFlags::CLOEXEC,
+    );
+    assert!(opened_cloexec_fd > 0);
+    let opened_cloexec_fd = opened_cloexec_fd as usize;
+    assert_eq!(fcntl(opened_cloexec_fd, F_GETFD, 0), FD_CLOEXEC as isize);
+    // CLOEXEC alone does not make a read-only open writable
+    let read_only_fd = open("cloexec_c\0", OpenFlags::RDONLY | OpenFlags::CLOEXEC);
+    assert!(read_only_fd > 0);
+    assert_eq!(write(read_only_fd as usize, b"x"), Errno::EBADF.ret());
+    close(read_only_fd as usize);
+    let pid = fork();
+    if pid == 0 {
+        // the flag is inherited by fork
+        assert_eq!(fcntl(closed_fd, F_GETFD, 0), FD_CLOEXEC as isize);
+        let closed_arg = format!("{}\0", closed_fd);
+        let kept_arg = format!("{}\0", kept_fd);
+        let opened_cloexec_arg = format!("{}\0", opened_cloexec_fd);
+        exec(
+            "cloexec\0",
+            &[
+                "cloexec\0".as_ptr(),
+                closed_arg.as_ptr(),
+                kept_arg.as_ptr(),
+                opened_cloexec_arg.as_ptr(),
+                core::ptr::null::<u8>(),
+            ],
+        );
+        panic!("exec cloexec failed");
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    // exec in the child leaves our own fds alone
+    assert_eq!(write(closed_fd, b"parent"), 6);
+    close(closed_fd);
+    close(kept_fd);
+    close(opened_cloexec_fd);
+    println!("cloexec passed!");
+    0
+}
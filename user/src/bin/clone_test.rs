@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{clone, exit, gettid, waittid, Errno};
+
+const THREAD_COUNT: usize = 2;
+const PER_THREAD: usize = 10000;
+const STACK_SIZE: usize = 4096 * 2;
+
+#[repr(C, align(16))]
+struct Stack([u8; STACK_SIZE]);
+
+static mut STACKS: [Stack; THREAD_COUNT] = [Stack([0; STACK_SIZE]), Stack([0; STACK_SIZE])];
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn increment(times: usize) -> ! {
+    for _ in 0..times {
+        COUNTER.fetch_add(1, Ordering::Relaxed);
+    }
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut tids = [0; THREAD_COUNT];
+    for (i, tid) in tids.iter_mut().enumerate() {
+        let stack_top = unsafe { STACKS[i].0.as_ptr() as usize + STACK_SIZE };
+        *tid = clone(increment as usize, stack_top, PER_THREAD);
+        assert!(*tid > 0);
+    }
+    for tid in tids {
+        assert_eq!(waittid(tid as usize), 0);
+        // reaped already
+        assert_eq!(waittid(tid as usize), Errno::ESRCH.ret());
+    }
+    assert_eq!(waittid(gettid() as usize), Errno::EDEADLK.ret());
+    assert_eq!(waittid(1 << 20), Errno::ESRCH.ret());
+    assert_eq!(COUNTER.load(Ordering::Relaxed), THREAD_COUNT * PER_THREAD);
+    println!("clone_test passed!");
+    0
+}
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, dup, dup2, open, read, Errno, OpenFlags};
+
+const STDOUT: usize = 1;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let name = "dup2_out\0";
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let saved_stdout = dup(STDOUT);
+    assert!(saved_stdout > 0);
+    let saved_stdout = saved_stdout as usize;
+    // redirect stdout into the file
+    assert_eq!(dup2(fd, STDOUT), STDOUT as isize);
+    print!("captured by dup2");
+    // restore stdout
+    assert_eq!(dup2(saved_stdout, STDOUT), STDOUT as isize);
+    close(saved_stdout);
+    close(fd);
+    // invalid old fd and a target beyond the current table
+    assert_eq!(dup2(100, STDOUT), Errno::EBADF.ret());
+    assert_eq!(dup2(STDOUT, 40), 40);
+    close(40);
+    // but not one the kernel would have to grow the table to
+    assert_eq!(dup2(STDOUT, 1 << 40), Errno::EBADF.ret());
+
+    let fd = open(name, OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let mut buffer = [0u8; 64];
+    let len = read(fd, &mut buffer) as usize;
+    close(fd);
+    assert_eq!(
+        core::str::from_utf8(&buffer[..len]).unwrap(),
+        "captured by dup2"
+    );
+    println!("dup2_redirect passed!");
+    0
+}
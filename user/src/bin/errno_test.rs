@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::arch::asm;
+use user_lib::{close, getpid, kill, open, Errno, OpenFlags, SignalFlags};
+
+/// A syscall id the kernel does not implement.
+const SYSCALL_BOGUS: usize = 9999;
+
+fn raw_syscall(id: usize) -> isize {
+    let mut ret: isize;
+    unsafe {
+        asm!(
+            "ecall",
+            inlateout("x10") 0usize => ret,
+            in("x17") id
+        );
+    }
+    ret
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // an unknown syscall must fail with ENOSYS rather than kill the caller
+    assert_eq!(Errno::check(raw_syscall(SYSCALL_BOGUS)), Err(Errno::ENOSYS));
+    assert_eq!(Errno::check(raw_syscall(usize::MAX)), Err(Errno::ENOSYS));
+    // and the caller goes on as if nothing happened
+    assert!(getpid() > 0);
+    assert_eq!(
+        Errno::check(open("errno_no_such_file\0", OpenFlags::RDONLY)),
+        Err(Errno::ENOENT)
+    );
+    assert_eq!(Errno::check(close(100)), Err(Errno::EBADF));
+    assert_eq!(
+        Errno::check(kill(100000, SignalFlags::SIGINT.bits())),
+        Err(Errno::ESRCH)
+    );
+    println!("errno_test passed!");
+    0
+}
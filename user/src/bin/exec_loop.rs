@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use user_lib::{exec, sysinfo, SysInfo};
+
+/// Each round holds 8MiB, so leaking the old image on exec runs out of frames
+/// long before the last round. Smaller leaks show up in the free frames left
+/// after the last round.
+const BIG_SIZE: usize = 8 * 1024 * 1024;
+const ROUNDS: usize = 32;
+const PAGE_SIZE: usize = 4096;
+/// Frames that may differ between two images of this program, e.g. page
+/// tables for a stack at another random offset.
+const SLACK_FRAMES: usize = 16;
+
+static mut BIG: [u8; BIG_SIZE] = [0; BIG_SIZE];
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    let round: usize = if argc > 1 {
+        argv[1].parse().unwrap()
+    } else {
+        0
+    };
+    if round == 0 {
+        println!("into exec_loop test!");
+    }
+    // a fresh image must never see the data of the previous round
+    for i in (0..BIG_SIZE).step_by(PAGE_SIZE) {
+        unsafe {
+            assert_eq!(BIG[i], 0);
+            BIG[i] = round as u8 + 1;
+        }
+    }
+    let mut info = SysInfo::default();
+    assert_eq!(sysinfo(&mut info), 0);
+    // free frames once the first image is fully mapped
+    let first_free: usize = if argc > 2 {
+        argv[2].parse().unwrap()
+    } else {
+        info.free_frames
+    };
+    if round == ROUNDS {
+        assert!(
+            info.free_frames + SLACK_FRAMES >= first_free,
+            "exec leaked {} frames",
+            first_free - info.free_frames
+        );
+        println!("exec_loop passed!");
+        return 0;
+    }
+    let next = format!("{}\0", round + 1);
+    let first_free = format!("{}\0", first_free);
+    exec(
+        "exec_loop\0",
+        &[
+            "exec_loop\0".as_ptr(),
+            next.as_ptr(),
+            first_free.as_ptr(),
+            core::ptr::null::<u8>(),
+        ],
+    );
+    panic!("exec exec_loop failed");
+}
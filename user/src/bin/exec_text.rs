@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{chmod, close, exec, open, read, write, Errno, OpenFlags};
+
+fn write_file(name: &str, data: &[u8]) {
+    let fd = open(
+        name,
+        OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY,
+    );
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, data), data.len() as isize);
+    close(fd as usize);
+    assert_eq!(chmod(name, 0o755), 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // neither a text file nor a bare elf magic can be loaded,
+    // and the caller keeps running after the failed exec
+    write_file("exec_text_file\0", b"this is not a program\n");
+    assert_eq!(
+        exec("exec_text_file\0", &[core::ptr::null::<u8>()]),
+        Errno::ENOEXEC.ret()
+    );
+    write_file("exec_magic_only\0", b"\x7fELF");
+    assert_eq!(
+        exec("exec_magic_only\0", &[core::ptr::null::<u8>()]),
+        Errno::ENOEXEC.ret()
+    );
+    // a program cut short has segments running past the end of the file
+    let fd = open("exit\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let mut head = [0u8; 2048];
+    assert_eq!(read(fd as usize, &mut head), head.len() as isize);
+    close(fd as usize);
+    write_file("exec_truncated\0", &head);
+    assert_eq!(
+        exec("exec_truncated\0", &[core::ptr::null::<u8>()]),
+        Errno::ENOEXEC.ret()
+    );
+    // a real program still needs the exec bit
+    assert_eq!(chmod("exit\0", 0o644), 0);
+    assert_eq!(
+        exec("exit\0", &[core::ptr::null::<u8>()]),
+        Errno::EACCES.ret()
+    );
+    assert_eq!(chmod("exit\0", 0o755), 0);
+    assert_eq!(
+        exec("exec_missing\0", &[core::ptr::null::<u8>()]),
+        Errno::ENOENT.ret()
+    );
+    println!("exec_text passed!");
+    0
+}
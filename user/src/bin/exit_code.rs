@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+use user_lib::{exit, fork, sleep, wait, waitpid, waitpid_nostatus};
+
+const CHILD_RETURN: i32 = 42;
+
+/// The child returns from `main`, so the code goes through `_start`'s `exit(main())`.
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("into exit_code test!");
+    let pid = fork();
+    if pid == 0 {
+        return CHILD_RETURN;
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, CHILD_RETURN);
+    // negative codes survive the usize round trip through the syscall args
+    let pid = fork();
+    if pid == 0 {
+        return -CHILD_RETURN;
+    }
+    exit_code = 0;
+    assert_eq!(wait(&mut exit_code), pid);
+    assert_eq!(exit_code, -CHILD_RETURN);
+    // the child is still running when the parent starts waiting
+    let pid = fork();
+    if pid == 0 {
+        sleep(50);
+        exit(CHILD_RETURN + 1);
+    }
+    exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, CHILD_RETURN + 1);
+    // a null exit code pointer is skipped rather than faulting
+    let pid = fork();
+    if pid == 0 {
+        exit(CHILD_RETURN);
+    }
+    assert_eq!(waitpid_nostatus(pid as usize), pid);
+    println!("exit_code passed!");
+    0
+}
@@ -0,0 +1,10 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+/// Fails on purpose, `exit` in the shell afterwards shuts down with code 1.
+#[no_mangle]
+pub fn main() -> i32 {
+    1
+}
@@ -0,0 +1,66 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time_us, times, waitpid, yield_, Tms};
+
+/// Spin in user mode without making any syscall.
+fn busy_loop() {
+    let mut x: usize = 1;
+    for i in 0..20_000_000usize {
+        // volatile, so that the loop is not optimized away
+        unsafe {
+            core::ptr::write_volatile(&mut x, x.wrapping_mul(31).wrapping_add(i));
+        }
+    }
+}
+
+fn total(tms: &Tms) -> usize {
+    tms.utime + tms.stime + tms.cutime + tms.cstime
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = Tms::default();
+    assert_eq!(times(&mut before), 0);
+    let start = get_time_us();
+    // switches away inside nearly every syscall it makes
+    let yielder = fork();
+    if yielder == 0 {
+        for _ in 0..5000 {
+            yield_();
+        }
+        exit(0);
+    }
+    // makes no syscall while it runs, so it should hardly be charged any
+    // kernel time, whatever the yielder does in between
+    let spinner = fork();
+    if spinner == 0 {
+        let mut tms = Tms::default();
+        assert_eq!(times(&mut tms), 0);
+        let (utime, stime) = (tms.utime, tms.stime);
+        busy_loop();
+        assert_eq!(times(&mut tms), 0);
+        let (utime, stime) = (tms.utime - utime, tms.stime - stime);
+        println!("spinner: utime {}us, stime {}us", utime, stime);
+        assert!(utime > 0);
+        assert!(stime * 4 < utime);
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(yielder as usize, &mut exit_code), yielder);
+    assert_eq!(exit_code, 0);
+    assert_eq!(waitpid(spinner as usize, &mut exit_code), spinner);
+    assert_eq!(exit_code, 0);
+    let elapsed = get_time_us() - start;
+    let mut after = Tms::default();
+    assert_eq!(times(&mut after), 0);
+    // on one hart, time billed to anyone cannot exceed the wall clock time
+    let charged = total(&after) - total(&before);
+    println!("charged {}us in {}us", charged, elapsed);
+    assert!(charged <= elapsed);
+    println!("fair_accounting passed!");
+    0
+}
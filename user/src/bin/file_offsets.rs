@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, dup, exit, fork, open, read, unlink, waitpid, write, OpenFlags};
+
+fn read_str(fd: usize, buffer: &mut [u8]) -> &str {
+    let len = read(fd, buffer);
+    assert!(len >= 0);
+    core::str::from_utf8(&buffer[..len as usize]).unwrap()
+}
+
+fn check(name: &str) {
+    let fd = open(
+        name,
+        OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY,
+    );
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"0123456789"), 10);
+    close(fd as usize);
+    let mut buffer = [0u8; 3];
+    let fd = open(name, OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    // a dup'd fd shares the offset
+    let dup_fd = dup(fd);
+    assert!(dup_fd > 0);
+    let dup_fd = dup_fd as usize;
+    assert_eq!(read_str(fd, &mut buffer), "012");
+    assert_eq!(read_str(dup_fd, &mut buffer), "345");
+    // a second open has an offset of its own
+    let other_fd = open(name, OpenFlags::RDONLY);
+    assert!(other_fd > 0);
+    let other_fd = other_fd as usize;
+    assert_eq!(read_str(other_fd, &mut buffer), "012");
+    assert_eq!(read_str(fd, &mut buffer), "678");
+    // a forked child shares the offsets of the fds it inherits
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(read_str(other_fd, &mut buffer[..2]), "34");
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(read_str(other_fd, &mut buffer), "567");
+    assert_eq!(read_str(dup_fd, &mut buffer), "9");
+    close(fd);
+    close(dup_fd);
+    close(other_fd);
+    assert_eq!(unlink(name), 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    check("file_offsets\0");
+    check("/tmp/file_offsets\0");
+    println!("file_offsets passed!");
+    0
+}
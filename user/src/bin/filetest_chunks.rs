@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, open, read, write, OpenFlags};
+
+const FILE_SIZE: usize = 2000;
+const CHUNK_SIZE: usize = 256;
+
+fn pattern(i: usize) -> u8 {
+    (i * 7 % 251) as u8
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let name = "filechunks\0";
+    let mut data = [0u8; FILE_SIZE];
+    for (i, byte) in data.iter_mut().enumerate() {
+        *byte = pattern(i);
+    }
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    // two writes must append, not overwrite each other
+    assert_eq!(write(fd, &data[..1000]), 1000);
+    assert_eq!(write(fd, &data[1000..]), 1000);
+    close(fd);
+
+    let fd = open(name, OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let mut readback = [0u8; FILE_SIZE];
+    let mut total = 0usize;
+    loop {
+        let mut chunk = [0u8; CHUNK_SIZE];
+        let len = read(fd, &mut chunk) as usize;
+        if len == 0 {
+            break;
+        }
+        assert!(total + len <= FILE_SIZE);
+        readback[total..total + len].copy_from_slice(&chunk[..len]);
+        total += len;
+    }
+    close(fd);
+
+    assert_eq!(total, FILE_SIZE);
+    assert!(readback == data);
+    println!("filetest_chunks passed!");
+    0
+}
@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exec, exit, fork, fork_exec, sysinfo, waitpid, Errno, SysInfo};
+
+const CHILDREN: usize = 50;
+const ARGS: [*const u8; 2] = ["exit_one\0".as_ptr(), core::ptr::null()];
+
+fn frames_allocated() -> usize {
+    let mut info = SysInfo::default();
+    assert_eq!(sysinfo(&mut info), 0);
+    info.frames_allocated
+}
+
+fn wait_exit_one(pid: isize) {
+    assert!(pid > 0);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 1);
+}
+
+/// Frames allocated to run exit_one `CHILDREN` times with `spawn`.
+fn churn(spawn: fn() -> isize) -> usize {
+    let before = frames_allocated();
+    for _ in 0..CHILDREN {
+        wait_exit_one(spawn());
+    }
+    frames_allocated() - before
+}
+
+fn fork_then_exec() -> isize {
+    let pid = fork();
+    if pid == 0 {
+        exec("exit_one\0", &ARGS);
+        exit(-1);
+    }
+    pid
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(fork_exec("fork_exec_missing\0", &ARGS), Errno::ENOENT.ret());
+    let slow = churn(fork_then_exec);
+    let fast = churn(|| fork_exec("exit_one\0", &ARGS));
+    println!(
+        "frames allocated for {} children: fork+exec {}, fork_exec {}",
+        CHILDREN, slow, fast
+    );
+    assert!(fast < slow);
+    println!("fork_exec_test passed!");
+    0
+}
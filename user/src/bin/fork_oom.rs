@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+use user_lib::{close, exit, fork, pipe, read, wait, waitpid};
+
+/// Far more children than physical memory can hold.
+const MAX_CHILDREN: usize = 100000;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("into fork_oom test!");
+    // children block on the read end until the parent closes the write end
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let mut children = 0;
+    while children < MAX_CHILDREN {
+        let pid = fork();
+        if pid == 0 {
+            close(pipe_fd[1]);
+            let mut buf = [0u8; 1];
+            read(pipe_fd[0], &mut buf);
+            exit(0);
+        }
+        if pid < 0 {
+            break;
+        }
+        children += 1;
+    }
+    assert!(children < MAX_CHILDREN, "fork never ran out of memory");
+    println!("fork failed after {} children", children);
+    // release all children
+    close(pipe_fd[1]);
+    close(pipe_fd[0]);
+    let mut exit_code: i32 = 0;
+    for _ in 0..children {
+        assert!(wait(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+    // frames are back, so fork works again
+    let pid = fork();
+    if pid == 0 {
+        exit(7);
+    }
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+    println!("fork_oom passed!");
+    0
+}
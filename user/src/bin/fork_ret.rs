@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, getppid, wait, waitpid, Errno};
+
+const ROUNDS: usize = 8;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let parent_pid = getpid();
+    for _ in 0..ROUNDS {
+        let ret = fork();
+        if ret == 0 {
+            let pid = getpid();
+            println!("child: pid = {}, fork returned {}", pid, ret);
+            assert_ne!(pid, parent_pid);
+            assert_eq!(getppid(), parent_pid);
+            // report the pid back so the parent can match it against fork
+            exit(pid as i32);
+        }
+        println!("parent: pid = {}, fork returned {}", getpid(), ret);
+        assert!(ret > 0);
+        assert_ne!(ret, parent_pid);
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(ret as usize, &mut exit_code), ret);
+        assert_eq!(exit_code as isize, ret);
+        // exactly one child was made, and only the parent got here
+        assert_eq!(wait(&mut exit_code), Errno::ECHILD.ret());
+    }
+    println!("fork_ret passed!");
+    0
+}
@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::arch::asm;
+use user_lib::{exit, fork, getpid, wait, yield_};
+
+const CHILDREN: usize = 3;
+const ROUNDS: usize = 200;
+const SYSCALL_YIELD: usize = 124;
+
+/// Put `pattern` into two fp registers and a rounding mode into fcsr,
+/// yield to the others and read them back.
+fn fp_round_trip(pattern: usize, frm: usize) -> (usize, usize, usize) {
+    let (f0, f1, got_frm): (usize, usize, usize);
+    unsafe {
+        asm!(
+            "fmv.d.x ft0, {p}",
+            "not {p}, {p}",
+            "fmv.d.x ft1, {p}",
+            "fsrm {frm}",
+            "ecall",
+            "fmv.x.d {f0}, ft0",
+            "fmv.x.d {f1}, ft1",
+            "frrm {frm}",
+            p = inout(reg) pattern => _,
+            frm = inout(reg) frm => got_frm,
+            f0 = out(reg) f0,
+            f1 = out(reg) f1,
+            inlateout("x10") 0usize => _,
+            in("x17") SYSCALL_YIELD,
+            out("ft0") _,
+            out("ft1") _,
+        );
+    }
+    (f0, f1, got_frm)
+}
+
+fn check(seed: usize) {
+    for i in 0..ROUNDS {
+        let pattern = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ i;
+        let frm = (seed + i) % 5;
+        let (f0, f1, got_frm) = fp_round_trip(pattern, frm);
+        assert_eq!(f0, pattern);
+        assert_eq!(f1, !pattern);
+        assert_eq!(got_frm, frm);
+    }
+    // plain float code across yields too
+    let mut x = seed as f64 + 0.5;
+    for _ in 0..ROUNDS {
+        x = x * 1.5 - x / 3.0;
+        yield_();
+    }
+    let mut y = seed as f64 + 0.5;
+    for _ in 0..ROUNDS {
+        y = y * 1.5 - y / 3.0;
+    }
+    assert_eq!(x.to_bits(), y.to_bits());
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for _ in 0..CHILDREN {
+        if fork() == 0 {
+            check(getpid() as usize);
+            exit(0);
+        }
+    }
+    check(getpid() as usize);
+    let mut exit_code = 0;
+    for _ in 0..CHILDREN {
+        assert!(wait(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+    println!("fp_yield passed!");
+    0
+}
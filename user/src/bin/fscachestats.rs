@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, fscachestats, open, read, CacheStats, OpenFlags};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = CacheStats::default();
+    assert_eq!(fscachestats(&mut before), 0);
+    // the executable itself was just loaded from disk
+    let fd = open("fscachestats\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let mut buffer = [0u8; 16];
+    assert_eq!(read(fd as usize, &mut buffer), 16);
+    close(fd as usize);
+    let mut after = CacheStats::default();
+    assert_eq!(fscachestats(&mut after), 0);
+    assert!(after.hits + after.misses > before.hits + before.misses);
+    println!(
+        "block cache: {} hits, {} misses, {} evictions",
+        after.hits, after.misses, after.evictions
+    );
+    println!("fscachestats passed!");
+    0
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, fsync, open, read, unlink, write, Errno, OpenFlags};
+
+fn check(name: &str) {
+    let data = [b'f'; 1000];
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    assert_eq!(write(fd, &data), data.len() as isize);
+    assert_eq!(fsync(fd), 0);
+    close(fd);
+    let fd = open(name, OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let mut buffer = [0u8; 1024];
+    assert_eq!(read(fd, &mut buffer), data.len() as isize);
+    assert!(buffer[..data.len()].iter().all(|&b| b == b'f'));
+    // a file open only for reading can be synced too
+    assert_eq!(fsync(fd), 0);
+    close(fd);
+    assert_eq!(unlink(name), 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    check("fsync_file\0");
+    check("/tmp/fsync_file\0");
+    // there is nothing to write back behind stdout
+    assert_eq!(fsync(1), Errno::EINVAL.ret());
+    assert_eq!(fsync(99), Errno::EBADF.ret());
+    println!("fsync_test passed!");
+    0
+}
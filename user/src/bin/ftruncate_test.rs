@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, ftruncate, open, read, unlink, write, Errno, OpenFlags};
+
+fn check(name: &str) {
+    let data = [b'x'; 4096];
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    assert_eq!(write(fd, &data), 4096);
+    assert_eq!(ftruncate(fd, 100), 0);
+    // far past what any fs here can hold
+    assert_eq!(ftruncate(fd, 1 << 40), Errno::EFBIG.ret());
+    // the offset stays at 4096, past the new end
+    let mut buffer = [0u8; 4096];
+    assert_eq!(read(fd, &mut buffer), 0);
+    close(fd);
+    let fd = open(name, OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    assert_eq!(read(fd, &mut buffer), 100);
+    assert!(buffer[..100].iter().all(|&b| b == b'x'));
+    assert_eq!(read(fd, &mut buffer), 0);
+    // only files open for writing can be truncated
+    assert_eq!(ftruncate(fd, 0), Errno::EINVAL.ret());
+    close(fd);
+    assert_eq!(unlink(name), 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    check("ftruncate_file\0");
+    check("/tmp/ftruncate_file\0");
+    assert_eq!(ftruncate(1, 0), Errno::EINVAL.ret());
+    assert_eq!(ftruncate(99, 0), Errno::EBADF.ret());
+    println!("ftruncate_test passed!");
+    0
+}
@@ -0,0 +1,19 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+/// Print `argv[1]` numbered lines, without a newline after the last one.
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    assert_eq!(argc, 2);
+    let lines: usize = argv[1].parse().unwrap();
+    for i in 0..lines {
+        if i > 0 {
+            println!();
+        }
+        print!("line {}", i);
+    }
+    0
+}
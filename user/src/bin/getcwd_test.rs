@@ -0,0 +1,30 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getcwd, wait, Errno};
+
+fn check_cwd() {
+    let mut buffer = [0xffu8; 16];
+    assert_eq!(getcwd(&mut buffer), 2);
+    assert_eq!(&buffer[..2], b"/\0");
+    // no room for the '\0'
+    assert_eq!(getcwd(&mut buffer[..1]), Errno::ERANGE.ret());
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    check_cwd();
+    // children start in the directory of the parent
+    if fork() == 0 {
+        check_cwd();
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert!(wait(&mut exit_code) > 0);
+    assert_eq!(exit_code, 0);
+    println!("getcwd_test passed!");
+    0
+}
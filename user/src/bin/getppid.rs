@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, getpid, getppid, pipe, read, waitpid, write, yield_};
+
+/// initproc is the first process created by the kernel.
+const INITPROC_PID: isize = 0;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let me = getpid();
+    let child = fork();
+    if child == 0 {
+        assert_eq!(getppid(), me);
+        let child_pid = getpid();
+        if fork() == 0 {
+            // grandchild: wait until the child has exited and we are adopted
+            close(pipe_fd[0]);
+            assert_eq!(getppid(), child_pid);
+            let mut tries = 0;
+            while getppid() == child_pid && tries < 10000 {
+                yield_();
+                tries += 1;
+            }
+            let adopted = getppid() == INITPROC_PID;
+            write(pipe_fd[1], &[adopted as u8]);
+            close(pipe_fd[1]);
+            exit(0);
+        }
+        // child exits first, leaving the grandchild as an orphan
+        exit(0);
+    }
+    close(pipe_fd[1]);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(child as usize, &mut exit_code), child);
+    let mut result = [0u8; 1];
+    assert_eq!(read(pipe_fd[0], &mut result), 1);
+    close(pipe_fd[0]);
+    assert_eq!(result[0], 1, "orphan was not adopted by initproc");
+    println!("getppid passed!");
+    0
+}
@@ -0,0 +1,22 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::getrandom;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut a = [0u8; 64];
+    let mut b = [0u8; 64];
+    assert_eq!(getrandom(&mut a), 64);
+    assert_eq!(getrandom(&mut b), 64);
+    assert_ne!(a, b);
+    assert!(a.iter().any(|byte| *byte != 0));
+    // lengths that are not a multiple of 8 work as well
+    let mut c = [0u8; 13];
+    assert_eq!(getrandom(&mut c), 13);
+    println!("getrandom passed!");
+    0
+}
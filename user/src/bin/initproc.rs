@@ -7,16 +7,22 @@ use user_lib::{exec, fork, wait, yield_};
 
 #[no_mangle]
 fn main() -> i32 {
-    if fork() == 0 {
+    let shell_pid = fork();
+    if shell_pid == 0 {
         exec("user_shell\0", &[core::ptr::null::<u8>()]);
     } else {
         loop {
             let mut exit_code: i32 = 0;
             let pid = wait(&mut exit_code);
-            if pid == -1 {
+            if pid < 0 {
                 yield_();
                 continue;
             }
+            // the kernel shuts down with our exit code, so a failed
+            // session is visible to whoever started the machine
+            if pid == shell_pid {
+                return exit_code;
+            }
             /*
             println!(
                 "[initproc] Released a zombie process, pid={}, exit_code={}",
@@ -0,0 +1,75 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, ioctl, open, pipe, unlink, Errno, OpenFlags, Termios, Winsize, ECHO, ICANON, TCGETS,
+    TCSETS, TIOCGWINSZ,
+};
+
+fn get_termios(fd: usize) -> Termios {
+    let mut termios = Termios::default();
+    assert_eq!(ioctl(fd, TCGETS, &mut termios as *mut _ as usize), 0);
+    termios
+}
+
+fn set_termios(fd: usize, termios: &Termios) {
+    assert_eq!(ioctl(fd, TCSETS, termios as *const _ as usize), 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the console starts raw, the shell reads it a byte at a time
+    let saved = get_termios(0);
+    assert_eq!(saved.lflag & ICANON, 0);
+
+    // switch to cooked mode, the change is seen through stdout as well
+    let mut cooked = saved;
+    cooked.lflag |= ICANON | ECHO;
+    set_termios(0, &cooked);
+    assert_eq!(get_termios(1).lflag & (ICANON | ECHO), ICANON | ECHO);
+
+    // and back to raw mode, where a one byte read does not wait for a newline
+    let mut raw = cooked;
+    raw.lflag &= !(ICANON | ECHO);
+    set_termios(1, &raw);
+    assert_eq!(get_termios(0).lflag & (ICANON | ECHO), 0);
+
+    let mut winsize = Winsize::default();
+    assert_eq!(ioctl(1, TIOCGWINSZ, &mut winsize as *mut _ as usize), 0);
+    assert!(winsize.row > 0 && winsize.col > 0);
+    println!("console is {}x{}", winsize.col, winsize.row);
+
+    // only the console takes terminal requests
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    assert_eq!(
+        ioctl(pipe_fd[0], TIOCGWINSZ, &mut winsize as *mut _ as usize),
+        Errno::ENOTTY.ret()
+    );
+    close(pipe_fd[0]);
+    close(pipe_fd[1]);
+    let fd = open("ioctl_test\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    assert_eq!(
+        ioctl(fd, TCGETS, &mut raw as *mut _ as usize),
+        Errno::ENOTTY.ret()
+    );
+    close(fd);
+    assert_eq!(
+        ioctl(fd, TCGETS, &mut raw as *mut _ as usize),
+        Errno::EBADF.ret()
+    );
+    assert_eq!(unlink("ioctl_test\0"), 0);
+
+    assert_eq!(ioctl(0, 0x1234, 0), Errno::EINVAL.ret());
+    assert_eq!(ioctl(0, TCGETS, 0), Errno::EFAULT.ret());
+    assert_eq!(ioctl(0, TCSETS, 0), Errno::EFAULT.ret());
+
+    set_termios(0, &saved);
+    println!("ioctl_test passed!");
+    0
+}
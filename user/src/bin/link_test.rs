@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, link, open, read, stat, unlink, write, Errno, OpenFlags, Stat};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("link_src\0", OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"hello"), 5);
+    close(fd as usize);
+
+    assert_eq!(link("link_src\0", "link_dst\0"), 0);
+    let fd = open("link_dst\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let mut buffer = [0u8; 16];
+    assert_eq!(read(fd as usize, &mut buffer), 5);
+    assert_eq!(&buffer[..5], b"hello");
+    close(fd as usize);
+
+    // both names are the same inode, a write through one shows through the other
+    let fd = open("link_dst\0", OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"HELLO, links"), 12);
+    close(fd as usize);
+    let fd = open("link_src\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    assert_eq!(read(fd as usize, &mut buffer), 12);
+    assert_eq!(&buffer[..12], b"HELLO, links");
+    close(fd as usize);
+    let mut st = Stat::new();
+    assert_eq!(stat("link_src\0", &mut st), 0);
+    assert_eq!(st.nlink, 2);
+
+    assert_eq!(link("link_src\0", "link_dst\0"), Errno::EEXIST.ret());
+    assert_eq!(link("link_missing\0", "link_new\0"), Errno::ENOENT.ret());
+    assert_eq!(link("link_src\0", "/mnt/link_dst\0"), Errno::EXDEV.ret());
+    // the data stays until the last name is gone
+    assert_eq!(unlink("link_src\0"), 0);
+    let fd = open("link_dst\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    assert_eq!(read(fd as usize, &mut buffer), 12);
+    assert_eq!(&buffer[..12], b"HELLO, links");
+    close(fd as usize);
+    assert_eq!(stat("link_dst\0", &mut st), 0);
+    assert_eq!(st.nlink, 1);
+    assert_eq!(unlink("link_dst\0"), 0);
+    println!("link_test passed!");
+    0
+}
@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_time, getpid, list_tasks, sleep, waitpid, TaskInfo, TASK_BLOCKING,
+    TASK_RUNNING,
+};
+
+const MAX_TASKS: usize = 32;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // burn some user time so that it shows up
+    let start = get_time();
+    while get_time() - start < 20 {}
+    let mut children = [0isize; 2];
+    for child in children.iter_mut() {
+        *child = fork();
+        if *child == 0 {
+            sleep(500);
+            exit(0);
+        }
+    }
+    sleep(10);
+    let me = getpid() as usize;
+    let mut infos = [TaskInfo::default(); MAX_TASKS];
+    let total = list_tasks(&mut infos) as usize;
+    // initproc, the shell or usertests, this process and its children
+    assert!(total >= 4 && total <= MAX_TASKS);
+    let infos = &infos[..total];
+    let own = infos.iter().find(|info| info.pid == me).unwrap();
+    assert_eq!(own.status, TASK_RUNNING);
+    assert!(own.user_time_us > 0);
+    for child in children {
+        let sleeping = infos
+            .iter()
+            .find(|info| info.pid == child as usize)
+            .unwrap();
+        assert_eq!(sleeping.status, TASK_BLOCKING);
+    }
+
+    // a short buffer is filled up and the total is still returned
+    let mut short = [TaskInfo::default(); 2];
+    short[1].pid = usize::MAX;
+    assert_eq!(list_tasks(&mut short[..1]) as usize, total);
+    assert_eq!(short[1].pid, usize::MAX);
+    assert_eq!(list_tasks(&mut []) as usize, total);
+
+    let mut exit_code: i32 = 0;
+    for child in children {
+        assert_eq!(waitpid(child as usize, &mut exit_code), child);
+    }
+    println!("listtasks passed!");
+    0
+}
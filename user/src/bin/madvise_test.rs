@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    madvise, mmap, munmap, sysinfo, Errno, SysInfo, MADV_DONTNEED, PROT_READ, PROT_WRITE,
+};
+
+const START: usize = 0x2800_0000;
+const PAGE_SIZE: usize = 4096;
+const PAGES: usize = 8;
+
+fn free_frames() -> usize {
+    let mut info = SysInfo::default();
+    assert_eq!(sysinfo(&mut info), 0);
+    info.free_frames
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let len = PAGES * PAGE_SIZE;
+    assert_eq!(mmap(START, len, PROT_READ | PROT_WRITE), START as isize);
+    let memory = unsafe { core::slice::from_raw_parts_mut(START as *mut u8, len) };
+    let free = free_frames();
+    for page in memory.chunks_mut(PAGE_SIZE) {
+        page.fill(0x5a);
+    }
+    assert_eq!(free_frames(), free - PAGES);
+    // the first two pages go back to the allocator and read as zero
+    assert_eq!(madvise(START, 2 * PAGE_SIZE, MADV_DONTNEED), 0);
+    assert_eq!(free_frames(), free - PAGES + 2);
+    assert!(memory[..2 * PAGE_SIZE].iter().all(|b| *b == 0));
+    assert!(memory[2 * PAGE_SIZE..].iter().all(|b| *b == 0x5a));
+    assert_eq!(free_frames(), free - PAGES + 2);
+    // the mapping is still there, a write takes a zeroed frame again
+    memory[1] = 1;
+    assert_eq!(free_frames(), free - PAGES + 1);
+    assert_eq!(memory[0], 0);
+    assert_eq!(memory[1], 1);
+    // only mmap-ed memory, and only DONTNEED
+    assert_eq!(
+        madvise(START, len + PAGE_SIZE, MADV_DONTNEED),
+        Errno::EINVAL.ret()
+    );
+    assert_eq!(
+        madvise(START + 1, PAGE_SIZE, MADV_DONTNEED),
+        Errno::EINVAL.ret()
+    );
+    assert_eq!(madvise(START, len, 0), Errno::EINVAL.ret());
+    assert_eq!(madvise(START, len, MADV_DONTNEED), 0);
+    assert_eq!(free_frames(), free);
+    // a range may cross from one mapping into the next
+    let next = START + len;
+    assert_eq!(mmap(next, len, PROT_READ | PROT_WRITE), next as isize);
+    let free = free_frames();
+    let both = unsafe { core::slice::from_raw_parts_mut(START as *mut u8, 2 * len) };
+    both.fill(0xa5);
+    assert_eq!(free_frames(), free - 2 * PAGES);
+    assert_eq!(madvise(next - PAGE_SIZE, 2 * PAGE_SIZE, MADV_DONTNEED), 0);
+    assert_eq!(free_frames(), free - 2 * PAGES + 2);
+    assert!(both[len - PAGE_SIZE..len + PAGE_SIZE]
+        .iter()
+        .all(|b| *b == 0));
+    assert!(both[len + PAGE_SIZE..].iter().all(|b| *b == 0xa5));
+    assert_eq!(munmap(next, len), 0);
+    assert_eq!(munmap(START, len), 0);
+    println!("madvise_test passed!");
+    0
+}
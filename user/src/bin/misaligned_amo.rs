@@ -0,0 +1,19 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::arch::asm;
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("Into Test misaligned_amo, we will insert a misaligned amo...");
+    println!("Kernel should kill this application with SIGBUS!");
+    let words = [0u32; 2];
+    let addr = words.as_ptr() as usize + 1;
+    unsafe {
+        asm!("amoadd.w zero, zero, ({0})", in(reg) addr);
+    }
+    0
+}
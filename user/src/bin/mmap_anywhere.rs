@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap, Errno, PROT_READ, PROT_WRITE};
+
+const PAGE_SIZE: usize = 4096;
+const LEN: usize = 2 * PAGE_SIZE;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // start 0 lets the kernel pick a free place
+    let first = mmap(0, LEN, PROT_READ | PROT_WRITE);
+    assert!(first > 0);
+    let first = first as usize;
+    assert_eq!(first % PAGE_SIZE, 0);
+    let memory = unsafe { core::slice::from_raw_parts_mut(first as *mut u8, LEN) };
+    assert!(memory.iter().all(|b| *b == 0));
+    memory.fill(0x42);
+    // the place is taken now, the next one goes elsewhere
+    assert_eq!(mmap(first, PAGE_SIZE, PROT_READ), Errno::EEXIST.ret());
+    let second = mmap(0, LEN + 1, PROT_READ | PROT_WRITE);
+    assert!(second > 0);
+    let second = second as usize;
+    assert!(second + LEN + PAGE_SIZE <= first || first + LEN <= second);
+    let other = unsafe { core::slice::from_raw_parts_mut(second as *mut u8, LEN + 1) };
+    other[LEN] = 1;
+    assert!(memory.iter().all(|b| *b == 0x42));
+    assert_eq!(munmap(first, LEN), 0);
+    assert_eq!(munmap(second, LEN + 1), 0);
+    println!("mmap_anywhere passed!");
+    0
+}
@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap, Errno, PROT_READ, PROT_WRITE};
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x2c00_0000;
+const RW: usize = PROT_READ | PROT_WRITE;
+
+fn page_of(addr: usize) -> usize {
+    addr & !(PAGE_SIZE - 1)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // a length that is not page aligned maps the last partial page whole
+    assert_eq!(mmap(START, PAGE_SIZE + 1, RW), START as isize);
+    let memory = unsafe { core::slice::from_raw_parts_mut(START as *mut u8, 2 * PAGE_SIZE) };
+    memory[2 * PAGE_SIZE - 1] = 1;
+    assert_eq!(memory[PAGE_SIZE], 0);
+
+    // any overlap with an existing area is refused, the same range as well
+    assert_eq!(mmap(START, PAGE_SIZE + 1, RW), Errno::EEXIST.ret());
+    assert_eq!(mmap(START + PAGE_SIZE, PAGE_SIZE, RW), Errno::EEXIST.ret());
+    assert_eq!(
+        mmap(START - PAGE_SIZE, 2 * PAGE_SIZE, RW),
+        Errno::EEXIST.ret()
+    );
+    // so are the areas of the elf and the user stack
+    let text = page_of(main as usize);
+    assert_eq!(mmap(text, PAGE_SIZE, RW), Errno::EEXIST.ret());
+    let stack = page_of(&memory as *const _ as usize);
+    assert_eq!(mmap(stack, PAGE_SIZE, RW), Errno::EEXIST.ret());
+    // right behind the mapping is still free
+    assert_eq!(
+        mmap(START + 2 * PAGE_SIZE, PAGE_SIZE, RW),
+        (START + 2 * PAGE_SIZE) as isize
+    );
+
+    // ranges wrapping around or leaving user space
+    let top = page_of(usize::MAX);
+    assert_eq!(mmap(top, 2 * PAGE_SIZE, RW), Errno::EINVAL.ret());
+    assert_eq!(mmap(START, usize::MAX - START + 1, RW), Errno::EINVAL.ret());
+    assert_eq!(mmap(START, usize::MAX, RW), Errno::EINVAL.ret());
+    assert_eq!(mmap(0, usize::MAX, RW), Errno::EINVAL.ret());
+    // and a start that is not page aligned, or nothing to map
+    assert_eq!(mmap(START + 1, PAGE_SIZE, RW), Errno::EINVAL.ret());
+    assert_eq!(mmap(START + 4 * PAGE_SIZE, 0, RW), Errno::EINVAL.ret());
+
+    assert_eq!(munmap(START, 3 * PAGE_SIZE), 0);
+    println!("mmap_checks passed!");
+    0
+}
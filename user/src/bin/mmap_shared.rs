@@ -0,0 +1,77 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, madvise, mmap, mmap_shared, munmap, pipe, read, waitpid, write, Errno,
+    MADV_DONTNEED, PROT_READ, PROT_WRITE,
+};
+
+const PAGE_SIZE: usize = 4096;
+const SHARED: usize = 0x2d00_0000;
+const PRIVATE: usize = 0x2d10_0000;
+const LEN: usize = 2 * PAGE_SIZE;
+
+fn memory(start: usize) -> &'static mut [u8] {
+    unsafe { core::slice::from_raw_parts_mut(start as *mut u8, LEN) }
+}
+
+fn wait_ok(pid: isize) {
+    assert!(pid > 0);
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let rw = PROT_READ | PROT_WRITE;
+    assert_eq!(mmap_shared(SHARED, LEN, rw), SHARED as isize);
+    assert_eq!(mmap(PRIVATE, LEN, rw), PRIVATE as isize);
+    let (shared, private) = (memory(SHARED), memory(PRIVATE));
+    // the second page is left untouched until the child writes it
+    shared[0] = 1;
+    private[0] = 1;
+
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(shared[0], 1);
+        assert_eq!(private[0], 1);
+        shared[0] = 42;
+        shared[PAGE_SIZE] = 43;
+        private[0] = 42;
+        exit(0);
+    }
+    wait_ok(pid);
+    // writes of the child to the shared mapping outlive it
+    assert_eq!(shared[0], 42);
+    assert_eq!(shared[PAGE_SIZE], 43);
+    assert_eq!(private[0], 1);
+
+    // and the child sees what the parent writes after the fork
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let pid = fork();
+    if pid == 0 {
+        let mut byte = [0u8; 1];
+        assert_eq!(read(pipe_fd[0], &mut byte), 1);
+        assert_eq!(shared[1], 7);
+        assert_eq!(private[1], 0);
+        exit(0);
+    }
+    shared[1] = 7;
+    private[1] = 7;
+    assert_eq!(write(pipe_fd[1], &[1]), 1);
+    wait_ok(pid);
+    close(pipe_fd[0]);
+    close(pipe_fd[1]);
+
+    // the frames of a shared mapping can not be dropped by one side
+    assert_eq!(madvise(SHARED, LEN, MADV_DONTNEED), Errno::EINVAL.ret());
+    assert_eq!(munmap(SHARED, LEN), 0);
+    assert_eq!(munmap(PRIVATE, LEN), 0);
+    println!("mmap_shared passed!");
+    0
+}
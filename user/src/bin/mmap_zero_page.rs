@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fork, mmap, munmap, pipe, sysinfo, waitpid, write, Errno, SysInfo, PROT_READ, PROT_WRITE,
+};
+
+const START: usize = 0x1000_0000;
+const LEN: usize = 1 << 20;
+const PAGE_SIZE: usize = 4096;
+
+fn free_frames() -> usize {
+    let mut info = SysInfo::default();
+    assert_eq!(sysinfo(&mut info), 0);
+    info.free_frames
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, LEN, PROT_READ | PROT_WRITE), START as isize);
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_READ), Errno::EEXIST.ret());
+    let memory = unsafe { core::slice::from_raw_parts_mut(START as *mut u8, LEN) };
+    // reading the whole megabyte needs no frame
+    let free = free_frames();
+    assert!(memory.iter().all(|b| *b == 0));
+    assert_eq!(free_frames(), free);
+    // neither does the kernel reading it
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let free = free_frames();
+    assert_eq!(write(pipe_fd[1], &memory[..64]), 64);
+    assert_eq!(free_frames(), free);
+    close(pipe_fd[0]);
+    close(pipe_fd[1]);
+    // writing one page takes exactly one
+    memory[PAGE_SIZE + 1] = 42;
+    assert_eq!(free_frames(), free - 1);
+    assert_eq!(memory[PAGE_SIZE + 1], 42);
+    assert_eq!(memory[2 * PAGE_SIZE + 1], 0);
+    // the child sees the written page, and its own writes stay private
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(memory[PAGE_SIZE + 1], 42);
+        memory[3 * PAGE_SIZE] = 7;
+        return 0;
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(memory[3 * PAGE_SIZE], 0);
+    // the private page goes back, no matter what the child left behind
+    let before_munmap = free_frames();
+    assert_eq!(munmap(START, LEN), 0);
+    assert_eq!(free_frames(), before_munmap + 1);
+    println!("mmap_zero_page passed!");
+    0
+}
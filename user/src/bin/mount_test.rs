@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, open, read, write, Errno, OpenFlags};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let test_str = "Hello, mounted fs!";
+    let fd = open("/mnt/mntfile\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    write(fd, test_str.as_bytes());
+    close(fd);
+
+    // the file lives on the second device only
+    assert_eq!(open("mntfile\0", OpenFlags::RDONLY), Errno::ENOENT.ret());
+    assert_eq!(open("/mntfile\0", OpenFlags::RDONLY), Errno::ENOENT.ret());
+
+    let fd = open("/mnt/mntfile\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let mut buffer = [0u8; 100];
+    let read_len = read(fd, &mut buffer) as usize;
+    close(fd);
+    assert_eq!(test_str, core::str::from_utf8(&buffer[..read_len]).unwrap());
+
+    // a leading slash still resolves into the root fs
+    let fd = open("/initproc\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    println!("mount_test passed!");
+    0
+}
@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mprotect, munmap, waitpid, Errno, PROT_READ, PROT_WRITE};
+
+const START: usize = 0x2000_0000;
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let page = START as *mut u8;
+    assert_eq!(
+        mmap(START, 2 * PAGE_SIZE, PROT_READ | PROT_WRITE),
+        START as isize
+    );
+    unsafe { page.write_volatile(1) };
+    assert_eq!(mprotect(START, PAGE_SIZE, PROT_READ), 0);
+    assert_eq!(unsafe { page.read_volatile() }, 1);
+    let pid = fork();
+    if pid == 0 {
+        // the page is read-only now, so this write faults
+        unsafe { page.write_volatile(2) };
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -11);
+    // the second page of the mapping is still writable, though never touched
+    let second = (START + PAGE_SIZE) as *mut u8;
+    unsafe { second.write_volatile(3) };
+    assert_eq!(unsafe { second.read_volatile() }, 3);
+
+    // a range with an unmapped gap in the middle is refused as a whole
+    let far = START + 3 * PAGE_SIZE;
+    assert_eq!(mmap(far, PAGE_SIZE, PROT_READ | PROT_WRITE), far as isize);
+    assert_eq!(
+        mprotect(START, 4 * PAGE_SIZE, PROT_READ),
+        Errno::ENOMEM.ret()
+    );
+    unsafe { second.write_volatile(4) };
+    assert_eq!(mprotect(START, 2 * PAGE_SIZE, PROT_READ | PROT_WRITE), 0);
+    unsafe { page.write_volatile(5) };
+    assert_eq!(unsafe { page.read_volatile() }, 5);
+    assert_eq!(munmap(START, 2 * PAGE_SIZE), 0);
+    assert_eq!(munmap(far, PAGE_SIZE), 0);
+    println!("mprotect_mmap passed!");
+    0
+}
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::{addr_of, addr_of_mut};
+use user_lib::{exit, fork, mprotect, waitpid, Errno, PROT_READ, PROT_WRITE};
+
+const PAGE_SIZE: usize = 4096;
+
+/// A page of its own, so that protecting it leaves the rest of .bss alone.
+#[repr(C, align(4096))]
+struct Page([u8; PAGE_SIZE]);
+
+static mut PAGE: Page = Page([0; PAGE_SIZE]);
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let page = unsafe { addr_of_mut!(PAGE) as *mut u8 };
+    unsafe { page.write_volatile(1) };
+    assert_eq!(mprotect(page as usize, PAGE_SIZE, PROT_READ), 0);
+    // still readable
+    assert_eq!(unsafe { addr_of!(PAGE.0[0]).read_volatile() }, 1);
+
+    let pid = fork();
+    if pid == 0 {
+        // the read-only mapping is inherited, so this write faults
+        unsafe { page.write_volatile(2) };
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -11);
+    assert_eq!(unsafe { page.read_volatile() }, 1);
+
+    assert_eq!(mprotect(page as usize, PAGE_SIZE, PROT_READ | PROT_WRITE), 0);
+    unsafe { page.write_volatile(3) };
+    assert_eq!(unsafe { page.read_volatile() }, 3);
+
+    // unaligned start, write-only and unmapped ranges are refused
+    assert_eq!(mprotect(page as usize + 1, PAGE_SIZE, PROT_READ), Errno::EINVAL.ret());
+    assert_eq!(mprotect(page as usize, PAGE_SIZE, PROT_WRITE), Errno::EINVAL.ret());
+    assert_eq!(mprotect(0, PAGE_SIZE, PROT_READ), Errno::ENOMEM.ret());
+    println!("mprotect_test passed!");
+    0
+}
@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mq_recv, mq_send, waitpid, Errno};
+
+const MQ_ID: usize = 57;
+/// Never sent to.
+const UNUSED_MQ_ID: usize = 58;
+const MESSAGES: usize = 100;
+
+/// Message `i` is `i % 50 + 1` bytes of `i`, so lengths and contents differ.
+fn message(i: usize, buffer: &mut [u8]) -> &[u8] {
+    let len = i % 50 + 1;
+    buffer[..len].fill(i as u8);
+    &buffer[..len]
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mq_recv(UNUSED_MQ_ID, &mut [0u8; 8]), Errno::ENOENT.ret());
+    // create the queue before the consumer starts receiving from it
+    let mut buffer = [0u8; 64];
+    assert_eq!(mq_send(MQ_ID, message(0, &mut buffer)), 0);
+    let pid = fork();
+    if pid == 0 {
+        // far more than the queue holds, so the producer has to wait
+        for i in 1..MESSAGES {
+            assert_eq!(mq_send(MQ_ID, message(i, &mut buffer)), 0);
+        }
+        exit(0);
+    }
+    let mut expected = [0u8; 64];
+    for i in 0..MESSAGES {
+        let expected = message(i, &mut expected);
+        // a buffer too small for the next message leaves it queued
+        if expected.len() > 1 {
+            assert_eq!(mq_recv(MQ_ID, &mut buffer[..1]), Errno::EMSGSIZE.ret());
+        }
+        let len = mq_recv(MQ_ID, &mut buffer);
+        assert_eq!(len as usize, expected.len());
+        assert_eq!(&buffer[..len as usize], expected);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(mq_send(MQ_ID, &[0u8; 257]), Errno::EMSGSIZE.ret());
+    println!("mq_test passed!");
+    0
+}
@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, open, read, write, Errno, OpenFlags};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // CREATE | EXCL fails once the file exists
+    let name = "open_excl\0";
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::EXCL | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    assert_eq!(
+        open(name, OpenFlags::CREATE | OpenFlags::EXCL | OpenFlags::WRONLY),
+        Errno::EEXIST.ret()
+    );
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+
+    // CREATE alone keeps the contents, TRUNC empties the file
+    let name = "open_trunc\0";
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"some data"), 9);
+    close(fd as usize);
+    let mut buffer = [0u8; 16];
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    assert_eq!(read(fd as usize, &mut buffer), 9);
+    close(fd as usize);
+    let fd = open(name, OpenFlags::TRUNC | OpenFlags::RDWR);
+    assert!(fd > 0);
+    assert_eq!(read(fd as usize, &mut buffer), 0);
+    close(fd as usize);
+    println!("open_flags passed!");
+    0
+}
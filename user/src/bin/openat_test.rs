@@ -0,0 +1,104 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fstat, open, openat, read, write, Errno, OpenFlags, Stat, StatMode, AT_FDCWD,
+};
+
+fn read_file(fd: isize, buffer: &mut [u8]) -> usize {
+    assert!(fd > 0);
+    let len = read(fd as usize, buffer);
+    close(fd as usize);
+    len as usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open(
+        "openat_file\0",
+        OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY,
+    );
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"relative"), 8);
+    close(fd as usize);
+
+    let dirfd = open("/\0", OpenFlags::RDONLY);
+    assert!(dirfd > 0);
+    let mut stat = Stat::new();
+    assert_eq!(fstat(dirfd as usize, &mut stat), 0);
+    assert!(stat.mode.contains(StatMode::DIR));
+    let mut buffer = [0u8; 16];
+    assert_eq!(read(dirfd as usize, &mut buffer), 0);
+
+    // through the directory fd, AT_FDCWD and an absolute path alike
+    let mut expected = [0u8; 16];
+    let len = read_file(open("/openat_file\0", OpenFlags::RDONLY), &mut expected);
+    assert_eq!(&expected[..len], b"relative");
+    for (base, path) in [
+        (dirfd, "openat_file\0"),
+        (dirfd, "./openat_file\0"),
+        (AT_FDCWD, "openat_file\0"),
+        (dirfd, "/openat_file\0"),
+    ] {
+        let mut buffer = [0u8; 16];
+        assert_eq!(
+            read_file(openat(base, path, OpenFlags::RDONLY), &mut buffer),
+            len
+        );
+        assert_eq!(buffer, expected);
+    }
+
+    // a directory fd on the fs mounted at /mnt/ resolves inside that fs
+    let fd = open(
+        "/mnt/openat_mnt\0",
+        OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY,
+    );
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"mounted"), 7);
+    close(fd as usize);
+    let mnt_dirfd = open("/mnt/\0", OpenFlags::RDONLY);
+    assert!(mnt_dirfd > 0);
+    let mut expected = [0u8; 16];
+    let len = read_file(open("/mnt/openat_mnt\0", OpenFlags::RDONLY), &mut expected);
+    assert_eq!(&expected[..len], b"mounted");
+    let mut buffer = [0u8; 16];
+    assert_eq!(
+        read_file(
+            openat(mnt_dirfd, "openat_mnt\0", OpenFlags::RDONLY),
+            &mut buffer
+        ),
+        len
+    );
+    assert_eq!(buffer, expected);
+    assert_eq!(
+        openat(mnt_dirfd, "openat_file\0", OpenFlags::RDONLY),
+        Errno::ENOENT.ret()
+    );
+    close(mnt_dirfd as usize);
+
+    let fd = openat(dirfd, "openat_file\0", OpenFlags::RDONLY);
+    assert_eq!(
+        openat(fd, "openat_file\0", OpenFlags::RDONLY),
+        Errno::ENOTDIR.ret()
+    );
+    close(fd as usize);
+    assert_eq!(
+        openat(fd, "openat_file\0", OpenFlags::RDONLY),
+        Errno::EBADF.ret()
+    );
+    assert_eq!(
+        openat(dirfd, "../openat_file\0", OpenFlags::RDONLY),
+        Errno::EINVAL.ret()
+    );
+    assert_eq!(
+        openat(dirfd, "..\0", OpenFlags::RDONLY),
+        Errno::EINVAL.ret()
+    );
+    assert_eq!(openat(dirfd, "\0", OpenFlags::WRONLY), Errno::EISDIR.ret());
+    close(dirfd as usize);
+    println!("openat_test passed!");
+    0
+}
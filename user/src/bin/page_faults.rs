@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{getpid, gettid, list_tasks, mmap, munmap, TaskInfo, PROT_READ, PROT_WRITE};
+
+const START: usize = 0x2400_0000;
+const PAGE_SIZE: usize = 4096;
+const PAGES: usize = 8;
+const MAX_TASKS: usize = 64;
+
+fn my_faults() -> (usize, usize) {
+    let mut infos = [TaskInfo::default(); MAX_TASKS];
+    let count = list_tasks(&mut infos);
+    assert!(count > 0);
+    let (pid, tid) = (getpid() as usize, gettid() as usize);
+    let info = infos[..(count as usize).min(MAX_TASKS)]
+        .iter()
+        .find(|info| info.pid == pid && info.tid == tid)
+        .unwrap();
+    (info.minor_faults, info.major_faults)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(
+        mmap(START, PAGES * PAGE_SIZE, PROT_READ | PROT_WRITE),
+        START as isize
+    );
+    let (minor_before, major_before) = my_faults();
+    // reads see the shared zero frame without faulting
+    for i in 0..PAGES {
+        let page = (START + i * PAGE_SIZE) as *const u8;
+        assert_eq!(unsafe { page.read_volatile() }, 0);
+    }
+    assert_eq!(my_faults().0, minor_before);
+    // the first write to each page takes one fault, later writes none
+    for _ in 0..2 {
+        for i in 0..PAGES {
+            let page = (START + i * PAGE_SIZE) as *mut u8;
+            unsafe { page.write_volatile(i as u8 + 1) };
+        }
+    }
+    let (minor_after, major_after) = my_faults();
+    assert_eq!(minor_after - minor_before, PAGES);
+    assert_eq!(major_after, major_before);
+    assert_eq!(munmap(START, PAGES * PAGE_SIZE), 0);
+    println!("page_faults passed!");
+    0
+}
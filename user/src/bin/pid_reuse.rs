@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, waitpid};
+
+const ROUNDS: usize = 100;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut min_pid = isize::MAX;
+    let mut max_pid = 0;
+    for i in 0..ROUNDS {
+        let pid = fork();
+        if pid == 0 {
+            exit(i as i32);
+        }
+        assert!(pid > 0);
+        let mut exit_code = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        assert_eq!(exit_code, i as i32);
+        min_pid = min_pid.min(pid);
+        max_pid = max_pid.max(pid);
+    }
+    // a reaped child gives its pid back, so the next one takes it again
+    println!("pids {}..={} over {} children", min_pid, max_pid, ROUNDS);
+    assert!(max_pid - min_pid < 4);
+    println!("pid_reuse passed!");
+    0
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, get_time, pipe, read, sleep, waitpid, write};
+
+const SLEEP_MS: usize = 100;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let start = get_time();
+    let pid = fork();
+    if pid == 0 {
+        // reader blocks on the empty pipe until the writer wakes it
+        close(pipe_fd[1]);
+        let mut buffer = [0u8; 5];
+        assert_eq!(read(pipe_fd[0], &mut buffer), 5);
+        assert_eq!(&buffer, b"wake!");
+        assert!(get_time() - start >= SLEEP_MS as isize);
+        // the write end is closed now, so read returns instead of blocking
+        assert_eq!(read(pipe_fd[0], &mut buffer), 0);
+        close(pipe_fd[0]);
+        exit(0);
+    }
+    close(pipe_fd[0]);
+    sleep(SLEEP_MS);
+    assert_eq!(write(pipe_fd[1], b"wake!"), 5);
+    close(pipe_fd[1]);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("pipe_block passed!");
+    0
+}
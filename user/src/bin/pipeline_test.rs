@@ -0,0 +1,70 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::null;
+use user_lib::{close, dup2, exec, fork, pipe, read, waitpid};
+
+const LINES: usize = 100;
+
+/// Wire up `gen_lines 100 | count_lines` the way the shell does
+/// and read what `count_lines` prints through a second pipe.
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut gen_to_count = [0usize; 2];
+    let mut count_to_test = [0usize; 2];
+    assert_eq!(pipe(&mut gen_to_count), 0);
+    assert_eq!(pipe(&mut count_to_test), 0);
+    let pipes = [gen_to_count, count_to_test];
+    let close_all = || {
+        for pipe_fd in pipes.iter() {
+            close(pipe_fd[0]);
+            close(pipe_fd[1]);
+        }
+    };
+
+    let generator = fork();
+    if generator == 0 {
+        assert_eq!(dup2(gen_to_count[1], 1), 1);
+        close_all();
+        let args = ["gen_lines\0".as_ptr(), "100\0".as_ptr(), null()];
+        exec("gen_lines\0", &args);
+        unreachable!();
+    }
+    let consumer = fork();
+    if consumer == 0 {
+        assert_eq!(dup2(gen_to_count[0], 0), 0);
+        assert_eq!(dup2(count_to_test[1], 1), 1);
+        close_all();
+        let args = ["count_lines\0".as_ptr(), null()];
+        exec("count_lines\0", &args);
+        unreachable!();
+    }
+    // keep only the read end of the result, or no EOF ever comes
+    close(gen_to_count[0]);
+    close(gen_to_count[1]);
+    close(count_to_test[1]);
+
+    let mut buffer = [0u8; 32];
+    let mut len = 0;
+    loop {
+        let size = read(count_to_test[0], &mut buffer[len..]) as usize;
+        if size == 0 {
+            break;
+        }
+        len += size;
+    }
+    close(count_to_test[0]);
+    let output = core::str::from_utf8(&buffer[..len]).unwrap();
+    assert_eq!(output.trim().parse::<usize>().unwrap(), LINES);
+
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(generator as usize, &mut exit_code), generator);
+    assert_eq!(exit_code, 0);
+    assert_eq!(waitpid(consumer as usize, &mut exit_code), consumer);
+    assert_eq!(exit_code, 0);
+    println!("pipeline_test passed!");
+    0
+}
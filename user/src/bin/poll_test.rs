@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, get_time, pipe, poll, read, sleep, waitpid, write, PollEvents, PollFd,
+};
+
+const DELAY_MS: usize = 50;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let pid = fork();
+    if pid == 0 {
+        close(pipe_fd[0]);
+        sleep(DELAY_MS);
+        assert_eq!(write(pipe_fd[1], b"ready"), 5);
+        close(pipe_fd[1]);
+        exit(0);
+    }
+    close(pipe_fd[1]);
+    // nothing written yet, a zero timeout returns at once
+    let mut fds = [PollFd::new(pipe_fd[0], PollEvents::POLLIN)];
+    assert_eq!(poll(&mut fds, 0), 0);
+    assert!(fds[0].revents().is_empty());
+    // wait for the writer
+    let start = get_time();
+    assert_eq!(poll(&mut fds, -1), 1);
+    assert!(get_time() - start >= DELAY_MS as isize - 10);
+    assert!(fds[0].revents().contains(PollEvents::POLLIN));
+    let mut buffer = [0u8; 8];
+    assert_eq!(read(pipe_fd[0], &mut buffer), 5);
+    assert_eq!(&buffer[..5], b"ready");
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    close(pipe_fd[0]);
+    // a closed fd is reported as invalid
+    assert_eq!(poll(&mut fds, 0), 1);
+    assert!(fds[0].revents().contains(PollEvents::POLLNVAL));
+    println!("poll_test passed!");
+    0
+}
@@ -0,0 +1,21 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{reboot, shutdown, Errno};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // a debug kernel would really power off, assume it matches this build
+    if cfg!(debug_assertions) {
+        println!("power_off_test skipped in debug builds");
+        return 0;
+    }
+    // only the initial process may power off or reboot
+    assert_eq!(shutdown(3), Errno::EPERM.ret());
+    assert_eq!(reboot(), Errno::EPERM.ret());
+    println!("power_off_test passed!");
+    0
+}
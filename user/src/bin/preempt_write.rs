@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use user_lib::{close, exit, get_time, open, thread_create, unlink, waittid, write, OpenFlags};
+
+const CHUNK: usize = 64 * 1024;
+const ROUNDS: usize = 16;
+
+static mut DATA: [u8; CHUNK] = [0; CHUNK];
+static SPINS: AtomicUsize = AtomicUsize::new(0);
+static STOP: AtomicBool = AtomicBool::new(false);
+
+fn spinner() -> ! {
+    while !STOP.load(Ordering::Relaxed) {
+        SPINS.fetch_add(1, Ordering::Relaxed);
+    }
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let tid = thread_create(spinner as usize, 0);
+    assert!(tid > 0);
+    let fd = open(
+        "preempt_write\0",
+        OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY,
+    );
+    assert!(fd > 0);
+    // the spinner can only move during a write if the syscall was preempted
+    let mut preempted = 0;
+    let start = get_time();
+    for _ in 0..ROUNDS {
+        let before = SPINS.load(Ordering::Relaxed);
+        assert_eq!(write(fd as usize, unsafe { &DATA }), CHUNK as isize);
+        if SPINS.load(Ordering::Relaxed) != before {
+            preempted += 1;
+        }
+    }
+    let elapsed = get_time() - start;
+    close(fd as usize);
+    assert_eq!(unlink("preempt_write\0"), 0);
+    STOP.store(true, Ordering::Relaxed);
+    assert_eq!(waittid(tid as usize), 0);
+    println!(
+        "{} of {} writes were preempted in {}ms",
+        preempted, ROUNDS, elapsed
+    );
+    assert!(preempted > 0);
+    println!("preempt_write passed!");
+    0
+}
@@ -0,0 +1,77 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, mmap, munmap, open, read, Errno, OpenFlags, PROT_READ, PROT_WRITE};
+
+const PAGE_SIZE: usize = 4096;
+const PAGES: usize = 16;
+const START: usize = 0x2b00_0000;
+
+/// Print the file `path` like cat, return what was read into `buf`.
+fn cat<'a>(path: &str, buf: &'a mut [u8]) -> &'a str {
+    let fd = open(path, OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let mut len = 0;
+    // small reads go on with the same contents
+    loop {
+        let end = (len + 7).min(buf.len());
+        let size = read(fd, &mut buf[len..end]);
+        assert!(size >= 0);
+        if size == 0 {
+            break;
+        }
+        len += size as usize;
+    }
+    close(fd);
+    let text = core::str::from_utf8(&buf[..len]).unwrap();
+    print!("{}", text);
+    text
+}
+
+/// The number on the line of /proc/meminfo starting with `key`.
+fn meminfo(key: &str) -> usize {
+    let mut buf = [0u8; 256];
+    cat("/proc/meminfo\0", &mut buf)
+        .lines()
+        .find_map(|line| line.strip_prefix(key))
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut buf = [0u8; 256];
+    let (secs, ms) = cat("/proc/uptime\0", &mut buf)
+        .trim_end()
+        .split_once('.')
+        .unwrap();
+    assert!(secs.parse::<usize>().is_ok());
+    assert_eq!(ms.len(), 3);
+    assert!(meminfo("Tasks:") > 0);
+    let used = meminfo("FramesUsed:");
+    assert_eq!(
+        mmap(START, PAGES * PAGE_SIZE, PROT_READ | PROT_WRITE),
+        START as isize
+    );
+    // the pages only get frames of their own once written
+    let memory = unsafe { core::slice::from_raw_parts_mut(START as *mut u8, PAGES * PAGE_SIZE) };
+    memory.fill(1);
+    assert!(meminfo("FramesUsed:") >= used + PAGES);
+    assert_eq!(munmap(START, PAGES * PAGE_SIZE), 0);
+    assert_eq!(
+        open("/proc/meminfo\0", OpenFlags::WRONLY),
+        Errno::EACCES.ret()
+    );
+    assert_eq!(
+        open("/proc/no_such_file\0", OpenFlags::RDONLY),
+        Errno::ENOENT.ret()
+    );
+    println!("proc_test passed!");
+    0
+}
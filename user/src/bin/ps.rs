@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{list_tasks, TaskInfo, TASK_BLOCKING, TASK_READY, TASK_RUNNING, TASK_STOPPED};
+
+const MAX_TASKS: usize = 64;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut infos = [TaskInfo::default(); MAX_TASKS];
+    let total = list_tasks(&mut infos) as usize;
+    println!("  PID  TID  STATUS    USER(us)  KERNEL(us)");
+    for info in infos.iter().take(total.min(MAX_TASKS)) {
+        let status = match info.status {
+            TASK_READY => "ready",
+            TASK_RUNNING => "running",
+            TASK_BLOCKING => "blocked",
+            TASK_STOPPED => "stopped",
+            _ => "unknown",
+        };
+        println!(
+            "{:>5} {:>4}  {:<8} {:>9} {:>11}",
+            info.pid, info.tid, status, info.user_time_us, info.kernel_time_us
+        );
+    }
+    if total > MAX_TASKS {
+        println!("... {} more", total - MAX_TASKS);
+    }
+    0
+}
@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, list_tasks, sleep, wait, TaskInfo};
+
+/// More children than any fixed per-app table would hold.
+const CHILDREN: usize = 20;
+const BUSY_MS: isize = 5;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pids = [0usize; CHILDREN];
+    for pid in pids.iter_mut() {
+        let child = fork();
+        if child == 0 {
+            let start = get_time();
+            while get_time() - start < BUSY_MS {}
+            // stay alive until the parent has looked at us
+            sleep(200);
+            exit(0);
+        }
+        assert!(child > 0);
+        *pid = child as usize;
+    }
+    sleep(100);
+    let mut infos = [TaskInfo::default(); 2 * CHILDREN];
+    let total = list_tasks(&mut infos) as usize;
+    assert!(total <= infos.len());
+    for pid in pids.iter() {
+        let info = infos[..total].iter().find(|info| info.pid == *pid).unwrap();
+        assert!(info.user_time_us + info.kernel_time_us > 0);
+    }
+    let mut exit_code: i32 = 0;
+    for _ in 0..CHILDREN {
+        assert!(wait(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+    println!("runtime_many passed!");
+    0
+}
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{get_time, setitimer, sigaction, sigreturn, yield_, SIGALRM};
+
+static ALARMS: AtomicUsize = AtomicUsize::new(0);
+
+fn alarm_handler(signum: usize) {
+    assert_eq!(signum, SIGALRM);
+    ALARMS.fetch_add(1, Ordering::SeqCst);
+    sigreturn();
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("into sig_alarm test!");
+    assert_eq!(sigaction(SIGALRM, alarm_handler as usize), 0);
+    // fire after 20ms and then every 20ms
+    setitimer(20, 20);
+    let start = get_time();
+    while ALARMS.load(Ordering::SeqCst) < 3 {
+        assert!(get_time() - start < 1000, "SIGALRM was not delivered");
+        yield_();
+    }
+    setitimer(0, 0);
+    println!(
+        "got {} alarms in {}ms",
+        ALARMS.load(Ordering::SeqCst),
+        get_time() - start
+    );
+    println!("sig_alarm passed!");
+    0
+}
@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, kill, list_tasks, pause, sleep, times, waitpid, Errno, SignalFlags, TaskInfo, Tms,
+    TASK_BLOCKING, TASK_STOPPED,
+};
+
+const MAX_TASKS: usize = 32;
+
+fn task_of(pid: usize) -> TaskInfo {
+    let mut infos = [TaskInfo::default(); MAX_TASKS];
+    let total = list_tasks(&mut infos) as usize;
+    *infos[..total.min(MAX_TASKS)]
+        .iter()
+        .find(|info| info.pid == pid)
+        .unwrap()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // a paused child returns -EINTR once a signal arrives
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(pause(), Errno::EINTR.ret());
+        exit(7);
+    }
+    sleep(20);
+    assert_eq!(task_of(pid as usize).status, TASK_BLOCKING);
+    assert_eq!(kill(pid as usize, SignalFlags::SIGCONT.bits()), 0);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+
+    // a stopped child makes no progress until SIGCONT
+    let pid = fork();
+    if pid == 0 {
+        let mut tms = Tms::default();
+        let mut x: usize = 1;
+        while tms.utime < 100_000 {
+            for i in 0..100_000usize {
+                // volatile, so that the loop is not optimized away
+                unsafe {
+                    core::ptr::write_volatile(&mut x, x.wrapping_mul(31).wrapping_add(i));
+                }
+            }
+            times(&mut tms);
+        }
+        exit(0);
+    }
+    sleep(20);
+    assert_eq!(kill(pid as usize, SignalFlags::SIGSTOP.bits()), 0);
+    // it stops the next time it leaves the kernel
+    sleep(30);
+    let stopped = task_of(pid as usize);
+    assert_eq!(stopped.status, TASK_STOPPED);
+    sleep(50);
+    let still = task_of(pid as usize);
+    assert_eq!(still.status, TASK_STOPPED);
+    assert_eq!(still.user_time_us, stopped.user_time_us);
+    assert_eq!(kill(pid as usize, SignalFlags::SIGCONT.bits()), 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("sig_stop_cont passed!");
+    0
+}
@@ -0,0 +1,23 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, sleep};
+
+/// The kernel timer fires every 10ms.
+const TICK_MS: isize = 10;
+const SLEEP_MS: isize = 50;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = get_time();
+    assert_eq!(sleep(SLEEP_MS as usize), 0);
+    let elapsed = get_time() - start;
+    println!("slept {}ms for a {}ms request", elapsed, SLEEP_MS);
+    assert!(elapsed >= SLEEP_MS);
+    assert!(elapsed <= SLEEP_MS + TICK_MS);
+    println!("sleep_precise passed!");
+    0
+}
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exec, fork, set_stack_aslr, waitpid, Errno};
+
+const RUNS: usize = 4;
+
+/// The stack page of a fresh run of stack_page.
+fn stack_page() -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exec("stack_page\0", &[core::ptr::null::<u8>()]);
+        panic!("exec stack_page failed");
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert!(exit_code > 0);
+    exit_code
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // only the initial process may change it, unless the kernel is a debug
+    // build; assume it matches this build
+    if !cfg!(debug_assertions) {
+        assert_eq!(set_stack_aslr(false), Errno::EPERM.ret());
+        println!("stack_aslr passed!");
+        return 0;
+    }
+    let was_on = set_stack_aslr(false) == 1;
+    let page = stack_page();
+    assert!((0..RUNS).all(|_| stack_page() == page));
+    assert_eq!(set_stack_aslr(true), 0);
+    let mut pages = [0; RUNS];
+    for page in pages.iter_mut() {
+        *page = stack_page();
+    }
+    println!("stack pages: {:?}", pages);
+    assert!(pages.iter().any(|&page| page != pages[0]));
+    set_stack_aslr(was_on);
+    println!("stack_aslr passed!");
+    0
+}
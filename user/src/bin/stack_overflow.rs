@@ -14,7 +14,7 @@ fn f(depth: usize) {
 
 #[no_mangle]
 pub fn main() -> i32 {
-    println!("It should trigger segmentation fault!");
+    println!("It should trigger segmentation fault, reported as stack overflow!");
     f(0);
     0
 }
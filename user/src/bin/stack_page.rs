@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::arch::asm;
+
+/// Use some stack, so that a misplaced one would fault.
+fn depth(n: usize) -> usize {
+    let frame = [n; 16];
+    if n == 0 {
+        0
+    } else {
+        // volatile, so that the frame is not optimized away
+        unsafe { core::ptr::read_volatile(&frame[n % 16]) + depth(n - 1) }
+    }
+}
+
+/// Exit with the page number of the stack pointer, for stack_aslr.
+#[no_mangle]
+pub fn main() -> i32 {
+    let sp: usize;
+    unsafe {
+        asm!("mv {}, sp", out(reg) sp);
+    }
+    assert_eq!(depth(100), 5050);
+    (sp >> 12) as i32
+}
@@ -0,0 +1,30 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, fstat, open, stat, Errno, OpenFlags, Stat, StatMode};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let name = "stat_file\0";
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let mut by_fd = Stat::new();
+    assert_eq!(fstat(fd as usize, &mut by_fd), 0);
+    close(fd as usize);
+
+    // stat by path agrees with fstat on the same file
+    let mut by_path = Stat::new();
+    assert_eq!(stat(name, &mut by_path), 0);
+    assert!(by_path.mode.contains(StatMode::FILE));
+    assert_eq!(by_path.ino, by_fd.ino);
+    assert_eq!(by_path.mode, by_fd.mode);
+    assert_eq!(by_path.nlink, 1);
+
+    let mut missing = Stat::new();
+    assert_eq!(stat("stat_missing\0", &mut missing), Errno::ENOENT.ret());
+    println!("stat_test passed!");
+    0
+}
@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, open, read, sync, unlink, write, OpenFlags};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let name = "sync_file\0";
+    let data = b"written before sync";
+    let fd = open(
+        name,
+        OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY,
+    );
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, data), data.len() as isize);
+    assert_eq!(sync(), 0);
+    close(fd as usize);
+    // syncing with nothing dirty is fine too
+    assert_eq!(sync(), 0);
+    let fd = open(name, OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let mut buffer = [0u8; 64];
+    assert_eq!(read(fd as usize, &mut buffer), data.len() as isize);
+    assert_eq!(&buffer[..data.len()], data);
+    close(fd as usize);
+    assert_eq!(unlink(name), 0);
+    println!("sync_test passed!");
+    0
+}
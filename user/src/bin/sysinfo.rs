@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, sleep, sysinfo, waitpid, SysInfo};
+
+const CHILDREN: usize = 4;
+
+fn print_sysinfo(when: &str) -> SysInfo {
+    let mut info = SysInfo::default();
+    assert_eq!(sysinfo(&mut info), 0);
+    println!(
+        "{}: total_frames = {}, free_frames = {}, num_tasks = {}",
+        when, info.total_frames, info.free_frames, info.num_tasks
+    );
+    info
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let before = print_sysinfo("before fork");
+    assert!(before.free_frames <= before.total_frames);
+    let mut pids = [0usize; CHILDREN];
+    for pid in pids.iter_mut() {
+        let ret = fork();
+        if ret == 0 {
+            sleep(100);
+            exit(0);
+        }
+        *pid = ret as usize;
+    }
+    let running = print_sysinfo("children running");
+    assert_eq!(running.num_tasks, before.num_tasks + CHILDREN);
+    assert!(running.free_frames < before.free_frames);
+    let mut exit_code: i32 = 0;
+    for pid in pids {
+        assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+    }
+    let after = print_sysinfo("children reaped");
+    assert_eq!(after.num_tasks, before.num_tasks);
+    assert!(after.free_frames > running.free_frames);
+    println!("sysinfo passed!");
+    0
+}
@@ -0,0 +1,15 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("Into Test text_write, we will write into our own code...");
+    println!("Kernel should kill this application!");
+    unsafe {
+        (main as usize as *mut u8).write_volatile(0);
+    }
+    0
+}
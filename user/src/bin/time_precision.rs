@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, get_time_ns, get_time_us, sleep};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut last_us = get_time_us();
+    for _ in 0..1000 {
+        let now_us = get_time_us();
+        assert!(now_us >= last_us);
+        last_us = now_us;
+    }
+    let ns = get_time_ns();
+    assert!(ns / 1000 >= last_us);
+
+    let start_ms = get_time() as usize;
+    let start_us = get_time_us();
+    sleep(50);
+    let delta_ms = get_time() as usize - start_ms;
+    let delta_us = get_time_us() - start_us;
+    println!("slept {}ms, {}us", delta_ms, delta_us);
+    // both clocks come from the same counter, so they agree within a few ms
+    assert!(delta_us / 1000 + 2 >= delta_ms && delta_ms + 2 >= delta_us / 1000);
+    assert!(delta_us >= 50_000);
+    println!("time_precision passed!");
+    0
+}
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, set_time_slice, times, waitpid, Errno, Tms};
+
+const LONG_SLICE: usize = 4;
+const SHORT_SLICE: usize = 1;
+const SPIN_MS: isize = 500;
+
+/// Spin until `deadline` and exit with the user time spent, in ms.
+fn spin(slice: usize, deadline: isize) -> ! {
+    assert_eq!(set_time_slice(slice), 0);
+    while get_time() < deadline {}
+    let mut tms = Tms::default();
+    assert_eq!(times(&mut tms), 0);
+    exit((tms.utime / 1000) as i32);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(set_time_slice(0), Errno::EINVAL.ret());
+    // both children compete for the cpu until the same deadline
+    let deadline = get_time() + SPIN_MS;
+    let long = fork();
+    if long == 0 {
+        spin(LONG_SLICE, deadline);
+    }
+    let short = fork();
+    if short == 0 {
+        spin(SHORT_SLICE, deadline);
+    }
+    let (mut long_ms, mut short_ms) = (0, 0);
+    assert_eq!(waitpid(long as usize, &mut long_ms), long);
+    assert_eq!(waitpid(short as usize, &mut short_ms), short);
+    println!(
+        "slice {} ran {}ms, slice {} ran {}ms",
+        LONG_SLICE, long_ms, SHORT_SLICE, short_ms
+    );
+    // about 4:1, leave room for the other tasks and the time before the second fork
+    assert!(long_ms > 2 * short_ms);
+    println!("time_slice passed!");
+    0
+}
@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, times, waitpid, Tms};
+
+/// Spin in user mode without making any syscall.
+fn busy_loop() {
+    let mut x: usize = 1;
+    for i in 0..20_000_000usize {
+        // volatile, so that the loop is not optimized away
+        unsafe {
+            core::ptr::write_volatile(&mut x, x.wrapping_mul(31).wrapping_add(i));
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = Tms::default();
+    assert_eq!(times(&mut before), 0);
+    busy_loop();
+    let mut after = Tms::default();
+    assert_eq!(times(&mut after), 0);
+    let utime = after.utime - before.utime;
+    let stime = after.stime - before.stime;
+    println!("busy loop: utime {}us, stime {}us", utime, stime);
+    assert!(utime > 0);
+    assert!(stime < utime);
+    // the times of a child show up once it is reaped
+    assert_eq!(after.cutime, 0);
+    let pid = fork();
+    if pid == 0 {
+        busy_loop();
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(times(&mut after), 0);
+    assert!(after.cutime > 0);
+    println!("times_test passed!");
+    0
+}
@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec;
+
+use user_lib::{
+    close, fscachestats, open, read, stat, unlink, write, CacheStats, Errno, OpenFlags, Stat,
+};
+
+const LEN: usize = 10 * 1024;
+/// Size of the kernel heap, which holds the tmpfs files.
+const HEAP_SIZE: usize = 16 << 20;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = CacheStats::default();
+    assert_eq!(fscachestats(&mut before), 0);
+
+    let mut data = vec![0u8; LEN];
+    for (i, byte) in data.iter_mut().enumerate() {
+        *byte = (i % 251) as u8;
+    }
+    let fd = open(
+        "/tmp/x\0",
+        OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY,
+    );
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, &data), LEN as isize);
+    close(fd as usize);
+
+    let mut buffer = vec![0u8; LEN + 16];
+    let fd = open("/tmp/x\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    assert_eq!(read(fd as usize, &mut buffer), LEN as isize);
+    assert_eq!(read(fd as usize, &mut buffer), 0);
+    close(fd as usize);
+    assert!(buffer[..LEN] == data[..]);
+
+    let mut st = Stat::new();
+    assert_eq!(stat("/tmp/x\0", &mut st), 0);
+    assert_eq!(st.nlink, 1);
+    // the file is not on the root fs
+    assert_eq!(open("tmp/x\0", OpenFlags::RDONLY), Errno::ENOENT.ret());
+    assert_eq!(unlink("/tmp/x\0"), 0);
+    assert_eq!(open("/tmp/x\0", OpenFlags::RDONLY), Errno::ENOENT.ret());
+
+    // the tmpfs has a size limit well below the kernel heap
+    let fd = open("/tmp/full\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let mut total = 0;
+    let full = loop {
+        let ret = write(fd as usize, &data);
+        if ret < 0 || total > HEAP_SIZE {
+            break ret;
+        }
+        total += ret as usize;
+    };
+    assert_eq!(full, Errno::ENOSPC.ret());
+    close(fd as usize);
+    // unlinking gives the space back
+    assert_eq!(unlink("/tmp/full\0"), 0);
+    let fd = open("/tmp/x\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, &data), LEN as isize);
+    close(fd as usize);
+    assert_eq!(unlink("/tmp/x\0"), 0);
+
+    // none of this went through the block cache
+    let mut after = CacheStats::default();
+    assert_eq!(fscachestats(&mut after), 0);
+    assert_eq!(after.hits, before.hits);
+    assert_eq!(after.misses, before.misses);
+    println!("tmpfs_test passed!");
+    0
+}
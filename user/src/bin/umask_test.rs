@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, open, stat, umask, unlink, waitpid, Errno, OpenFlags, Stat, StatMode,
+};
+
+const PERM_MASK: u32 = 0o777;
+
+/// Create `path` afresh and return its permission bits.
+fn create_mode(path: &str) -> u32 {
+    unlink(path);
+    let fd = open(path, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    let mut st = Stat::new();
+    assert_eq!(stat(path, &mut st), 0);
+    assert!(st.mode.contains(StatMode::FILE));
+    st.mode.bits() & PERM_MASK
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the default takes nothing from 0o644
+    assert_eq!(umask(0o222), 0o022);
+    for path in ["umask_file\0", "/tmp/umask_file\0"] {
+        assert_eq!(create_mode(path), 0o444);
+        assert_eq!(open(path, OpenFlags::WRONLY), Errno::EACCES.ret());
+        // only new files are affected
+        let fd = open(path, OpenFlags::CREATE | OpenFlags::RDONLY);
+        assert!(fd > 0);
+        close(fd as usize);
+    }
+
+    // children start with the mask of their parent
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(umask(0o077), 0o222);
+        assert_eq!(create_mode("umask_file\0"), 0o600);
+        exit(0);
+    }
+    assert!(pid > 0);
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    assert_eq!(umask(0o022), 0o222);
+    assert_eq!(create_mode("umask_file\0"), 0o644);
+    assert_eq!(unlink("umask_file\0"), 0);
+    assert_eq!(unlink("/tmp/umask_file\0"), 0);
+    println!("umask_test passed!");
+    0
+}
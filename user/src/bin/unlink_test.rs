@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, link, open, read, unlink, write, Errno, OpenFlags};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for name in ["unlink_a\0", "unlink_b\0", "unlink_c\0"] {
+        let fd = open(
+            name,
+            OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY,
+        );
+        assert!(fd > 0);
+        assert_eq!(write(fd as usize, name.as_bytes()), name.len() as isize);
+        close(fd as usize);
+    }
+    assert_eq!(unlink("unlink_b\0"), 0);
+    assert_eq!(open("unlink_b\0", OpenFlags::RDONLY), Errno::ENOENT.ret());
+    assert_eq!(unlink("unlink_b\0"), Errno::ENOENT.ret());
+    // the neighbours of the removed entry are intact
+    let mut buffer = [0u8; 16];
+    for name in ["unlink_a\0", "unlink_c\0"] {
+        let fd = open(name, OpenFlags::RDONLY);
+        assert!(fd > 0);
+        assert_eq!(read(fd as usize, &mut buffer), name.len() as isize);
+        assert_eq!(&buffer[..name.len()], name.as_bytes());
+        close(fd as usize);
+    }
+    // a file outlives one of its two names
+    assert_eq!(link("unlink_a\0", "unlink_a2\0"), 0);
+    assert_eq!(unlink("unlink_a\0"), 0);
+    let fd = open("unlink_a2\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    for name in ["unlink_a2\0", "unlink_c\0"] {
+        assert_eq!(unlink(name), 0);
+    }
+    // an open file stays usable after its last name is gone
+    let writer = open("unlink_open\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(writer > 0);
+    let reader = open("unlink_open\0", OpenFlags::RDONLY);
+    assert!(reader > 0);
+    assert_eq!(write(writer as usize, b"before"), 6);
+    assert_eq!(unlink("unlink_open\0"), 0);
+    assert_eq!(
+        open("unlink_open\0", OpenFlags::RDONLY),
+        Errno::ENOENT.ret()
+    );
+    // a new file does not get the blocks of the unlinked one
+    let fd = open("unlink_other\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert_eq!(write(fd as usize, b"xxxxxxxxxxxx"), 12);
+    close(fd as usize);
+    assert_eq!(write(writer as usize, b"after"), 5);
+    assert_eq!(read(reader as usize, &mut buffer), 11);
+    assert_eq!(&buffer[..11], b"beforeafter");
+    close(writer as usize);
+    close(reader as usize);
+    assert_eq!(unlink("unlink_other\0"), 0);
+    println!("unlink_test passed!");
+    0
+}
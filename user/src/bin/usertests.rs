@@ -9,19 +9,29 @@ extern crate user_lib;
 
 // item of TESTS : app_name(argv_0), argv_1, argv_2, argv_3, exit_code
 static SUCC_TESTS: &[(&str, &str, &str, &str, i32)] = &[
+    ("backtrace\0", "\0", "\0", "\0", 0),
+    ("bss_zero\0", "\0", "\0", "\0", 0),
     ("filetest_simple\0", "\0", "\0", "\0", 0),
+    ("filetest_chunks\0", "\0", "\0", "\0", 0),
     ("cat\0", "filea\0", "\0", "\0", 0),
+    ("cloexec\0", "\0", "\0", "\0", 0),
     ("cmdline_args\0", "1\0", "2\0", "3\0", 0),
+    ("dup2_redirect\0", "\0", "\0", "\0", 0),
     ("eisenberg\0", "\0", "\0", "\0", 0),
+    ("exec_loop\0", "\0", "\0", "\0", 0),
     ("exit\0", "\0", "\0", "\0", 0),
+    ("exit_code\0", "\0", "\0", "\0", 0),
     ("fantastic_text\0", "\0", "\0", "\0", 0),
+    ("fork_oom\0", "\0", "\0", "\0", 0),
     ("forktest_simple\0", "\0", "\0", "\0", 0),
     ("forktest\0", "\0", "\0", "\0", 0),
     ("forktest2\0", "\0", "\0", "\0", 0),
     ("forktree\0", "\0", "\0", "\0", 0),
+    ("getppid\0", "\0", "\0", "\0", 0),
     ("hello_world\0", "\0", "\0", "\0", 0),
     ("huge_write\0", "\0", "\0", "\0", 0),
     ("matrix\0", "\0", "\0", "\0", 0),
+    ("mount_test\0", "\0", "\0", "\0", 0),
     ("mpsc_sem\0", "\0", "\0", "\0", 0),
     ("peterson\0", "\0", "\0", "\0", 0),
     ("phil_din_mutex\0", "\0", "\0", "\0", 0),
@@ -32,11 +42,67 @@ static SUCC_TESTS: &[(&str, &str, &str, &str, i32)] = &[
     ("adder_mutex_blocking\0", "\0", "\0", "\0", 0),
     ("adder_mutex_spin\0", "\0", "\0", "\0", 0),
     ("run_pipe_test\0", "\0", "\0", "\0", 0),
+    ("sig_alarm\0", "\0", "\0", "\0", 0),
     ("sleep_simple\0", "\0", "\0", "\0", 0),
     ("sleep\0", "\0", "\0", "\0", 0),
     ("sleep_simple\0", "\0", "\0", "\0", 0),
     ("sync_sem\0", "\0", "\0", "\0", 0),
+    ("sysinfo\0", "\0", "\0", "\0", 0),
     ("test_condvar\0", "\0", "\0", "\0", 0),
+    ("time_precision\0", "\0", "\0", "\0", 0),
+    ("errno_test\0", "\0", "\0", "\0", 0),
+    ("open_flags\0", "\0", "\0", "\0", 0),
+    ("pipe_block\0", "\0", "\0", "\0", 0),
+    ("link_test\0", "\0", "\0", "\0", 0),
+    ("chmod_test\0", "\0", "\0", "\0", 0),
+    ("listtasks\0", "\0", "\0", "\0", 0),
+    ("runtime_many\0", "\0", "\0", "\0", 0),
+    ("stat_test\0", "\0", "\0", "\0", 0),
+    ("mprotect_test\0", "\0", "\0", "\0", 0),
+    ("pipeline_test\0", "\0", "\0", "\0", 0),
+    ("yield_to\0", "\0", "\0", "\0", 0),
+    ("fork_ret\0", "\0", "\0", "\0", 0),
+    ("sleep_precise\0", "\0", "\0", "\0", 0),
+    ("clone_test\0", "\0", "\0", "\0", 0),
+    ("poll_test\0", "\0", "\0", "\0", 0),
+    ("fscachestats\0", "\0", "\0", "\0", 0),
+    ("getrandom\0", "\0", "\0", "\0", 0),
+    ("mmap_zero_page\0", "\0", "\0", "\0", 0),
+    ("times_test\0", "\0", "\0", "\0", 0),
+    ("sig_stop_cont\0", "\0", "\0", "\0", 0),
+    ("unlink_test\0", "\0", "\0", "\0", 0),
+    ("mprotect_mmap\0", "\0", "\0", "\0", 0),
+    ("openat_test\0", "\0", "\0", "\0", 0),
+    ("mq_test\0", "\0", "\0", "\0", 0),
+    ("exec_text\0", "\0", "\0", "\0", 0),
+    ("tmpfs_test\0", "\0", "\0", "\0", 0),
+    ("preempt_write\0", "\0", "\0", "\0", 0),
+    ("page_faults\0", "\0", "\0", "\0", 0),
+    ("waitpid_specific\0", "\0", "\0", "\0", 0),
+    ("date\0", "\0", "\0", "\0", 0),
+    ("fp_yield\0", "\0", "\0", "\0", 0),
+    ("ftruncate_test\0", "\0", "\0", "\0", 0),
+    ("getcwd_test\0", "\0", "\0", "\0", 0),
+    ("madvise_test\0", "\0", "\0", "\0", 0),
+    ("time_slice\0", "\0", "\0", "\0", 0),
+    ("power_off_test\0", "\0", "\0", "\0", 0),
+    ("mmap_anywhere\0", "\0", "\0", "\0", 0),
+    ("sync_test\0", "\0", "\0", "\0", 0),
+    ("fair_accounting\0", "\0", "\0", "\0", 0),
+    ("fsync_test\0", "\0", "\0", "\0", 0),
+    ("stack_aslr\0", "\0", "\0", "\0", 0),
+    ("fork_exec_test\0", "\0", "\0", "\0", 0),
+    ("bad_buffer\0", "\0", "\0", "\0", 0),
+    ("file_offsets\0", "\0", "\0", "\0", 0),
+    ("proc_test\0", "\0", "\0", "\0", 0),
+    ("ioctl_test\0", "\0", "\0", "\0", 0),
+    ("waitall_test\0", "\0", "\0", "\0", 0),
+    ("clock_nanosleep\0", "\0", "\0", "\0", 0),
+    ("mmap_checks\0", "\0", "\0", "\0", 0),
+    ("mmap_shared\0", "\0", "\0", "\0", 0),
+    ("pid_reuse\0", "\0", "\0", "\0", 0),
+    ("umask_test\0", "\0", "\0", "\0", 0),
+    ("access_test\0", "\0", "\0", "\0", 0),
     ("threads_arg\0", "\0", "\0", "\0", 0),
     ("threads\0", "\0", "\0", "\0", 0),
     ("yield\0", "\0", "\0", "\0", 0),
@@ -48,6 +114,9 @@ static FAIL_TESTS: &[(&str, &str, &str, &str, i32)] = &[
     ("priv_csr\0", "\0", "\0", "\0", -4),
     ("priv_inst\0", "\0", "\0", "\0", -4),
     ("store_fault\0", "\0", "\0", "\0", -11),
+    ("text_write\0", "\0", "\0", "\0", -11),
+    ("misaligned_amo\0", "\0", "\0", "\0", -7),
+    ("exit_one\0", "\0", "\0", "\0", 1),
     ("until_timeout\0", "\0", "\0", "\0", -6),
     ("adder\0", "\0", "\0", "\0", -6),
     ("adder_simple_spin\0", "\0", "\0", "\0", -6),
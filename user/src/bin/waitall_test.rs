@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, pipe, read, sysinfo, waitall, waitpid, write, yield_, Errno, SysInfo,
+};
+
+const CHILDREN: usize = 10;
+
+fn num_tasks() -> usize {
+    let mut info = SysInfo::default();
+    assert_eq!(sysinfo(&mut info), 0);
+    info.num_tasks
+}
+
+/// Wait until only `tasks` threads are left, a child that no longer counts
+/// is a zombie.
+fn wait_tasks(tasks: usize) {
+    while num_tasks() > tasks {
+        yield_();
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut codes = [0i32; CHILDREN + 1];
+    assert_eq!(waitall(&mut codes), Errno::ECHILD.ret());
+
+    let tasks = num_tasks();
+    for i in 0..CHILDREN {
+        let pid = fork();
+        if pid == 0 {
+            exit(i as i32 + 1);
+        }
+        assert!(pid > 0);
+    }
+    wait_tasks(tasks);
+    // a single call reaps them all
+    assert_eq!(waitall(&mut codes), CHILDREN as isize);
+    codes[..CHILDREN].sort_unstable();
+    for (i, code) in codes[..CHILDREN].iter().enumerate() {
+        assert_eq!(*code, i as i32 + 1);
+    }
+    assert_eq!(waitall(&mut codes), Errno::ECHILD.ret());
+
+    // a running child stays, and no more than fit in `codes` are reaped
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let running = fork();
+    if running == 0 {
+        close(pipe_fd[1]);
+        let mut byte = [0u8; 1];
+        assert_eq!(read(pipe_fd[0], &mut byte), 1);
+        exit(7);
+    }
+    assert!(running > 0);
+    for _ in 0..3 {
+        let pid = fork();
+        if pid == 0 {
+            exit(0);
+        }
+        assert!(pid > 0);
+    }
+    wait_tasks(tasks + 1);
+    assert_eq!(waitall(&mut codes[..2]), 2);
+    assert_eq!(waitall(&mut codes), 1);
+    assert_eq!(waitall(&mut codes), 0);
+    assert_eq!(write(pipe_fd[1], &[1]), 1);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(running as usize, &mut exit_code), running);
+    assert_eq!(exit_code, 7);
+    close(pipe_fd[0]);
+    close(pipe_fd[1]);
+    println!("waitall_test passed!");
+    0
+}
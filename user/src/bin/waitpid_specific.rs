@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, getppid, sleep, wait, waitpid, waitpid_nb, Errno};
+
+const CHILDREN: usize = 4;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pids = [0usize; CHILDREN];
+    for (i, pid) in pids.iter_mut().enumerate() {
+        let ret = fork();
+        if ret == 0 {
+            // the later children exit first
+            sleep(20 * (CHILDREN - i));
+            exit(100 + i as i32);
+        }
+        assert!(ret > 0);
+        *pid = ret as usize;
+    }
+    let mut exit_code: i32 = 0;
+    // neither ourselves nor our parent is a child
+    assert_eq!(
+        waitpid(getpid() as usize, &mut exit_code),
+        Errno::ECHILD.ret()
+    );
+    assert_eq!(
+        waitpid(getppid() as usize, &mut exit_code),
+        Errno::ECHILD.ret()
+    );
+    // the first child is still sleeping
+    assert_eq!(waitpid_nb(pids[0], &mut exit_code), Errno::EAGAIN.ret());
+
+    // reap the first child only, the others exit before it
+    assert_eq!(waitpid(pids[0], &mut exit_code), pids[0] as isize);
+    assert_eq!(exit_code, 100);
+    assert_eq!(waitpid(pids[0], &mut exit_code), Errno::ECHILD.ret());
+    // then a specific one of the zombies
+    assert_eq!(waitpid(pids[2], &mut exit_code), pids[2] as isize);
+    assert_eq!(exit_code, 102);
+    // any child picks up the rest
+    let mut reaped = [false; CHILDREN];
+    for _ in 0..2 {
+        let pid = wait(&mut exit_code);
+        let i = pids.iter().position(|p| *p as isize == pid).unwrap();
+        assert!(i == 1 || i == 3);
+        assert!(!reaped[i]);
+        reaped[i] = true;
+        assert_eq!(exit_code, 100 + i as i32);
+    }
+    assert_eq!(wait(&mut exit_code), Errno::ECHILD.ret());
+    println!("waitpid_specific passed!");
+    0
+}
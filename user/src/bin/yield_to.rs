@@ -0,0 +1,70 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, pipe, read, set_time_slice, waitpid, write, yield_to, Errno};
+
+/// The longest time slice the kernel allows. The slice is refilled every
+/// time the parent is scheduled, so it is not preempted between waking the
+/// children and yielding, where the first child would get to run first.
+const TIME_SLICE: usize = 100;
+
+/// Wake two blocked children in order and yield to the second one.
+/// Return the order in which they ran.
+fn handoff() -> [u8; 2] {
+    let mut wake = [[0usize; 2]; 2];
+    let mut result = [0usize; 2];
+    for wake_fd in wake.iter_mut() {
+        assert_eq!(pipe(wake_fd), 0);
+    }
+    assert_eq!(pipe(&mut result), 0);
+    let mut children = [0usize; 2];
+    for (i, child) in children.iter_mut().enumerate() {
+        let pid = fork();
+        if pid == 0 {
+            let mut byte = [0u8; 1];
+            assert_eq!(read(wake[i][0], &mut byte), 1);
+            write(result[1], &[b'0' + i as u8]);
+            exit(0);
+        }
+        *child = pid as usize;
+    }
+    assert_eq!(set_time_slice(TIME_SLICE), 0);
+    // a child stops being ready once it blocks on its pipe
+    for child in children.iter() {
+        while yield_to(*child) == 0 {}
+    }
+    // both are blocked on their pipes now
+    for child in children.iter() {
+        assert_eq!(yield_to(*child), Errno::EAGAIN.ret());
+    }
+    write(wake[0][1], b"x");
+    write(wake[1][1], b"x");
+    // the first child is ahead in the ready queue, but the second runs next
+    assert_eq!(yield_to(children[1]), 0);
+
+    close(result[1]);
+    let mut order = [0u8; 2];
+    assert_eq!(read(result[0], &mut order[..1]), 1);
+    assert_eq!(read(result[0], &mut order[1..]), 1);
+    let mut exit_code: i32 = 0;
+    for child in children.iter() {
+        assert_eq!(waitpid(*child, &mut exit_code), *child as isize);
+    }
+    close(result[0]);
+    for wake_fd in wake.iter() {
+        close(wake_fd[0]);
+        close(wake_fd[1]);
+    }
+    order
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(yield_to(usize::MAX), Errno::ESRCH.ret());
+    assert_eq!(&handoff(), b"10");
+    println!("yield_to passed!");
+    0
+}
@@ -0,0 +1,45 @@
+/// Error number of a failed syscall, which returns `-errno`.
+/// The values mirror the kernel's `Errno`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Errno(pub isize);
+
+impl Errno {
+    pub const EPERM: Errno = Errno(1);
+    pub const ENOENT: Errno = Errno(2);
+    pub const ESRCH: Errno = Errno(3);
+    pub const EINTR: Errno = Errno(4);
+    pub const EIO: Errno = Errno(5);
+    pub const ENOEXEC: Errno = Errno(8);
+    pub const EBADF: Errno = Errno(9);
+    pub const ECHILD: Errno = Errno(10);
+    pub const EAGAIN: Errno = Errno(11);
+    pub const ENOMEM: Errno = Errno(12);
+    pub const EACCES: Errno = Errno(13);
+    pub const EFAULT: Errno = Errno(14);
+    pub const EEXIST: Errno = Errno(17);
+    pub const EXDEV: Errno = Errno(18);
+    pub const ENOTDIR: Errno = Errno(20);
+    pub const EISDIR: Errno = Errno(21);
+    pub const EINVAL: Errno = Errno(22);
+    pub const ENOTTY: Errno = Errno(25);
+    pub const EFBIG: Errno = Errno(27);
+    pub const ENOSPC: Errno = Errno(28);
+    pub const ERANGE: Errno = Errno(34);
+    pub const EDEADLK: Errno = Errno(35);
+    pub const ENOSYS: Errno = Errno(38);
+    pub const EMSGSIZE: Errno = Errno(90);
+
+    /// Map the return value of a syscall to a `Result`.
+    pub fn check(ret: isize) -> Result<usize, Errno> {
+        if ret < 0 {
+            Err(Errno(-ret))
+        } else {
+            Ok(ret as usize)
+        }
+    }
+
+    /// The raw value returned by a syscall failing with this error.
+    pub fn ret(self) -> isize {
+        -self.0
+    }
+}
@@ -7,18 +7,219 @@ bitflags! {
         const RDWR = 1 << 1;
         const CREATE = 1 << 9;
         const TRUNC = 1 << 10;
+        const EXCL = 1 << 11;
+        /// close the fd on exec
+        const CLOEXEC = 1 << 19;
     }
 }
 
+/// `dirfd` of `openat` meaning the current directory.
+pub const AT_FDCWD: isize = -100;
+
+/// `mode` of `access`: the file exists, and its owner may read, write or
+/// execute it.
+pub const F_OK: u32 = 0;
+pub const R_OK: u32 = 4;
+pub const W_OK: u32 = 2;
+pub const X_OK: u32 = 1;
+
+bitflags! {
+    pub struct StatMode: u32 {
+        const NULL  = 0;
+        /// directory
+        const DIR   = 0o040000;
+        /// ordinary regular file
+        const FILE  = 0o100000;
+        /// owner has read permission
+        const S_IRUSR = 0o400;
+        /// owner has write permission
+        const S_IWUSR = 0o200;
+        /// owner has execute permission
+        const S_IXUSR = 0o100;
+        /// group and others permission bits
+        const S_IRWXG = 0o070;
+        const S_IRWXO = 0o007;
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct Stat {
+    /// ID of the device containing the file
+    pub dev: u64,
+    /// inode number
+    pub ino: u64,
+    /// file type and permission bits
+    pub mode: StatMode,
+    /// number of hard links
+    pub nlink: u32,
+    pad: [u64; 7],
+}
+
+impl Stat {
+    pub fn new() -> Self {
+        Self {
+            dev: 0,
+            ino: 0,
+            mode: StatMode::NULL,
+            nlink: 0,
+            pad: [0; 7],
+        }
+    }
+}
+
+impl Default for Stat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+bitflags! {
+    pub struct PollEvents: i16 {
+        /// there is data to read
+        const POLLIN = 0x001;
+        /// writing will not block
+        const POLLOUT = 0x004;
+        /// the fd is not open
+        const POLLNVAL = 0x020;
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct PollFd {
+    pub fd: i32,
+    pub events: i16,
+    pub revents: i16,
+}
+
+impl PollFd {
+    pub fn new(fd: usize, events: PollEvents) -> Self {
+        Self {
+            fd: fd as i32,
+            events: events.bits(),
+            revents: 0,
+        }
+    }
+    pub fn revents(&self) -> PollEvents {
+        PollEvents::from_bits_truncate(self.revents)
+    }
+}
+
+/// Block cache counters of the kernel file system since boot.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    /// misses that had to drop another cached block
+    pub evictions: usize,
+    /// blocks loaded by read-ahead before they were asked for
+    pub prefetches: usize,
+}
+
+pub const F_GETFD: usize = 1;
+pub const F_SETFD: usize = 2;
+pub const FD_CLOEXEC: usize = 1;
+
+/// `ioctl` requests of the console, `arg` points to a `Termios` or `Winsize`.
+pub const TCGETS: usize = 0x5401;
+pub const TCSETS: usize = 0x5402;
+pub const TIOCGWINSZ: usize = 0x5413;
+
+/// `Termios::lflag` bit: read input a line at a time, with erasing.
+pub const ICANON: u32 = 0o000002;
+/// `Termios::lflag` bit: echo input characters.
+pub const ECHO: u32 = 0o000010;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Termios {
+    pub iflag: u32,
+    pub oflag: u32,
+    pub cflag: u32,
+    pub lflag: u32,
+    pub line: u8,
+    pub cc: [u8; 19],
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Winsize {
+    pub row: u16,
+    pub col: u16,
+    pub xpixel: u16,
+    pub ypixel: u16,
+}
+
 pub fn dup(fd: usize) -> isize {
     sys_dup(fd)
 }
+pub fn dup2(old_fd: usize, new_fd: usize) -> isize {
+    sys_dup2(old_fd, new_fd)
+}
+pub fn fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    sys_fcntl(fd, cmd, arg)
+}
+pub fn ioctl(fd: usize, request: usize, arg: usize) -> isize {
+    sys_ioctl(fd, request, arg)
+}
+pub fn fstat(fd: usize, st: &mut Stat) -> isize {
+    sys_fstat(fd, st)
+}
+pub fn getcwd(buf: &mut [u8]) -> isize {
+    sys_getcwd(buf)
+}
+pub fn ftruncate(fd: usize, len: usize) -> isize {
+    sys_ftruncate(fd, len)
+}
+/// Write all cached file system blocks back to the disks.
+pub fn sync() -> isize {
+    sys_sync()
+}
+/// Write the cached blocks of the file `fd` back to its disk.
+pub fn fsync(fd: usize) -> isize {
+    sys_fsync(fd)
+}
+pub fn stat(path: &str, st: &mut Stat) -> isize {
+    sys_stat(path, st)
+}
+/// 0 if `path` exists and allows `mode`, checked without opening it.
+pub fn access(path: &str, mode: u32) -> isize {
+    sys_faccessat(path, mode)
+}
+pub fn chmod(path: &str, mode: u32) -> isize {
+    sys_chmod(path, mode)
+}
+/// Set the permission bits files created from now on go without, return the
+/// previous mask.
+pub fn umask(mask: u32) -> u32 {
+    sys_umask(mask) as u32
+}
+pub fn link(old_path: &str, new_path: &str) -> isize {
+    sys_linkat(old_path, new_path)
+}
+pub fn unlink(path: &str) -> isize {
+    sys_unlinkat(path)
+}
 pub fn open(path: &str, flags: OpenFlags) -> isize {
-    sys_open(path, flags.bits)
+    sys_openat(AT_FDCWD, path, flags.bits)
+}
+/// Open `path` relative to the directory open at `dirfd`, which may be `AT_FDCWD`.
+pub fn openat(dirfd: isize, path: &str, flags: OpenFlags) -> isize {
+    sys_openat(dirfd, path, flags.bits)
 }
 pub fn close(fd: usize) -> isize {
     sys_close(fd)
 }
+/// Wait up to `timeout` ms (forever if negative) for one of `fds` to be
+/// ready, return the number of ready fds.
+pub fn poll(fds: &mut [PollFd], timeout: isize) -> isize {
+    sys_poll(fds, timeout)
+}
+pub fn fscachestats(stats: &mut CacheStats) -> isize {
+    sys_fscachestats(stats)
+}
 pub fn pipe(pipe_fd: &mut [usize]) -> isize {
     sys_pipe(pipe_fd)
 }
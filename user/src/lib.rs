@@ -8,6 +8,7 @@
 pub mod console;
 mod lang_items;
 mod syscall;
+mod errno;
 mod file;
 mod task;
 mod sync;
@@ -20,6 +21,7 @@ extern crate bitflags;
 use alloc::vec::Vec;
 use buddy_system_allocator::LockedHeap;
 use syscall::*;
+pub use errno::Errno;
 pub use file::*;
 pub use task::*;
 pub use sync::*;
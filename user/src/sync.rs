@@ -29,4 +29,15 @@ pub fn condvar_signal(condvar_id: usize) {
 }
 pub fn condvar_wait(condvar_id: usize, mutex_id: usize) {
     sys_condvar_wait(condvar_id, mutex_id);
-}
\ No newline at end of file
+}
+/// Send `buf` as one message to queue `id`, blocking while the queue is full.
+/// The first send to an id creates its queue, or fails with -ENOSPC if there
+/// are too many.
+pub fn mq_send(id: usize, buf: &[u8]) -> isize {
+    sys_mq_send(id, buf)
+}
+/// Receive the oldest message of queue `id`, blocking while it is empty,
+/// and return its length; -ENOENT if the queue does not exist.
+pub fn mq_recv(id: usize, buf: &mut [u8]) -> isize {
+    sys_mq_recv(id, buf)
+}
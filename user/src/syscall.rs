@@ -1,21 +1,64 @@
+use super::{CacheStats, PollFd, Stat, SysInfo, TaskInfo, TimeSpec, Tms};
+
+const SYSCALL_GETCWD: usize = 17;
+const SYSCALL_DUP2: usize = 23;
 const SYSCALL_DUP: usize = 24;
-const SYSCALL_OPEN: usize = 56;
+const SYSCALL_FCNTL: usize = 25;
+const SYSCALL_IOCTL: usize = 29;
+const SYSCALL_PAUSE: usize = 34;
+const SYSCALL_UNLINKAT: usize = 35;
+const SYSCALL_LINKAT: usize = 37;
+const SYSCALL_FTRUNCATE: usize = 46;
+const SYSCALL_FACCESSAT: usize = 48;
+const SYSCALL_CHMOD: usize = 53;
+const SYSCALL_OPENAT: usize = 56;
 const SYSCALL_CLOSE: usize = 57;
 const SYSCALL_PIPE: usize = 59;
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
+const SYSCALL_POLL: usize = 73;
+const SYSCALL_STAT: usize = 79;
+const SYSCALL_FSTAT: usize = 80;
+const SYSCALL_SYNC: usize = 81;
+const SYSCALL_FSYNC: usize = 82;
 const SYSCALL_EXIT: usize = 93;
 const SYSCALL_SLEEP: usize = 101;
+const SYSCALL_SETITIMER: usize = 103;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
+const SYSCALL_CLOCK_NANOSLEEP: usize = 115;
 const SYSCALL_YIELD: usize = 124;
 const SYSCALL_KILL: usize = 129;
+const SYSCALL_SIGACTION: usize = 134;
+const SYSCALL_SIGRETURN: usize = 139;
+const SYSCALL_TIMES: usize = 153;
+const SYSCALL_UMASK: usize = 166;
 const SYSCALL_GET_TIME: usize = 169;
 const SYSCALL_GETPID: usize = 172;
+const SYSCALL_GETPPID: usize = 173;
+const SYSCALL_SYSINFO: usize = 179;
+const SYSCALL_MUNMAP: usize = 215;
 const SYSCALL_FORK: usize = 220;
 const SYSCALL_EXEC: usize = 221;
+const SYSCALL_MMAP: usize = 222;
+const SYSCALL_MPROTECT: usize = 226;
+const SYSCALL_MADVISE: usize = 233;
 const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_GETRANDOM: usize = 278;
+const SYSCALL_BACKTRACE: usize = 500;
+const SYSCALL_LISTTASKS: usize = 501;
+const SYSCALL_YIELD_TO: usize = 502;
+const SYSCALL_FSCACHESTATS: usize = 503;
+const SYSCALL_TIME: usize = 504;
+const SYSCALL_SET_TIME_SLICE: usize = 505;
+const SYSCALL_SHUTDOWN: usize = 506;
+const SYSCALL_REBOOT: usize = 507;
+const SYSCALL_SET_STACK_ASLR: usize = 508;
+const SYSCALL_FORK_EXEC: usize = 509;
+const SYSCALL_WAITALL: usize = 510;
 const SYSCALL_THREAD_CREATE: usize = 1000;
 const SYSCALL_GETTID: usize = 1001;
 const SYSCALL_WAITTID: usize = 1002;
+const SYSCALL_CLONE: usize = 1003;
 const SYSCALL_MUTEX_CREATE: usize = 1010;
 const SYSCALL_MUTEX_LOCK: usize = 1011;
 const SYSCALL_MUTEX_UNLOCK: usize = 1012;
@@ -25,6 +68,8 @@ const SYSCALL_SEMAPHORE_DOWN: usize = 1022;
 const SYSCALL_CONDVAR_CREATE: usize = 1030;
 const SYSCALL_CONDVAR_SIGNAL: usize = 1031;
 const SYSCALL_CONDVAR_WAIT: usize = 1032;
+const SYSCALL_MQ_SEND: usize = 1040;
+const SYSCALL_MQ_RECV: usize = 1041;
 const SYSCALL_FRAMEBUFFER: usize = 2000;
 const SYSCALL_FRAMEBUFFER_FLUSH: usize = 2001;
 const SYSCALL_EVENT_GET: usize = 3000;
@@ -44,12 +89,62 @@ fn syscall(id: usize, args: [usize; 3]) -> isize {
     ret
 }
 
+/// `syscall` with a fourth argument in a3.
+fn syscall4(id: usize, args: [usize; 4]) -> isize {
+    let mut ret: isize;
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("x10") args[0] => ret,
+            in("x11") args[1],
+            in("x12") args[2],
+            in("x13") args[3],
+            in("x17") id
+        );
+    }
+    ret
+}
+
 pub fn sys_dup(fd: usize) -> isize {
     syscall(SYSCALL_DUP, [fd, 0, 0])
 }
 
-pub fn sys_open(path: &str, flags: u32) -> isize {
-    syscall(SYSCALL_OPEN, [path.as_ptr() as usize, flags as usize, 0])
+pub fn sys_getcwd(buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_GETCWD, [buf.as_mut_ptr() as usize, buf.len(), 0])
+}
+
+pub fn sys_dup2(old_fd: usize, new_fd: usize) -> isize {
+    syscall(SYSCALL_DUP2, [old_fd, new_fd, 0])
+}
+
+pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    syscall(SYSCALL_FCNTL, [fd, cmd, arg])
+}
+
+pub fn sys_ioctl(fd: usize, request: usize, arg: usize) -> isize {
+    syscall(SYSCALL_IOCTL, [fd, request, arg])
+}
+
+pub fn sys_linkat(old_path: &str, new_path: &str) -> isize {
+    syscall(
+        SYSCALL_LINKAT,
+        [old_path.as_ptr() as usize, new_path.as_ptr() as usize, 0],
+    )
+}
+
+pub fn sys_unlinkat(path: &str) -> isize {
+    syscall(SYSCALL_UNLINKAT, [path.as_ptr() as usize, 0, 0])
+}
+
+pub fn sys_faccessat(path: &str, mode: u32) -> isize {
+    syscall(SYSCALL_FACCESSAT, [path.as_ptr() as usize, mode as usize, 0])
+}
+
+pub fn sys_openat(dirfd: isize, path: &str, flags: u32) -> isize {
+    syscall(
+        SYSCALL_OPENAT,
+        [dirfd as usize, path.as_ptr() as usize, flags as usize],
+    )
 }
 
 pub fn sys_close(fd: usize) -> isize {
@@ -71,6 +166,45 @@ pub fn sys_write(fd: usize, buffer: &[u8]) -> isize {
     syscall(SYSCALL_WRITE, [fd, buffer.as_ptr() as usize, buffer.len()])
 }
 
+pub fn sys_poll(fds: &mut [PollFd], timeout: isize) -> isize {
+    syscall(
+        SYSCALL_POLL,
+        [fds.as_mut_ptr() as usize, fds.len(), timeout as usize],
+    )
+}
+
+pub fn sys_fscachestats(stats: &mut CacheStats) -> isize {
+    syscall(SYSCALL_FSCACHESTATS, [stats as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_fstat(fd: usize, st: &mut Stat) -> isize {
+    syscall(SYSCALL_FSTAT, [fd, st as *mut _ as usize, 0])
+}
+
+pub fn sys_ftruncate(fd: usize, len: usize) -> isize {
+    syscall(SYSCALL_FTRUNCATE, [fd, len, 0])
+}
+
+pub fn sys_sync() -> isize {
+    syscall(SYSCALL_SYNC, [0, 0, 0])
+}
+
+pub fn sys_fsync(fd: usize) -> isize {
+    syscall(SYSCALL_FSYNC, [fd, 0, 0])
+}
+
+pub fn sys_stat(path: &str, st: &mut Stat) -> isize {
+    syscall(SYSCALL_STAT, [path.as_ptr() as usize, st as *mut _ as usize, 0])
+}
+
+pub fn sys_chmod(path: &str, mode: u32) -> isize {
+    syscall(SYSCALL_CHMOD, [path.as_ptr() as usize, mode as usize, 0])
+}
+
+pub fn sys_umask(mask: u32) -> isize {
+    syscall(SYSCALL_UMASK, [mask as usize, 0, 0])
+}
+
 pub fn sys_exit(exit_code: i32) -> ! {
     syscall(SYSCALL_EXIT, [exit_code as usize, 0, 0]);
     panic!("sys_exit never returns!");
@@ -84,18 +218,81 @@ pub fn sys_yield() -> isize {
     syscall(SYSCALL_YIELD, [0, 0, 0])
 }
 
+pub fn sys_yield_to(pid: usize) -> isize {
+    syscall(SYSCALL_YIELD_TO, [pid, 0, 0])
+}
+
 pub fn sys_kill(pid: usize, signal: i32) -> isize {
     syscall(SYSCALL_KILL, [pid, signal as usize, 0])
 }
 
+pub fn sys_pause() -> isize {
+    syscall(SYSCALL_PAUSE, [0, 0, 0])
+}
+
+pub fn sys_setitimer(delay_ms: usize, interval_ms: usize) -> isize {
+    syscall(SYSCALL_SETITIMER, [delay_ms, interval_ms, 0])
+}
+
+pub fn sys_sigaction(signum: usize, handler: usize) -> isize {
+    syscall(SYSCALL_SIGACTION, [signum, handler, 0])
+}
+
+pub fn sys_sigreturn() -> isize {
+    syscall(SYSCALL_SIGRETURN, [0, 0, 0])
+}
+
 pub fn sys_get_time() -> isize {
     syscall(SYSCALL_GET_TIME, [0, 0, 0])
 }
 
+pub fn sys_time() -> isize {
+    syscall(SYSCALL_TIME, [0, 0, 0])
+}
+
+pub fn sys_set_time_slice(ticks: usize) -> isize {
+    syscall(SYSCALL_SET_TIME_SLICE, [ticks, 0, 0])
+}
+
+pub fn sys_shutdown(exit_code: usize) -> isize {
+    syscall(SYSCALL_SHUTDOWN, [exit_code, 0, 0])
+}
+
+pub fn sys_reboot() -> isize {
+    syscall(SYSCALL_REBOOT, [0, 0, 0])
+}
+
+pub fn sys_set_stack_aslr(enabled: usize) -> isize {
+    syscall(SYSCALL_SET_STACK_ASLR, [enabled, 0, 0])
+}
+
+pub fn sys_clock_gettime(clock_id: usize, ts: &mut TimeSpec) -> isize {
+    syscall(SYSCALL_CLOCK_GETTIME, [clock_id, ts as *mut _ as usize, 0])
+}
+
+pub fn sys_clock_nanosleep(clock_id: usize, flags: usize, req: &TimeSpec) -> isize {
+    syscall(
+        SYSCALL_CLOCK_NANOSLEEP,
+        [clock_id, flags, req as *const _ as usize],
+    )
+}
+
 pub fn sys_getpid() -> isize {
     syscall(SYSCALL_GETPID, [0, 0, 0])
 }
 
+pub fn sys_getppid() -> isize {
+    syscall(SYSCALL_GETPPID, [0, 0, 0])
+}
+
+pub fn sys_times(tms: &mut Tms) -> isize {
+    syscall(SYSCALL_TIMES, [tms as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_sysinfo(info: &mut SysInfo) -> isize {
+    syscall(SYSCALL_SYSINFO, [info as *mut _ as usize, 0, 0])
+}
+
 pub fn sys_fork() -> isize {
     syscall(SYSCALL_FORK, [0, 0, 0])
 }
@@ -107,14 +304,60 @@ pub fn sys_exec(path: &str, args: &[*const u8]) -> isize {
     )
 }
 
+pub fn sys_fork_exec(path: &str, args: &[*const u8]) -> isize {
+    syscall(
+        SYSCALL_FORK_EXEC,
+        [path.as_ptr() as usize, args.as_ptr() as usize, 0],
+    )
+}
+
+pub fn sys_waitall(codes: &mut [i32]) -> isize {
+    syscall(
+        SYSCALL_WAITALL,
+        [codes.as_mut_ptr() as usize, codes.len(), 0],
+    )
+}
+
+pub fn sys_mmap(start: usize, len: usize, prot: usize, flags: usize) -> isize {
+    syscall4(SYSCALL_MMAP, [start, len, prot, flags])
+}
+
+pub fn sys_munmap(start: usize, len: usize) -> isize {
+    syscall(SYSCALL_MUNMAP, [start, len, 0])
+}
+
+pub fn sys_mprotect(start: usize, len: usize, prot: usize) -> isize {
+    syscall(SYSCALL_MPROTECT, [start, len, prot])
+}
+
+pub fn sys_madvise(start: usize, len: usize, advice: usize) -> isize {
+    syscall(SYSCALL_MADVISE, [start, len, advice])
+}
+
+pub fn sys_getrandom(buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_GETRANDOM, [buf.as_mut_ptr() as usize, buf.len(), 0])
+}
+
 pub fn sys_waitpid(pid: isize, exit_code: *mut i32) -> isize {
     syscall(SYSCALL_WAITPID, [pid as usize, exit_code as usize, 0])
 }
 
+pub fn sys_backtrace(pid: usize, buf: &mut [usize]) -> isize {
+    syscall(SYSCALL_BACKTRACE, [pid, buf.as_mut_ptr() as usize, buf.len()])
+}
+
+pub fn sys_listtasks(buf: &mut [TaskInfo]) -> isize {
+    syscall(SYSCALL_LISTTASKS, [buf.as_mut_ptr() as usize, buf.len(), 0])
+}
+
 pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
     syscall(SYSCALL_THREAD_CREATE, [entry, arg, 0])
 }
 
+pub fn sys_clone(entry: usize, stack: usize, arg: usize) -> isize {
+    syscall(SYSCALL_CLONE, [entry, stack, arg])
+}
+
 pub fn sys_gettid() -> isize {
     syscall(SYSCALL_GETTID, [0; 3])
 }
@@ -159,6 +402,14 @@ pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
     syscall(SYSCALL_CONDVAR_WAIT, [condvar_id, mutex_id, 0])
 }
 
+pub fn sys_mq_send(id: usize, buf: &[u8]) -> isize {
+    syscall(SYSCALL_MQ_SEND, [id, buf.as_ptr() as usize, buf.len()])
+}
+
+pub fn sys_mq_recv(id: usize, buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_MQ_RECV, [id, buf.as_mut_ptr() as usize, buf.len()])
+}
+
 
 pub fn sys_framebuffer() -> isize {
     syscall(SYSCALL_FRAMEBUFFER, [0, 0, 0])
@@ -6,25 +6,86 @@ pub fn exit(exit_code: i32) -> ! {
 pub fn yield_() -> isize {
     sys_yield()
 }
+pub fn yield_to(pid: usize) -> isize {
+    sys_yield_to(pid)
+}
+/// Power off with `exit_code` as the exit code of qemu. Only the initial
+/// process may, unless the kernel is a debug build; returns -EPERM otherwise.
+pub fn shutdown(exit_code: usize) -> isize {
+    sys_shutdown(exit_code)
+}
+/// Boot again, with the same permission check as `shutdown`.
+pub fn reboot() -> isize {
+    sys_reboot()
+}
+/// Start the stack of the programs exec'd from now on at a random page
+/// offset if `enabled`, return 1 if it was on before, else 0. Needs the
+/// same permission as `shutdown`; returns -EPERM otherwise.
+pub fn set_stack_aslr(enabled: bool) -> isize {
+    sys_set_stack_aslr(enabled as usize)
+}
+/// Run `ticks` timer ticks before being preempted, 1 by default.
+pub fn set_time_slice(ticks: usize) -> isize {
+    sys_set_time_slice(ticks)
+}
 pub fn get_time() -> isize {
     sys_get_time()
 }
+/// Seconds since the Unix epoch, or since boot if the machine has no RTC.
+pub fn time() -> isize {
+    sys_time()
+}
+
+pub const CLOCK_MONOTONIC: usize = 1;
+
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
+pub fn clock_gettime(clock_id: usize, ts: &mut TimeSpec) -> isize {
+    sys_clock_gettime(clock_id, ts)
+}
+/// `flags` bit of `clock_nanosleep`: `req` is a deadline, not a duration.
+pub const TIMER_ABSTIME: usize = 1;
+/// Sleep until the clock reaches `req`, or for `req` without `TIMER_ABSTIME`.
+pub fn clock_nanosleep(clock_id: usize, flags: usize, req: &TimeSpec) -> isize {
+    sys_clock_nanosleep(clock_id, flags, req)
+}
+pub fn get_time_ns() -> usize {
+    let mut ts = TimeSpec::default();
+    clock_gettime(CLOCK_MONOTONIC, &mut ts);
+    ts.sec * 1_000_000_000 + ts.nsec
+}
+pub fn get_time_us() -> usize {
+    get_time_ns() / 1000
+}
 pub fn getpid() -> isize {
     sys_getpid()
 }
+pub fn getppid() -> isize {
+    sys_getppid()
+}
 pub fn fork() -> isize {
     sys_fork()
 }
 pub fn exec(path: &str, args: &[*const u8]) -> isize {
     sys_exec(path, args)
 }
+/// Run the program `path` in a new child and return its pid, as `fork`
+/// and then `exec` in the child would, without copying this address space.
+pub fn fork_exec(path: &str, args: &[*const u8]) -> isize {
+    sys_fork_exec(path, args)
+}
 pub fn wait(exit_code: &mut i32) -> isize {
     loop {
         match sys_waitpid(-1, exit_code as *mut _) {
-            -2 => {
+            ret if ret == Errno::EAGAIN.ret() => {
                 yield_();
             }
-            // -1 or a real pid
+            // -ECHILD or a real pid
             exit_pid => return exit_pid,
         }
     }
@@ -33,10 +94,23 @@ pub fn wait(exit_code: &mut i32) -> isize {
 pub fn waitpid(pid: usize, exit_code: &mut i32) -> isize {
     loop {
         match sys_waitpid(pid as isize, exit_code as *mut _) {
-            -2 => {
+            ret if ret == Errno::EAGAIN.ret() => {
                 yield_();
             }
-            // -1 or a real pid
+            // -ECHILD or a real pid
+            exit_pid => return exit_pid,
+        }
+    }
+}
+
+/// Like `waitpid`, but the exit code of the child is dropped.
+pub fn waitpid_nostatus(pid: usize) -> isize {
+    loop {
+        match sys_waitpid(pid as isize, core::ptr::null_mut()) {
+            ret if ret == Errno::EAGAIN.ret() => {
+                yield_();
+            }
+            // -ECHILD or a real pid
             exit_pid => return exit_pid,
         }
     }
@@ -46,36 +120,163 @@ pub fn waitpid_nb(pid: usize, exit_code: &mut i32) -> isize {
     sys_waitpid(pid as isize, exit_code as *mut _)
 }
 
+/// Reap every child that has exited, up to `codes.len()`, without blocking.
+/// Return how many were reaped, their exit codes fill the front of `codes`.
+pub fn waitall(codes: &mut [i32]) -> isize {
+    sys_waitall(codes)
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct SysInfo {
+    pub total_frames: usize,
+    pub free_frames: usize,
+    pub num_tasks: usize,
+    /// frames allocated since boot
+    pub frames_allocated: usize,
+}
+
+pub fn sysinfo(info: &mut SysInfo) -> isize {
+    sys_sysinfo(info)
+}
+
+/// CPU time in us, `c*` count the reaped children.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Tms {
+    pub utime: usize,
+    pub stime: usize,
+    pub cutime: usize,
+    pub cstime: usize,
+}
+
+pub fn times(tms: &mut Tms) -> isize {
+    sys_times(tms)
+}
+
+/// Fill `buf` with pseudo-random bytes, not suitable for cryptography.
+pub fn getrandom(buf: &mut [u8]) -> isize {
+    sys_getrandom(buf)
+}
+
+pub const PROT_READ: usize = 1;
+pub const PROT_WRITE: usize = 2;
+pub const PROT_EXEC: usize = 4;
+
+/// `mmap` flags: writes are seen by forked children, or copied by fork.
+pub const MAP_SHARED: usize = 0x01;
+pub const MAP_PRIVATE: usize = 0x02;
+
+/// Map zeroed memory at `start`, or where the kernel likes if `start` is 0.
+/// Return the address or a negative errno.
+pub fn mmap(start: usize, len: usize, prot: usize) -> isize {
+    sys_mmap(start, len, prot, MAP_PRIVATE)
+}
+/// Like `mmap`, but the memory stays shared with the children forked later.
+pub fn mmap_shared(start: usize, len: usize, prot: usize) -> isize {
+    sys_mmap(start, len, prot, MAP_SHARED)
+}
+pub fn munmap(start: usize, len: usize) -> isize {
+    sys_munmap(start, len)
+}
+pub fn mprotect(start: usize, len: usize, prot: usize) -> isize {
+    sys_mprotect(start, len, prot)
+}
+
+/// `madvise` advice: free the frames, the pages read as zero afterwards.
+pub const MADV_DONTNEED: usize = 4;
+
+pub fn madvise(start: usize, len: usize, advice: usize) -> isize {
+    sys_madvise(start, len, advice)
+}
+
 bitflags! {
     pub struct SignalFlags: i32 {
         const SIGINT    = 1 << 2;
         const SIGILL    = 1 << 4;
         const SIGABRT   = 1 << 6;
+        const SIGBUS    = 1 << 7;
         const SIGFPE    = 1 << 8;
         const SIGSEGV   = 1 << 11;
+        const SIGALRM   = 1 << 14;
+        const SIGCONT   = 1 << 18;
+        const SIGSTOP   = 1 << 19;
     }
 }
 
+pub const SIGALRM: usize = 14;
+
 pub fn kill(pid: usize, signal: i32) -> isize {
     sys_kill(pid, signal)
 }
 
-pub fn sleep(sleep_ms: usize) {
-    sys_sleep(sleep_ms);
+/// Wait for a signal, always returns `-EINTR`.
+pub fn pause() -> isize {
+    sys_pause()
+}
+
+/// Return addresses on the stack of process `pid`, which must be the caller
+/// or a child of it, else -EPERM.
+pub fn backtrace(pid: usize, buf: &mut [usize]) -> isize {
+    sys_backtrace(pid, buf)
+}
+
+/// Values of `TaskInfo::status`.
+pub const TASK_READY: usize = 0;
+pub const TASK_RUNNING: usize = 1;
+pub const TASK_BLOCKING: usize = 2;
+pub const TASK_STOPPED: usize = 3;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TaskInfo {
+    pub pid: usize,
+    pub tid: usize,
+    pub status: usize,
+    pub user_time_us: usize,
+    pub kernel_time_us: usize,
+    pub minor_faults: usize,
+    pub major_faults: usize,
+}
+
+/// Fill `buf` with the live threads, return how many there are,
+/// which may be more than `buf.len()`.
+pub fn list_tasks(buf: &mut [TaskInfo]) -> isize {
+    sys_listtasks(buf)
+}
+
+/// Returns the milliseconds left if the sleep ended early, 0 otherwise.
+pub fn sleep(sleep_ms: usize) -> isize {
+    sys_sleep(sleep_ms)
+}
+
+pub fn setitimer(delay_ms: usize, interval_ms: usize) -> isize {
+    sys_setitimer(delay_ms, interval_ms)
+}
+pub fn sigaction(signum: usize, handler: usize) -> isize {
+    sys_sigaction(signum, handler)
+}
+pub fn sigreturn() -> isize {
+    sys_sigreturn()
 }
 
 pub fn thread_create(entry: usize, arg: usize) -> isize {
     sys_thread_create(entry, arg)
 }
+/// Start a thread at `entry` with its sp set to `stack` (0 for a fresh stack).
+pub fn clone(entry: usize, stack: usize, arg: usize) -> isize {
+    sys_clone(entry, stack, arg)
+}
 pub fn gettid() -> isize {
     sys_gettid()
 }
 pub fn waittid(tid: usize) -> isize {
     loop {
         match sys_waittid(tid) {
-            -2 => {
+            ret if ret == Errno::EAGAIN.ret() => {
                 yield_();
             }
+            // -EDEADLK, -ESRCH or the exit code
             exit_code => return exit_code,
         }
     }